@@ -0,0 +1,137 @@
+use crate::nds::{
+    bus::BusTrait,
+    gpus::framebuffer::{ColourOutput, Framebuffer},
+    logger,
+    spu::{
+        self,
+        output::{AudioSink, NullSink, WavWriter},
+    },
+    Emulator,
+};
+
+// what the ARM9 runs in one frame at 60fps
+const CYCLES_PER_FRAME: u64 = 66_000_000 / 60;
+
+const USAGE: &str = "Usage: NitrousDS <rom> [frames] [--arm9-bios <path>] [--arm7-bios <path>] [--firmware <path>] [--firmware-boot] [--wav <path>] [--screenshot <path>]";
+
+// runs a ROM with no window, for as many frames as asked or until it stops
+// the audio can be recorded to a WAV file, and the last frame saved as a PPM image
+pub fn run(mut emulator: Emulator) {
+    let mut rom_path = None;
+    let mut frames = None;
+    let mut wav_path = None;
+    let mut screenshot_path = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--arm9-bios" | "--arm7-bios" | "--firmware" | "--wav" | "--screenshot" => {
+                let Some(path) = args.next() else {
+                    error!("{}", USAGE);
+                    return;
+                };
+                match arg.as_str() {
+                    "--arm9-bios" => emulator.bus9.load_bios_from_path(&path),
+                    "--arm7-bios" => emulator.bus7.load_bios_from_path(&path),
+                    "--firmware" => emulator.bus7.load_firmware_from_path(&path),
+                    "--wav" => wav_path = Some(path),
+                    _ => screenshot_path = Some(path),
+                }
+            }
+            "--firmware-boot" => emulator.firmware_boot = true,
+            _ if rom_path.is_none() => rom_path = Some(arg),
+            _ => match arg.parse::<u64>() {
+                Ok(count) if frames.is_none() => frames = Some(count),
+                _ => {
+                    error!("{}", USAGE);
+                    return;
+                }
+            },
+        }
+    }
+
+    let Some(rom_path) = rom_path else {
+        error!("{}", USAGE);
+        return;
+    };
+    if emulator.firmware_boot && emulator.bus7.firmware.is_empty() {
+        error!("Booting through the firmware needs one, pass it with --firmware");
+        return;
+    }
+    let rom = match std::fs::read(&rom_path) {
+        Ok(rom) => rom,
+        Err(e) => {
+            error!("Couldn't read {}: {}", rom_path, e);
+            return;
+        }
+    };
+    // the SPU's mix goes in untouched, so a recording is the same every run
+    let sample_rate = spu::SAMPLE_RATE.round() as u32;
+    let mut sink: Box<dyn AudioSink> = match wav_path {
+        Some(path) => match WavWriter::create(&path, sample_rate) {
+            Ok(writer) => {
+                logger::info(
+                    logger::LogSource::Emu,
+                    format!("Recording audio to {} at {}Hz", path, writer.sample_rate()),
+                );
+                Box::new(writer)
+            }
+            Err(e) => {
+                error!("Couldn't create {}: {}", path, e);
+                return;
+            }
+        },
+        None => Box::new(NullSink),
+    };
+    let mut samples = Vec::new();
+
+    emulator.load_rom(rom, Some(rom_path));
+    emulator.start();
+
+    let mut frame = 0;
+    let mut arm7_discrepency = 0;
+    while emulator.is_running() && frames.is_none_or(|frames| frame < frames) {
+        let (cycles_ran_arm9, cycles_ran_arm7, _) =
+            emulator.run_for(CYCLES_PER_FRAME, arm7_discrepency, &mut ());
+        arm7_discrepency = cycles_ran_arm7 - (cycles_ran_arm9 / 2) as i32;
+        frame += 1;
+
+        samples.clear();
+        samples.extend(emulator.bus7.spu.drain_samples());
+        sink.write(&samples);
+    }
+
+    emulator.pause();
+    logger::info(
+        logger::LogSource::Emu,
+        format!("Stopped after {} frames", frame),
+    );
+
+    if let Some(path) = screenshot_path {
+        match save_screenshot(&emulator, &path) {
+            Ok(_) => logger::info(
+                logger::LogSource::Emu,
+                format!("Saved the last frame to {}", path),
+            ),
+            Err(e) => error!("Couldn't save the last frame to {}: {}", path, e),
+        }
+    }
+}
+
+// both screens on top of each other, the same way round as the DS
+fn save_screenshot(emulator: &Emulator, path: &str) -> std::io::Result<()> {
+    let gpus = &emulator.shared.gpus;
+    let powcnt1 = &emulator.shared.powcnt1;
+
+    let mut image = format!(
+        "P6\n{} {}\n255\n",
+        Framebuffer::WIDTH,
+        Framebuffer::HEIGHT * 2
+    )
+    .into_bytes();
+    for screen in [gpus.top_screen(powcnt1), gpus.bottom_screen(powcnt1)] {
+        let rgba = screen.to_rgba(ColourOutput::default());
+        image.extend(rgba.chunks_exact(4).flat_map(|pixel| &pixel[..3]));
+    }
+    std::fs::write(path, image)
+}
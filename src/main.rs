@@ -3,7 +3,10 @@ use crate::nds::logger;
 #[macro_use]
 extern crate log;
 
+#[cfg(not(feature = "ui"))]
+mod headless;
 mod nds;
+#[cfg(feature = "ui")]
 mod ui;
 
 fn main() {
@@ -17,11 +20,17 @@ fn main() {
 
     let emulator = nds::Emulator::default();
 
-    info!("Initializing UI");
-    let ui_result = ui::init(emulator);
-    if ui_result.is_err() {
-        error!("Error initializing UI: {:?}", ui_result.err().unwrap());
+    #[cfg(feature = "ui")]
+    {
+        info!("Initializing UI");
+        let ui_result = ui::init(emulator);
+        if ui_result.is_err() {
+            error!("Error initializing UI: {:?}", ui_result.err().unwrap());
+        }
     }
+
+    #[cfg(not(feature = "ui"))]
+    headless::run(emulator);
 }
 
 #[cfg(not(target_arch = "wasm32"))]
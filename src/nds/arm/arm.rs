@@ -11,6 +11,8 @@ pub struct Arm<Bus: BusTrait> {
     _phantom: std::marker::PhantomData<Bus>,
 
     pub halted: bool,
+    #[serde(default)]
+    pub stalled_cycles: u32, // the bus is busy with something else, like a DMA
 
     // R13: Stack Pointer
     // R14: Link Register
@@ -48,6 +50,7 @@ impl<Bus: BusTrait> Default for Arm<Bus> {
             _phantom: std::marker::PhantomData,
 
             halted: false,
+            stalled_cycles: 0,
 
             r: Registers::new_with_sp(sp),
             r_fiq: [0, 0, 0, 0, 0, 0, 0, 0],
@@ -68,6 +71,13 @@ impl<Bus: BusTrait> Default for Arm<Bus> {
 
 impl<Bus: BusTrait> Arm<Bus> {
     pub fn clock(&mut self, bus: &mut Bus, shared: &mut Shared, dma: &mut Dma) -> u32 {
+        if self.stalled_cycles != 0 {
+            return std::mem::take(&mut self.stalled_cycles);
+        }
+        if Bus::KIND == ArmKind::Arm9 && shared.gpus.gpu3d.is_stalling_cpu() {
+            return 1; // waiting on the GX FIFO
+        }
+
         if self.halted {
             if !self.cpsr().get_irq_interrupt() && bus.is_requesting_interrupt() {
                 self.handle_irq();
@@ -156,6 +166,14 @@ impl<Bus: BusTrait> Arm<Bus> {
         cycles
     }
 
+    // bus_cycles is in 33MHz cycles, the ARM9 runs twice as fast
+    pub fn stall(&mut self, bus_cycles: u32) {
+        self.stalled_cycles += match Bus::KIND {
+            ArmKind::Arm9 => bus_cycles * 2,
+            ArmKind::Arm7 => bus_cycles,
+        };
+    }
+
     fn handle_irq(&mut self) {
         self.set_mode_r(ProcessorMode::IRQ, 1, self.r[15] + 4);
         self.switch_mode::<false>(ProcessorMode::IRQ, true);
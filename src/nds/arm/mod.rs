@@ -1,5 +1,6 @@
 #[allow(clippy::module_inception)]
 mod arm; // this is intentional shut up
+#[cfg(feature = "ui")]
 mod fake;
 pub mod instructions;
 pub mod models;
@@ -7,6 +8,7 @@ mod rw;
 mod t;
 
 pub use arm::Arm;
+#[cfg(feature = "ui")]
 pub use fake::FakeArm;
 pub use models::{ArmBool, ArmKind};
 pub use rw::ArmInternalRW;
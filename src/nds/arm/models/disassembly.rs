@@ -1,9 +1,11 @@
+#[cfg(feature = "ui")]
 pub struct Chunk {
     pub kind: ChunkKind,
     pub value: String,
     pub raw: u32,
 }
 
+#[cfg(feature = "ui")]
 #[derive(PartialEq)]
 pub enum ChunkKind {
     Register,
@@ -12,6 +14,7 @@ pub enum ChunkKind {
     Punctuation,
 }
 
+#[cfg(feature = "ui")]
 impl Chunk {
     pub fn new(kind: ChunkKind, value: String, raw: u32) -> Self {
         Self { kind, value, raw }
@@ -34,6 +37,7 @@ pub trait DisassemblyTrait {
     fn push_str_end_arg(&mut self, arg: &str, prefix: Option<&str>);
 }
 
+#[cfg(feature = "ui")]
 pub struct Disassembly {
     pub cond: Option<[char; 2]>,
     pub inst: String,
@@ -42,6 +46,7 @@ pub struct Disassembly {
     pub end_args: Vec<Chunk>,
 }
 
+#[cfg(feature = "ui")]
 impl DisassemblyTrait for Disassembly {
     fn is_real(&self) -> bool {
         true
@@ -146,6 +151,7 @@ impl DisassemblyTrait for Disassembly {
 //     }
 // }
 
+#[cfg(feature = "ui")]
 impl Default for Disassembly {
     fn default() -> Self {
         Self {
@@ -7,6 +7,7 @@ impl Registers {
         Self([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, sp, 0, 0])
     }
 
+    #[cfg(feature = "ui")]
     pub fn new_with_pc(r15: u32) -> Self {
         Self([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x803EC0, 0, r15])
     }
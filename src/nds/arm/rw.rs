@@ -3,6 +3,7 @@ use crate::nds::{bus::BusTrait, dma::Dma, logger, shared::Shared};
 use super::{models::PowerDownMode, Arm, ArmKind, ArmTrait};
 
 pub trait ArmInternalRW<Bus: BusTrait> {
+    #[cfg(feature = "ui")]
     fn read_bulk(
         &self,
         bus: &mut Bus,
@@ -37,6 +38,7 @@ pub trait ArmInternalRW<Bus: BusTrait> {
 }
 
 impl<Bus: BusTrait> ArmInternalRW<Bus> for Arm<Bus> {
+    #[cfg(feature = "ui")]
     fn read_bulk(
         &self,
         bus: &mut Bus,
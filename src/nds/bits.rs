@@ -1,7 +1,9 @@
 // NOTE: do NOT use these for generics
 // i don't know why but it ruins the generic magic
 
-use num_traits::{PrimInt, WrappingSub};
+use num_traits::PrimInt;
+#[cfg(feature = "ui")]
+use num_traits::WrappingSub;
 
 pub trait Bits<T> {
     fn get_bit(&self, offset: Self) -> bool;
@@ -74,6 +76,7 @@ where
 pub trait Bytes {
     fn into_word(self) -> u32;
     fn into_halfword(self) -> u16;
+    #[allow(dead_code)]
     fn into_byte(self) -> u8;
 }
 
@@ -116,10 +119,12 @@ impl<const T: usize> Bytes for [u8; T] {
     }
 }
 
+#[cfg(feature = "ui")]
 pub trait IfElse<T> {
     fn if_else(&self, true_val: T, false_val: T) -> T;
 }
 
+#[cfg(feature = "ui")]
 impl<T> IfElse<T> for bool
 where
     T: PrimInt + WrappingSub,
@@ -3,8 +3,10 @@ use crate::nds::{
     dma::Dma,
     interrupts::Interrupts,
     logger::{self, format_debug, Logger, LoggerTrait},
+    rtc::Rtc,
     shared::Shared,
     spi::Spi,
+    spu::Spu,
     timers::Timers,
     Bits, Bytes,
 };
@@ -21,7 +23,9 @@ pub struct Bus7 {
     pub firmware: Vec<u8>,
     pub interrupts: Interrupts,
 
+    pub rtc: Rtc,
     pub spi: Spi,
+    pub spu: Spu,
     pub timers: Timers,
     pub wram7: Vec<u8>, // 64kb
 
@@ -37,7 +41,9 @@ impl Default for Bus7 {
             firmware: Vec::new(),
             interrupts: Interrupts::default(),
 
+            rtc: Rtc::default(),
             spi: Spi::default(),
+            spu: Spu::default(),
             timers: Timers::default(),
             wram7: vec![0; 1024 * 64],
 
@@ -51,12 +57,16 @@ impl BusTrait for Bus7 {
 
     fn reset(&mut self) {
         self.interrupts = Interrupts::default();
+        self.rtc.reset();
+        self.spu = Spu::default();
         self.timers = Timers::default();
         self.wram7 = vec![0; 1024 * 64];
     }
 
     fn load_state(&mut self, bus: Self) {
         self.interrupts = bus.interrupts;
+        self.rtc.load_state(bus.rtc);
+        self.spu = bus.spu;
         self.timers = bus.timers;
         self.wram7 = bus.wram7;
     }
@@ -188,20 +198,13 @@ impl BusTrait for Bus7 {
             }
             0x04000134..=0x04000135 => self.rcnt.to_bytes::<T>(),
             0x04000136..=0x04000137 => shared.extkeyin.value().to_bytes::<T>(),
-            0x04000138 => {
-                self.logger.log_warn_once(format_debug!(
-                    "RTC not implemented (R{} {:#010X})",
-                    T,
-                    addr
-                ));
-                bytes
-            }
+            0x04000138..=0x04000139 => (self.rtc.read() as u16).to_bytes::<T>(),
 
             0x04000180..=0x04000183 => shared.ipcsync.value::<false>().to_bytes::<T>(),
             0x04000184..=0x04000187 => shared.ipcfifo.get_cnt::<false>().to_bytes::<T>(),
 
             0x040001A0..=0x040001A1 => shared.cart.auxspicnt.value().to_bytes::<T>(),
-            0x040001A2..=0x040001A3 => bytes, // TODO: I NEED THIS
+            0x040001A2..=0x040001A3 => (shared.cart.backup.output as u16).to_bytes::<T>(),
             0x040001A4..=0x040001A7 => shared.cart.romctrl.value().to_bytes::<T>(),
 
             0x040001C0..=0x040001C1 => self.spi.cnt.value().to_bytes::<T>(),
@@ -215,19 +218,13 @@ impl BusTrait for Bus7 {
             0x04000300 => shared.postflg.0.to_bytes::<T>(),
             0x04000304..=0x04000307 => shared.powcnt1.value().to_bytes::<T>(),
 
-            0x04000400..=0x0400051F => {
-                self.logger.log_warn_once(format_debug!(
-                    "Sound channels not implemented (R{} {:#010X})",
-                    T,
-                    addr
-                ));
-                bytes
-            }
+            0x04000400..=0x0400051F => self.spu.read_slice::<T>(addr),
 
             0x04004008..=0x0400400B => bytes, // DSi Stuff, return nothing
             0x04004700..=0x04004701 => bytes, // DSi Stuff, return nothing
 
             0x04100000..=0x04100003 => shared.ipcfifo.receive::<false>().to_bytes::<T>(),
+            0x04100010..=0x04100013 => shared.cart.read_bus().to_bytes::<T>(),
 
             0x08000000..=0x0AFFFFFF => bytes, // gba slot
 
@@ -301,12 +298,7 @@ impl BusTrait for Bus7 {
                 value.into_word()
             )),
             0x04000134..=0x04000135 => self.rcnt = value.into_halfword(),
-            0x04000138 => self.logger.log_warn_once(format_debug!(
-                "RTC not implemented (W{} {:#010X}:{:#010X})",
-                T,
-                addr,
-                value.into_word()
-            )),
+            0x04000138 => self.rtc.write(value[0]),
 
             0x04000180..=0x04000183 => shared.ipcsync.set::<false, T>(addr - 0x04000180, value),
             0x04000184..=0x04000187 => shared
@@ -316,14 +308,15 @@ impl BusTrait for Bus7 {
 
             0x040001A1 => shared.cart.auxspicnt.set_hi(value.into_halfword()),
             0x040001A0..=0x040001A1 => shared.cart.auxspicnt.set(value.into_halfword()),
-            0x040001A2..=0x040001A3 => {} // TODO: I NEED THIS
-            0x040001A4..=0x040001A7 => shared.cart.romctrl.set(value.into_word()),
+            0x040001A2..=0x040001A3 => shared.cart.write_auxspidata(value[0]),
+            0x040001A4..=0x040001A7 => shared.cart.write_romctrl(value.into_word()),
             0x040001A8..=0x040001AF => {
                 shared
                     .cart
                     .command
                     .update(addr - 0x040001A8, T, value.into_word())
             }
+            0x040001B0..=0x040001BB => shared.cart.write_romseed(addr - 0x040001B0, value),
 
             0x040001C0..=0x040001C1 => self.spi.cnt.set(value.into_halfword()),
             0x040001C2..=0x040001C3 => {
@@ -344,12 +337,7 @@ impl BusTrait for Bus7 {
 
             0x04000304..=0x04000307 => shared.powcnt1 = value.into_word().into(),
 
-            0x04000400..=0x0400051F => self.logger.log_warn_once(format_debug!(
-                "Sound channels not implemented (W{} {:#010X}:{:#010X})",
-                T,
-                addr,
-                value.into_word()
-            )),
+            0x04000400..=0x0400051F => self.spu.write_slice(addr, value),
 
             0x048080AE..=0x048080AF => self.logger.log_warn_once(format_debug!(
                 "WIFI not implemented (W{} {:#010X}:{:#010X})",
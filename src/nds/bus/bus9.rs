@@ -156,22 +156,18 @@ impl BusTrait for Bus9 {
             0x04000004..=0x04000005 => shared.gpus.dispstat.value().to_bytes::<T>(),
             0x04000006..=0x04000007 => shared.gpus.vcount.to_bytes::<T>(),
 
-            0x04000020..=0x0400004F => {
-                self.logger.log_warn_once(format_debug!(
-                    "GPU feature not implemented (R{} {:#010X})",
-                    T,
-                    addr
-                ));
+            0x04000020..=0x0400003F | 0x04001020..=0x0400103F => bytes, // write only
+            0x04000040..=0x0400004B => {
+                let windows = &shared.gpus.a.windows;
+                bytes = std::array::from_fn(|i| windows.read_byte(addr + i - 0x04000040));
                 bytes
             }
-            0x04001020..=0x0400104F => {
-                self.logger.log_warn_once(format_debug!(
-                    "GPU feature not implemented (R{} {:#010X})",
-                    T,
-                    addr
-                ));
+            0x04001040..=0x0400104B => {
+                let windows = &shared.gpus.b.windows;
+                bytes = std::array::from_fn(|i| windows.read_byte(addr + i - 0x04001040));
                 bytes
             }
+            0x0400004C..=0x0400004F | 0x0400104C..=0x0400104F => bytes, // write only
 
             0x04000008..=0x04000009 => shared.gpus.a.bgxcnt[0].value().to_bytes::<T>(),
             0x0400000A..=0x0400000B => shared.gpus.a.bgxcnt[1].value().to_bytes::<T>(),
@@ -191,12 +187,14 @@ impl BusTrait for Bus9 {
             0x04001052 => shared.gpus.b.bldalpha[0].value().to_bytes::<T>(),
             0x04001053 => shared.gpus.b.bldalpha[1].value().to_bytes::<T>(),
 
-            0x04000060..=0x04000061 => {
-                self.logger.log_warn_once(format_debug!(
-                    "GPU3D not implemented (R{} {:#010X})",
-                    T,
-                    addr
-                ));
+            0x04000064..=0x04000067 => shared.gpus.a.dispcapcnt.value().to_bytes::<T>(),
+            0x04000068..=0x0400006B => bytes, // write only
+            0x0400006C..=0x0400006D => shared.gpus.a.master_bright.value().to_bytes::<T>(),
+            0x0400106C..=0x0400106D => shared.gpus.b.master_bright.value().to_bytes::<T>(),
+
+            0x04000060..=0x04000063 => {
+                let renderer = &shared.gpus.gpu3d.renderer;
+                bytes = std::array::from_fn(|i| renderer.read_byte(addr + i));
                 bytes
             }
 
@@ -221,6 +219,7 @@ impl BusTrait for Bus9 {
             0x04000184..=0x04000185 => shared.ipcfifo.get_cnt::<true>().to_bytes::<T>(),
 
             0x040001A0..=0x040001A1 => shared.cart.auxspicnt.value().to_bytes::<T>(),
+            0x040001A2..=0x040001A3 => (shared.cart.backup.output as u16).to_bytes::<T>(),
             0x040001A4..=0x040001A7 => shared.cart.romctrl.value().to_bytes::<T>(),
 
             0x04000204..=0x04000205 => shared.cart.exmemcnt.0.to_bytes::<T>(),
@@ -270,7 +269,20 @@ impl BusTrait for Bus9 {
             0x04000300 => shared.postflg.0.to_bytes::<T>(),
             0x04000304..=0x04000307 => shared.powcnt1.value().to_bytes::<T>(),
 
-            0x04000320..=0x040006A4 => {
+            0x04000600..=0x04000603 => {
+                (shared.gpus.gpu3d.read_gxstat() >> ((addr & 3) * 8)).to_bytes::<T>()
+            }
+            0x04000604..=0x04000607 => {
+                (shared.gpus.gpu3d.read_ram_count() >> ((addr & 3) * 8)).to_bytes::<T>()
+            }
+
+            0x04000620..=0x040006A3 => {
+                (shared.gpus.gpu3d.read_result(addr) >> ((addr & 3) * 8)).to_bytes::<T>()
+            }
+            0x04000320..=0x04000323 => 46u32.to_bytes::<T>(), // RDLINES_COUNT, we never run short of time
+            0x04000330..=0x040003BF | 0x04000610..=0x04000611 => bytes, // write only
+
+            0x04000324..=0x040006A4 => {
                 self.logger.log_warn_once(format_debug!(
                     "GPU3D not implemented (R{} {:#010X})",
                     T,
@@ -410,18 +422,56 @@ impl BusTrait for Bus9 {
                 shared.gpus.b.bgofs[3].set_part::<T>(addr as u32 - 0x0400101C, value.into_word());
             }
 
-            0x04000020..=0x0400004F => self.logger.log_warn_once(format_debug!(
-                "GPU feature not implemented (W{} {:#010X}:{:#010X})",
-                T,
-                addr,
-                value.into_word()
-            )),
-            0x04001020..=0x0400104F => self.logger.log_warn_once(format_debug!(
-                "GPU feature not implemented (W{} {:#010X}:{:#010X})",
-                T,
-                addr,
-                value.into_word()
-            )),
+            0x04000020..=0x0400003F => {
+                for (i, byte) in value.iter().enumerate() {
+                    let offset = addr + i - 0x04000020;
+                    shared.gpus.a.bg_affine[offset / 0x10].write_byte(offset % 0x10, *byte);
+                }
+            }
+            0x04001020..=0x0400103F => {
+                for (i, byte) in value.iter().enumerate() {
+                    let offset = addr + i - 0x04001020;
+                    shared.gpus.b.bg_affine[offset / 0x10].write_byte(offset % 0x10, *byte);
+                }
+            }
+            0x04000040..=0x0400004B => {
+                for (i, byte) in value.iter().enumerate() {
+                    shared
+                        .gpus
+                        .a
+                        .windows
+                        .write_byte(addr + i - 0x04000040, *byte);
+                }
+            }
+            0x04001040..=0x0400104B => {
+                for (i, byte) in value.iter().enumerate() {
+                    shared
+                        .gpus
+                        .b
+                        .windows
+                        .write_byte(addr + i - 0x04001040, *byte);
+                }
+            }
+            0x0400004C..=0x0400004F => {
+                for i in 0..T {
+                    let value = value[i] as u16;
+                    match addr + i {
+                        0x0400004C => shared.gpus.a.mosaic.0.set_bits(0, 7, value),
+                        0x0400004D => shared.gpus.a.mosaic.0.set_bits(8, 15, value),
+                        _ => {}
+                    }
+                }
+            }
+            0x0400104C..=0x0400104F => {
+                for i in 0..T {
+                    let value = value[i] as u16;
+                    match addr + i {
+                        0x0400104C => shared.gpus.b.mosaic.0.set_bits(0, 7, value),
+                        0x0400104D => shared.gpus.b.mosaic.0.set_bits(8, 15, value),
+                        _ => {}
+                    }
+                }
+            }
 
             0x04000050..=0x04000055 => {
                 for i in 0..T {
@@ -454,36 +504,36 @@ impl BusTrait for Bus9 {
 
             0x0400005C..=0x0400005F => {} // not real
 
-            0x04000060..=0x04000061 => self.logger.log_warn_once(format_debug!(
-                "GPU3D not implemented (W{} {:#010X}:{:#010X})",
-                T,
-                addr,
-                value.into_word()
-            )),
-            0x04000064..=0x04000067 => self.logger.log_warn_once(format_debug!(
-                "DISPCAPCNT not implemented (W{} {:#010X}:{:#010X})",
-                T,
-                addr,
-                value.into_word()
-            )),
-            0x04000068..=0x0400006B => self.logger.log_warn_once(format_debug!(
-                "DISP_MMEM_FIFO not implemented (W{} {:#010X}:{:#010X})",
-                T,
-                addr,
-                value.into_word()
-            )),
-            0x0400006C..=0x0400006D => self.logger.log_warn_once(format_debug!(
-                "MASTER_BRIGHT not implemented (W{} {:#010X}:{:#010X})",
-                T,
-                addr,
-                value.into_word()
-            )),
-            0x0400106C..=0x0400106D => self.logger.log_warn_once(format_debug!(
-                "MASTER_BRIGHT not implemented (W{} {:#010X}:{:#010X})",
-                T,
-                addr,
-                value.into_word()
-            )),
+            0x04000060..=0x04000063 | 0x04000330..=0x040003BF | 0x04000610..=0x04000611 => {
+                for (i, byte) in value.iter().enumerate() {
+                    shared.gpus.gpu3d.renderer.write_byte(addr + i, *byte);
+                }
+            }
+            0x04000064..=0x04000067 => {
+                let dispcapcnt = &mut shared.gpus.a.dispcapcnt.0;
+                dispcapcnt.set_part::<T>(addr as u32 - 0x04000064, value.into_word());
+            }
+            0x04000068..=0x0400006B => shared.gpus.a.write_main_memory_fifo(value.into_word()),
+            0x0400006C..=0x0400006D => {
+                for i in 0..T {
+                    let value = value[i] as u16;
+                    match addr + i {
+                        0x0400006C => shared.gpus.a.master_bright.0.set_bits(0, 7, value),
+                        0x0400006D => shared.gpus.a.master_bright.0.set_bits(8, 15, value),
+                        _ => {}
+                    }
+                }
+            }
+            0x0400106C..=0x0400106D => {
+                for i in 0..T {
+                    let value = value[i] as u16;
+                    match addr + i {
+                        0x0400106C => shared.gpus.b.master_bright.0.set_bits(0, 7, value),
+                        0x0400106D => shared.gpus.b.master_bright.0.set_bits(8, 15, value),
+                        _ => {}
+                    }
+                }
+            }
 
             0x04000100..=0x04000101 => self.timers.get_mut(0).set_l(value.into_halfword()),
             0x04000102..=0x04000103 => self.timers.get_mut(0).set_h(value.into_halfword()),
@@ -509,13 +559,15 @@ impl BusTrait for Bus9 {
 
             0x040001A1 => shared.cart.auxspicnt.set_hi(value.into_halfword()),
             0x040001A0..=0x040001A1 => shared.cart.auxspicnt.set(value.into_halfword()),
-            0x040001A4..=0x040001A7 => shared.cart.romctrl.set(value.into_word()),
+            0x040001A2..=0x040001A3 => shared.cart.write_auxspidata(value[0]),
+            0x040001A4..=0x040001A7 => shared.cart.write_romctrl(value.into_word()),
             0x040001A8..=0x040001AF => {
                 shared
                     .cart
                     .command
                     .update(addr - 0x040001A8, T, value.into_word())
             }
+            0x040001B0..=0x040001BB => shared.cart.write_romseed(addr - 0x040001B0, value),
 
             0x04000204..=0x04000205 => shared.cart.exmemcnt.0 = value.into_halfword(),
 
@@ -556,6 +608,22 @@ impl BusTrait for Bus9 {
 
             0x04000304..=0x04000307 => shared.powcnt1 = value.into_word().into(),
 
+            // the FIFO and command ports only take whole words, anything narrower would desync the FIFO
+            0x04000400..=0x040005FF if T != 4 => self.logger.log_warn_once(format!(
+                "Ignored a {} byte write to the GX port at {:#010X}",
+                T, addr
+            )),
+            0x04000400..=0x0400043F => shared.gpus.gpu3d.write_gxfifo(value.into_word()),
+            0x04000440..=0x040005FF => shared
+                .gpus
+                .gpu3d
+                .write_command_port(addr, value.into_word()),
+            0x04000600..=0x04000603 => {
+                let mut gxstat = shared.gpus.gpu3d.read_gxstat() & !(1 << 15); // don't acknowledge by accident
+                gxstat.set_part::<T>(addr as u32 - 0x04000600, value.into_word());
+                shared.gpus.gpu3d.write_gxstat(gxstat);
+            }
+
             0x04000320..=0x040006A4 => self.logger.log_warn_once(format_debug!(
                 "GPU3D not implemented (W{} {:#010X}:{:#010X})",
                 T,
@@ -0,0 +1,493 @@
+use std::fmt::Display;
+
+use crate::nds::{logger, Bits};
+
+use super::detect::SaveDetector;
+
+// the save chip that sits behind AUXSPICNT/AUXSPIDATA
+// EEPROM and FRAM share a command set, flash has its own (with a few overlaps)
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum BackupKind {
+    #[default]
+    None,
+    Eeprom512,
+    Eeprom8K,
+    Eeprom64K,
+    Fram32K,
+    Flash256K,
+    Flash512K,
+    Flash1M,
+}
+
+impl BackupKind {
+    #[cfg(feature = "ui")]
+    pub const ALL: [BackupKind; 8] = [
+        BackupKind::None,
+        BackupKind::Eeprom512,
+        BackupKind::Eeprom8K,
+        BackupKind::Eeprom64K,
+        BackupKind::Fram32K,
+        BackupKind::Flash256K,
+        BackupKind::Flash512K,
+        BackupKind::Flash1M,
+    ];
+
+    pub fn size(&self) -> usize {
+        match self {
+            BackupKind::None => 0,
+            BackupKind::Eeprom512 => 512,
+            BackupKind::Eeprom8K => 1024 * 8,
+            BackupKind::Eeprom64K => 1024 * 64,
+            BackupKind::Fram32K => 1024 * 32,
+            BackupKind::Flash256K => 1024 * 256,
+            BackupKind::Flash512K => 1024 * 512,
+            BackupKind::Flash1M => 1024 * 1024,
+        }
+    }
+
+    // used to guess the chip from an existing .sav
+    pub fn from_size(size: usize) -> Self {
+        match size {
+            512 => BackupKind::Eeprom512,
+            0x2000 => BackupKind::Eeprom8K,
+            0x8000 => BackupKind::Fram32K,
+            0x10000 => BackupKind::Eeprom64K,
+            0x40000 => BackupKind::Flash256K,
+            0x80000 => BackupKind::Flash512K,
+            0x100000 => BackupKind::Flash1M,
+            _ => BackupKind::None,
+        }
+    }
+
+    pub fn is_flash(&self) -> bool {
+        matches!(
+            self,
+            BackupKind::Flash256K | BackupKind::Flash512K | BackupKind::Flash1M
+        )
+    }
+
+    fn address_bytes(&self) -> u32 {
+        match self {
+            BackupKind::None => 0,
+            BackupKind::Eeprom512 => 1,
+            BackupKind::Eeprom8K | BackupKind::Eeprom64K | BackupKind::Fram32K => 2,
+            BackupKind::Flash256K | BackupKind::Flash512K | BackupKind::Flash1M => 3,
+        }
+    }
+
+    // writes wrap around inside of a page
+    fn page_size(&self) -> usize {
+        match self {
+            BackupKind::Eeprom512 => 16,
+            BackupKind::Eeprom8K => 32,
+            BackupKind::Eeprom64K => 128,
+            BackupKind::Fram32K => self.size(), // FRAM has no pages
+            _ => 256,
+        }
+    }
+
+    // the last byte of the flash JEDEC id
+    fn flash_id(&self) -> u8 {
+        match self {
+            BackupKind::Flash256K => 0x12,
+            BackupKind::Flash512K => 0x13,
+            BackupKind::Flash1M => 0x14,
+            _ => 0xFF,
+        }
+    }
+}
+
+impl Display for BackupKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BackupKind::None => write!(f, "None"),
+            BackupKind::Eeprom512 => write!(f, "EEPROM 0.5K"),
+            BackupKind::Eeprom8K => write!(f, "EEPROM 8K"),
+            BackupKind::Eeprom64K => write!(f, "EEPROM 64K"),
+            BackupKind::Fram32K => write!(f, "FRAM 32K"),
+            BackupKind::Flash256K => write!(f, "Flash 256K"),
+            BackupKind::Flash512K => write!(f, "Flash 512K"),
+            BackupKind::Flash1M => write!(f, "Flash 1M"),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Backup {
+    pub kind: BackupKind,
+    // the whole save file, the chip only uses the first kind.size() bytes
+    // anything after that (another emulator's footer, a bigger chip) is kept and written back untouched
+    data: Vec<u8>,
+    file_size: usize, // how big the save file was when it was loaded
+    path: Option<String>,
+    dirty: bool,
+    detector: SaveDetector,
+
+    pub output: u8, // 0x040001A2, AUXSPIDATA
+
+    selected: bool,
+    command: u8,
+    position: u32,
+    address: u32,
+    status: u8,
+}
+
+impl Backup {
+    const STATUS_WIP_OFFSET: u8 = 0;
+    const STATUS_WEL_OFFSET: u8 = 1;
+
+    pub fn load(&mut self, path: Option<String>) {
+        self.flush();
+
+        self.path = path;
+        self.dirty = false;
+        self.reset();
+
+        let file = self.path.as_ref().and_then(|path| std::fs::read(path).ok());
+        match file {
+            Some(file) => {
+                match BackupKind::from_size(file.len()) {
+                    BackupKind::None => logger::warn(
+                        logger::LogSource::Cart,
+                        format!(
+                            "Loaded {} byte save file of an unknown type, it won't be touched until the save type is known",
+                            file.len()
+                        ),
+                    ),
+                    kind => logger::info(
+                        logger::LogSource::Cart,
+                        format!("Loaded {} byte save file ({})", file.len(), kind),
+                    ),
+                }
+                self.file_size = file.len();
+                self.data = file;
+            }
+            None => {
+                self.file_size = 0;
+                self.data = Vec::new();
+            }
+        }
+
+        self.detect_kind();
+    }
+
+    // an existing save already tells us what the chip is, otherwise we watch what the game sends
+    pub fn detect_kind(&mut self) {
+        self.kind = BackupKind::from_size(self.file_size);
+        self.detector.active = false;
+        if self.kind == BackupKind::None {
+            self.detector.start(self.file_size);
+        }
+    }
+
+    pub fn set_kind(&mut self, kind: BackupKind) {
+        self.detector.active = false;
+        if kind == self.kind {
+            return;
+        }
+
+        logger::info(
+            logger::LogSource::Cart,
+            format!("Backup memory set to {}", kind),
+        );
+        self.kind = kind;
+
+        // never shrink the data, overriding to a smaller chip must not lose anything
+        if kind != BackupKind::None && !self.data.is_empty() && self.data.len() != kind.size() {
+            logger::warn(
+                logger::LogSource::Cart,
+                format!(
+                    "Save file is {} bytes but {} is {} bytes",
+                    self.data.len(),
+                    kind,
+                    kind.size()
+                ),
+            );
+        }
+        if self.data.len() < kind.size() {
+            self.data.resize(kind.size(), 0xFF);
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if !self.dirty {
+            return;
+        }
+
+        if let Some(path) = &self.path {
+            match std::fs::write(path, &self.data) {
+                Ok(_) => logger::info(
+                    logger::LogSource::Cart,
+                    format!("Saved backup memory to {}", path),
+                ),
+                Err(e) => logger::error(
+                    logger::LogSource::Cart,
+                    format!("Failed to save backup memory to {}: {}", path, e),
+                ),
+            }
+        }
+
+        self.dirty = false;
+    }
+
+    // only resets the transfer, the save data itself survives a reset
+    pub fn reset(&mut self) {
+        self.output = 0;
+        self.selected = false;
+        self.command = 0;
+        self.position = 0;
+        self.address = 0;
+        self.status = 0;
+    }
+
+    pub fn transfer(&mut self, value: u8, hold: bool) -> u8 {
+        self.output = if !self.selected {
+            self.selected = true;
+            self.command = value;
+            self.position = 0;
+            self.address = 0;
+            if self.detector.active {
+                if let Some(kind) = self.detector.command(value) {
+                    self.set_kind(kind);
+                }
+            }
+            self.start_command();
+            0xFF
+        } else {
+            self.position += 1;
+            if self.detector.active {
+                self.detector.byte(value);
+            }
+            self.command_byte(value)
+        };
+
+        if !hold {
+            self.deselect();
+        }
+
+        self.output
+    }
+
+    fn write_enabled(&self) -> bool {
+        self.status.get_bit(Self::STATUS_WEL_OFFSET)
+    }
+
+    fn start_command(&mut self) {
+        match self.command {
+            0x06 => self.status.set_bit(Self::STATUS_WEL_OFFSET, true), // WREN
+            0x04 => self.status.set_bit(Self::STATUS_WEL_OFFSET, false), // WRDI
+            0xC7 if self.kind.is_flash() && self.write_enabled() => {
+                // chip erase
+                self.data[..self.kind.size()].fill(0xFF);
+                self.dirty = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn deselect(&mut self) {
+        if self.detector.active {
+            if let Some(kind) = self.detector.deselect() {
+                logger::info(
+                    logger::LogSource::Cart,
+                    format!("Detected backup memory as {}", kind),
+                );
+                self.set_kind(kind);
+                self.replay();
+            }
+        }
+
+        // anything that writes clears the write enable latch once it's done
+        if matches!(self.command, 0x01 | 0x02 | 0x0A | 0xC7 | 0xD8 | 0xDB) {
+            self.status.set_bit(Self::STATUS_WEL_OFFSET, false);
+        }
+
+        self.selected = false;
+    }
+
+    // the transfer that told the detector what the chip is went nowhere, so it's run again on the real chip
+    // otherwise the game's first write would be lost
+    fn replay(&mut self) {
+        let (command, bytes) = self.detector.transfer();
+        let bytes = bytes.to_vec();
+
+        self.command = command;
+        self.position = 0;
+        self.address = 0;
+        for value in bytes {
+            self.position += 1;
+            self.command_byte(value);
+        }
+    }
+
+    fn command_byte(&mut self, value: u8) -> u8 {
+        if self.kind == BackupKind::None {
+            return 0xFF;
+        }
+
+        match self.command {
+            0x05 => {
+                // RDSR
+                self.status.set_bit(Self::STATUS_WIP_OFFSET, false);
+                if self.kind == BackupKind::Eeprom512 {
+                    self.status | 0xF0
+                } else {
+                    self.status
+                }
+            }
+            0x01 => {
+                // WRSR, only the block protect bits are writable
+                if self.position == 1 && self.write_enabled() {
+                    self.status.set_bits(2, 3, value.get_bits(2, 3));
+                }
+                0xFF
+            }
+            0x9F if self.kind.is_flash() => match self.position {
+                // RDID
+                1 => 0x20,
+                2 => 0x40,
+                3 => self.kind.flash_id(),
+                _ => 0xFF,
+            },
+            0x03 | 0x02 | 0x0A | 0x0B | 0xD8 | 0xDB => self.data_command(value),
+            _ => {
+                logger::warn_once(
+                    logger::LogSource::Cart,
+                    format!(
+                        "Unknown backup command {:#04X} ({})",
+                        self.command, self.kind
+                    ),
+                );
+                0xFF
+            }
+        }
+    }
+
+    fn data_command(&mut self, value: u8) -> u8 {
+        let address_bytes = self.kind.address_bytes();
+        if self.position <= address_bytes {
+            self.address = (self.address << 8) | value as u32;
+
+            if self.kind == BackupKind::Eeprom512 {
+                // bit 3 of the command is the 9th address bit
+                self.address.set_bit(8, self.command.get_bit(3));
+            }
+
+            if self.position == address_bytes {
+                self.address %= self.kind.size() as u32;
+                self.address_complete();
+            }
+
+            return 0xFF;
+        }
+
+        let is_flash = self.kind.is_flash();
+        match self.command {
+            0x03 => self.read_byte(),
+            0x0B if !is_flash => self.read_byte(),
+            0x0B => {
+                // fast read has a dummy byte after the address
+                if self.position == address_bytes + 1 {
+                    0xFF
+                } else {
+                    self.read_byte()
+                }
+            }
+            0x02 | 0x0A => {
+                if self.write_enabled() {
+                    let address = self.address as usize;
+                    self.data[address] = if is_flash && self.command == 0x02 {
+                        // page program can only clear bits
+                        self.data[address] & value
+                    } else {
+                        value
+                    };
+                    self.dirty = true;
+
+                    let page_size = self.kind.page_size();
+                    let page = address / page_size * page_size;
+                    self.address = (page + (address + 1) % page_size) as u32;
+                }
+                0xFF
+            }
+            _ => 0xFF,
+        }
+    }
+
+    fn address_complete(&mut self) {
+        if !self.kind.is_flash() || !self.write_enabled() {
+            return;
+        }
+
+        let erase_size = match self.command {
+            0xDB => 0x100,   // page erase
+            0xD8 => 0x10000, // sector erase
+            _ => return,
+        };
+
+        let start = self.address as usize / erase_size * erase_size;
+        let end = (start + erase_size).min(self.kind.size());
+        self.data[start..end].fill(0xFF);
+        self.dirty = true;
+    }
+
+    fn read_byte(&mut self) -> u8 {
+        let value = self.data[self.address as usize];
+        self.address = (self.address + 1) % self.kind.size() as u32;
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Backup, BackupKind};
+
+    // holds the chip select until the last byte, like a game does
+    fn send(backup: &mut Backup, bytes: &[u8]) -> Vec<u8> {
+        bytes
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| backup.transfer(value, i + 1 < bytes.len()))
+            .collect()
+    }
+
+    #[test]
+    fn the_detecting_write_reaches_the_chip() {
+        let mut backup = Backup::default();
+        backup.detect_kind();
+
+        send(&mut backup, &[0x06]);
+        send(&mut backup, &[0x02, 0x01, 0x23, 0xAB]);
+        assert_eq!(backup.kind, BackupKind::Eeprom64K);
+
+        let read = send(&mut backup, &[0x03, 0x01, 0x23, 0xFF]);
+        assert_eq!(read[3], 0xAB);
+
+        // and the write still cleared the write enable latch
+        let status = send(&mut backup, &[0x05, 0xFF]);
+        assert_eq!(status[1] & 0x02, 0);
+    }
+
+    #[test]
+    fn the_detecting_read_is_answered_next_time() {
+        let mut backup = Backup::default();
+        backup.detect_kind();
+
+        send(&mut backup, &[0x03, 0x00, 0xFF]);
+        assert_eq!(backup.kind, BackupKind::Eeprom512);
+
+        // a blank chip reads back as 0xFF
+        let read = send(&mut backup, &[0x03, 0x00, 0xFF]);
+        assert_eq!(read[2], 0xFF);
+    }
+
+    #[test]
+    fn rdid_reports_the_detected_flash() {
+        let mut backup = Backup::default();
+        backup.detect_kind();
+
+        let id = send(&mut backup, &[0x9F, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(backup.kind, BackupKind::Flash512K);
+        assert_eq!(id[1..], [0x20, 0x40, BackupKind::Flash512K.flash_id()]);
+    }
+}
@@ -0,0 +1,193 @@
+use super::backup::BackupKind;
+
+// games that trip up the detector, or where the chip size can't be guessed from the protocol
+// keyed on the first 3 characters of the game code so every region matches
+const GAME_CODES: [(&str, BackupKind); 14] = [
+    ("ADA", BackupKind::Flash512K), // Pokemon Diamond
+    ("APA", BackupKind::Flash512K), // Pokemon Pearl
+    ("CPU", BackupKind::Flash512K), // Pokemon Platinum
+    ("IPK", BackupKind::Flash512K), // Pokemon HeartGold
+    ("IPG", BackupKind::Flash512K), // Pokemon SoulSilver
+    ("IRB", BackupKind::Flash512K), // Pokemon Black
+    ("IRA", BackupKind::Flash512K), // Pokemon White
+    ("IRE", BackupKind::Flash512K), // Pokemon Black 2
+    ("IRD", BackupKind::Flash512K), // Pokemon White 2
+    ("ASM", BackupKind::Eeprom512), // Super Mario 64 DS
+    ("AMC", BackupKind::Eeprom8K),  // Mario Kart DS
+    ("A2D", BackupKind::Eeprom8K),  // New Super Mario Bros.
+    ("ADM", BackupKind::Flash256K), // Animal Crossing: Wild World
+    ("AZE", BackupKind::Eeprom64K), // The Legend of Zelda: Phantom Hourglass
+];
+
+pub fn lookup_game_code(game_code: &str) -> Option<BackupKind> {
+    let prefix = game_code.get(0..3)?;
+    GAME_CODES
+        .iter()
+        .find(|(code, _)| *code == prefix)
+        .map(|(_, kind)| *kind)
+}
+
+// watches the first read or write the game sends to a chip we know nothing about
+// and guesses the address width from how long the transfer is, the same idea as melonDS' old save discovery
+// the transfer is kept so it can be run again on the chip once we know what it is
+#[derive(Default)]
+pub struct SaveDetector {
+    pub active: bool,
+    command: u8,
+    bytes: Vec<u8>,   // sent after the command, up until the chip is deselected
+    size_hint: usize, // how big the save file we couldn't make sense of is
+}
+
+impl SaveDetector {
+    pub fn start(&mut self, size_hint: usize) {
+        self.active = true;
+        self.command = 0;
+        self.bytes.clear();
+        self.size_hint = size_hint;
+    }
+
+    pub fn command(&mut self, command: u8) -> Option<BackupKind> {
+        self.command = command;
+        self.bytes.clear();
+
+        match command {
+            // these only exist on flash, RDID needs an answer straight away
+            0x9F | 0xC7 | 0xD8 | 0xDB => self.finish(self.flash_kind()),
+            _ => None,
+        }
+    }
+
+    pub fn byte(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    // the command and everything sent after it
+    pub fn transfer(&self) -> (u8, &[u8]) {
+        (self.command, &self.bytes)
+    }
+
+    pub fn deselect(&mut self) -> Option<BackupKind> {
+        let len = self.bytes.len();
+        let kind = match self.command {
+            0x03 => {
+                // a read is the address and then the bytes the game clocks out with dummy writes
+                // games nearly always read a single byte first, so the last byte is the dummy one
+                let (address_bytes, _) = Self::split_transfer(len)?;
+                self.kind_from_address(address_bytes, None)
+            }
+            0x02 | 0x0A => {
+                // a write is the address and then the data, which is a whole page when it's more than a byte
+                let (address_bytes, data_bytes) = Self::split_transfer(len)?;
+                self.kind_from_address(address_bytes, Some(data_bytes))
+            }
+            0x0B => BackupKind::Eeprom512, // reading the upper half of a 0.5K EEPROM
+            _ => return None,
+        };
+        self.finish(kind)
+    }
+
+    // the address is 1 to 3 bytes, and anything after it is 1 byte or a multiple of 4 bytes
+    // a multiple of 4 in total can't be told apart, so that waits for the next transfer
+    fn split_transfer(len: usize) -> Option<(usize, usize)> {
+        match len {
+            0 | 1 => None,
+            2..=4 => Some((len - 1, 1)),
+            _ => (1..=3)
+                .find(|address_bytes| (len - address_bytes) % 4 == 0)
+                .map(|address_bytes| (address_bytes, len - address_bytes)),
+        }
+    }
+
+    fn kind_from_address(&self, address_bytes: usize, data_bytes: Option<usize>) -> BackupKind {
+        match (address_bytes, data_bytes) {
+            (1, _) => BackupKind::Eeprom512,
+            (2, Some(32)) => BackupKind::Eeprom8K, // 8K EEPROM has 32 byte pages
+            (2, _) => BackupKind::Eeprom64K, // the biggest 2 byte chip, so nothing wraps around
+            _ => self.flash_kind(),
+        }
+    }
+
+    // nothing in the protocol says how big flash is, that's only in the JEDEC id we give back
+    // so go with a size that fits the save file we have, and RDID reports the same size
+    fn flash_kind(&self) -> BackupKind {
+        match self.size_hint {
+            0 => BackupKind::Flash512K, // nothing to go on, this is the most common one
+            1..=0x40000 => BackupKind::Flash256K,
+            0x40001..=0x80000 => BackupKind::Flash512K,
+            _ => BackupKind::Flash1M,
+        }
+    }
+
+    fn finish(&mut self, kind: BackupKind) -> Option<BackupKind> {
+        self.active = false;
+        Some(kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BackupKind, SaveDetector};
+
+    // runs one transfer through the detector, the same way Backup feeds it
+    fn detect(size_hint: usize, transfer: &[u8]) -> Option<BackupKind> {
+        let mut detector = SaveDetector::default();
+        detector.start(size_hint);
+        if let Some(kind) = detector.command(transfer[0]) {
+            return Some(kind);
+        }
+        transfer[1..].iter().for_each(|&value| detector.byte(value));
+        detector.deselect()
+    }
+
+    #[test]
+    fn reads_give_the_address_width() {
+        assert_eq!(detect(0, &[0x03, 0x10, 0xFF]), Some(BackupKind::Eeprom512));
+        assert_eq!(detect(0, &[0x0B, 0x10, 0xFF]), Some(BackupKind::Eeprom512));
+        assert_eq!(
+            detect(0, &[0x03, 0x00, 0x10, 0xFF]),
+            Some(BackupKind::Eeprom64K)
+        );
+        assert_eq!(
+            detect(0, &[0x03, 0x00, 0x00, 0x10, 0xFF]),
+            Some(BackupKind::Flash512K)
+        );
+    }
+
+    #[test]
+    fn page_writes_give_the_eeprom_size() {
+        let mut page = vec![0x02, 0x00, 0x20];
+        page.extend([0xAA; 32]);
+        assert_eq!(detect(0, &page), Some(BackupKind::Eeprom8K));
+
+        let mut page = vec![0x02, 0x00, 0x80];
+        page.extend([0xAA; 128]);
+        assert_eq!(detect(0, &page), Some(BackupKind::Eeprom64K));
+    }
+
+    #[test]
+    fn ambiguous_transfers_wait() {
+        // 8 bytes after the command fits no address width with whole words of data
+        assert_eq!(detect(0, &[0x02, 0, 0, 0, 0, 0, 0, 0, 0]), None);
+        assert_eq!(detect(0, &[0x03]), None);
+        assert_eq!(detect(0, &[0x05, 0xFF]), None);
+    }
+
+    #[test]
+    fn flash_size_follows_the_save_file() {
+        assert_eq!(detect(0, &[0x9F]), Some(BackupKind::Flash512K));
+        assert_eq!(detect(0x40000, &[0x9F]), Some(BackupKind::Flash256K));
+        assert_eq!(detect(0x80010, &[0xD8]), Some(BackupKind::Flash1M));
+    }
+
+    #[test]
+    fn game_codes_match_every_region() {
+        assert_eq!(lookup("ADAE"), Some(BackupKind::Flash512K));
+        assert_eq!(lookup("ADAJ"), Some(BackupKind::Flash512K));
+        assert_eq!(lookup("ZZZE"), None);
+        assert_eq!(lookup("AD"), None);
+    }
+
+    fn lookup(game_code: &str) -> Option<BackupKind> {
+        super::lookup_game_code(game_code)
+    }
+}
@@ -0,0 +1,169 @@
+use crate::nds::Bytes;
+
+// Blowfish, as used by the BIOS and the cartridge for KEY1 commands and the secure area
+// the P-array and S-boxes come from the ARM7 BIOS (0x30..0x1078) and are mixed with the game code
+pub struct Key1 {
+    keybuf: Vec<u32>,
+    keycode: [u32; 3],
+}
+
+impl Key1 {
+    pub const TABLE_START: usize = 0x30;
+    pub const TABLE_END: usize = 0x1078;
+
+    pub fn new(table: &[u8], idcode: u32, level: u8, modulo: usize) -> Self {
+        let mut key1 = Self {
+            keybuf: table
+                .chunks_exact(4)
+                .map(|chunk| [chunk[0], chunk[1], chunk[2], chunk[3]].into_word())
+                .collect(),
+            keycode: [idcode, idcode / 2, idcode.wrapping_mul(2)],
+        };
+
+        if level >= 1 {
+            key1.apply_keycode(modulo);
+        }
+        if level >= 2 {
+            key1.apply_keycode(modulo);
+        }
+
+        key1.keycode[1] = key1.keycode[1].wrapping_mul(2);
+        key1.keycode[2] /= 2;
+
+        if level >= 3 {
+            key1.apply_keycode(modulo);
+        }
+
+        key1
+    }
+
+    // the game code from the header, which the key is mixed with
+    pub fn idcode(rom: &[u8]) -> u32 {
+        let mut bytes = [0; 4];
+        if let Some(code) = rom.get(0x0C..0x10) {
+            bytes.copy_from_slice(code);
+        }
+        bytes.into_word()
+    }
+
+    pub fn encrypt_64bit(&self, data: &mut [u32; 2]) {
+        let mut y = data[0];
+        let mut x = data[1];
+        for i in 0..=0x0F {
+            let z = self.keybuf[i] ^ x;
+            x = self.feistel(z);
+            x ^= y;
+            y = z;
+        }
+
+        data[0] = x ^ self.keybuf[0x10];
+        data[1] = y ^ self.keybuf[0x11];
+    }
+
+    pub fn decrypt_64bit(&self, data: &mut [u32; 2]) {
+        let mut y = data[0];
+        let mut x = data[1];
+        for i in (0x02..=0x11).rev() {
+            let z = self.keybuf[i] ^ x;
+            x = self.feistel(z);
+            x ^= y;
+            y = z;
+        }
+
+        data[0] = x ^ self.keybuf[0x01];
+        data[1] = y ^ self.keybuf[0x00];
+    }
+
+    fn feistel(&self, z: u32) -> u32 {
+        let mut x = self.keybuf[0x012 + (z >> 24) as usize];
+        x = x.wrapping_add(self.keybuf[0x112 + ((z >> 16) & 0xFF) as usize]);
+        x ^= self.keybuf[0x212 + ((z >> 8) & 0xFF) as usize];
+        x.wrapping_add(self.keybuf[0x312 + (z & 0xFF) as usize])
+    }
+
+    // modulo is in bytes, 8 for the NDS and 12 for the DSi
+    fn apply_keycode(&mut self, modulo: usize) {
+        let mut code = [self.keycode[1], self.keycode[2]];
+        self.encrypt_64bit(&mut code);
+        self.keycode[1] = code[0];
+        self.keycode[2] = code[1];
+
+        let mut code = [self.keycode[0], self.keycode[1]];
+        self.encrypt_64bit(&mut code);
+        self.keycode[0] = code[0];
+        self.keycode[1] = code[1];
+
+        for i in 0..=0x11 {
+            self.keybuf[i] ^= self.keycode[i % (modulo / 4)].swap_bytes();
+        }
+
+        let mut scratch = [0; 2];
+        for i in (0..=0x410).step_by(2) {
+            self.encrypt_64bit(&mut scratch);
+            self.keybuf[i] = scratch[1];
+            self.keybuf[i + 1] = scratch[0];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Key1;
+
+    // the real table is in the ARM7 BIOS, any bytes do to check the cipher against itself
+    fn table() -> Vec<u8> {
+        let mut state = 0x1234_5678u32;
+        (Key1::TABLE_START..Key1::TABLE_END)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn idcode_is_the_little_endian_game_code() {
+        let mut header = vec![0; 0x10];
+        header[0x0C..0x10].copy_from_slice(b"ADAE");
+        assert_eq!(Key1::idcode(&header), 0x4541_4441);
+        assert_eq!(Key1::idcode(&[]), 0);
+    }
+
+    #[test]
+    fn an_empty_table_only_swaps_the_halves() {
+        // with the whole table 0 each round is a plain swap, and there are 16 of them
+        let key1 = Key1::new(&[0; Key1::TABLE_END - Key1::TABLE_START], 0, 0, 8);
+        let mut data = [0x0123_4567, 0x89AB_CDEF];
+        key1.encrypt_64bit(&mut data);
+        assert_eq!(data, [0x89AB_CDEF, 0x0123_4567]);
+        key1.decrypt_64bit(&mut data);
+        assert_eq!(data, [0x0123_4567, 0x89AB_CDEF]);
+    }
+
+    #[test]
+    fn decrypt_undoes_encrypt() {
+        let table = table();
+        let idcode = u32::from_le_bytes(*b"ADAE");
+        for (level, modulo) in [(0, 8), (1, 8), (2, 8), (3, 8), (3, 12)] {
+            let key1 = Key1::new(&table, idcode, level, modulo);
+            let plain = [0xE7FF_DEFF, 0x0000_0001];
+            let mut data = plain;
+            key1.encrypt_64bit(&mut data);
+            assert_ne!(data, plain);
+            key1.decrypt_64bit(&mut data);
+            assert_eq!(data, plain);
+        }
+    }
+
+    #[test]
+    fn the_game_code_changes_the_key() {
+        let table = table();
+        let encrypt = |game_code: &[u8; 4]| {
+            let key1 = Key1::new(&table, u32::from_le_bytes(*game_code), 2, 8);
+            let mut data = [0, 0];
+            key1.encrypt_64bit(&mut data);
+            data
+        };
+        assert_ne!(encrypt(b"ADAE"), encrypt(b"ADAJ"));
+    }
+}
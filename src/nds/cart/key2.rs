@@ -0,0 +1,96 @@
+// KEY2 is a pair of 39 bit LFSRs, XORed over the command and data bytes
+// the DS and the cartridge each run their own copy, they only agree if both were seeded the same way
+#[derive(Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
+pub struct Key2 {
+    x: u64,
+    y: u64,
+}
+
+impl Key2 {
+    const MASK: u64 = 0x7F_FFFF_FFFF;
+
+    // the seed the cartridge always uses for y, and the DS side gets from the BIOS
+    pub const SEED1: u64 = 0x5C_879B_9B05;
+    const SEED_BYTES: [u64; 8] = [0xE8, 0x4D, 0x5A, 0xB1, 0x17, 0x8F, 0x99, 0xD5];
+
+    pub fn new(seed0: u64, seed1: u64) -> Self {
+        Self {
+            x: Self::reverse(seed0),
+            y: Self::reverse(seed1),
+        }
+    }
+
+    // the x seed derived from the mmmnnn part of the KEY1 "activate KEY2" command
+    // seed_index is header byte 0x13
+    pub fn seed0(mmmnnn: u32, seed_index: u8) -> u64 {
+        ((mmmnnn as u64) << 15) + 0x6000 + Self::SEED_BYTES[(seed_index & 7) as usize]
+    }
+
+    pub fn process(&mut self, value: u8) -> u8 {
+        let x = self.x;
+        let y = self.y;
+        self.x = ((((x >> 5) ^ (x >> 17) ^ (x >> 18) ^ (x >> 31)) & 0xFF) + (x << 8)) & Self::MASK;
+        self.y = ((((y >> 5) ^ (y >> 23) ^ (y >> 18) ^ (y >> 31)) & 0xFF) + (y << 8)) & Self::MASK;
+
+        ((self.x ^ self.y) & 0xFF) as u8 ^ value
+    }
+
+    pub fn process_slice(&mut self, values: &mut [u8]) {
+        values
+            .iter_mut()
+            .for_each(|value| *value = self.process(*value));
+    }
+
+    fn reverse(seed: u64) -> u64 {
+        (seed & Self::MASK).reverse_bits() >> (64 - 39)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Key2;
+
+    // the seed a retail cartridge resets x to, GBATEK lists it alongside SEED1
+    const CART_SEED0: u64 = 0x58_C56D_E0E8;
+
+    #[test]
+    fn seeds_are_bit_reversed() {
+        let key2 = Key2::new(1, Key2::SEED1);
+        assert_eq!(key2.x, 1 << 38);
+        assert_eq!(key2.y, 0x50_6CEC_F09D);
+    }
+
+    #[test]
+    fn seed0_mixes_in_the_header_byte() {
+        assert_eq!(Key2::seed0(0x123456, 0), 0x9_1A2B_60E8);
+        // only the bottom 3 bits of the header byte pick the seed byte
+        assert_eq!(Key2::seed0(0x123456, 9), Key2::seed0(0x123456, 1));
+    }
+
+    #[test]
+    fn keystream_matches() {
+        let mut key2 = Key2::new(CART_SEED0, Key2::SEED1);
+        let mut stream = [0; 8];
+        key2.process_slice(&mut stream);
+        assert_eq!(stream, [0x46, 0xC5, 0x3A, 0x81, 0xC3, 0xE0, 0xBA, 0xB0]);
+
+        // both registers stay at 0 without a seed
+        let mut key2 = Key2::new(0, 0);
+        let mut stream = [0x12; 8];
+        key2.process_slice(&mut stream);
+        assert_eq!(stream, [0x12; 8]);
+    }
+
+    #[test]
+    fn both_sides_agree() {
+        let mut ds = Key2::new(Key2::seed0(0xABCDEF, 3), Key2::SEED1);
+        let mut cart = ds;
+
+        let command = [0xB7, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00];
+        let mut sent = command;
+        ds.process_slice(&mut sent);
+        assert_ne!(sent, command);
+        cart.process_slice(&mut sent);
+        assert_eq!(sent, command);
+    }
+}
@@ -1,8 +1,24 @@
+use std::path::Path;
+
+use backup::{Backup, BackupKind};
+use key1::Key1;
+use key2::Key2;
 use models::{AuxSpiCnt, Command, ExMem, RomCtrl};
+use protocol::{Card, CardContext};
+use secure_area::SecureAreaState;
 
-use super::bus::{bus7::Bus7, bus9::Bus9};
+use super::{
+    bus::{bus7::Bus7, bus9::Bus9},
+    logger, Bits,
+};
 
+pub mod backup;
+mod detect;
+mod key1;
+mod key2;
 mod models;
+mod protocol;
+mod secure_area;
 
 // TODO: we need to stream this
 
@@ -14,66 +30,232 @@ pub struct Cartridge {
     pub rom: Vec<u8>,
     #[serde(skip)]
     pub metadata: models::Metadata,
+    #[serde(skip)]
+    pub backup: Backup,
+    #[serde(skip)]
+    key1_table: Vec<u8>, // ARM7 BIOS 0x30..0x1078
 
     pub romctrl: RomCtrl,
     pub auxspicnt: AuxSpiCnt,
     pub command: Command,
+    pub romseed: [u8; 12], // 0x040001B0..0x040001BB, write-only
     pub exmemcnt: ExMem,
     pub exmemstat: ExMem,
+
+    key2: Key2, // our side of KEY2, the cartridge has its own
+    card: Card,
+    response: Vec<u8>,
 }
 
 impl Cartridge {
-    pub fn load(&mut self, rom: Vec<u8>) -> bool {
+    pub fn load(&mut self, rom: Vec<u8>, rom_path: Option<String>) -> bool {
         self.loaded = false;
         self.metadata = models::Metadata::default();
 
         self.rom = rom;
-        self.metadata.parse(&self.rom);
+        self.metadata.parse(&self.rom, &self.key1_table);
+
+        let save_path = rom_path.map(|rom_path| {
+            Path::new(&rom_path)
+                .with_extension("sav")
+                .to_string_lossy()
+                .to_string()
+        });
+        self.backup.load(save_path);
+        self.detect_backup_kind();
 
         self.loaded = true;
         true
     }
 
+    #[cfg(feature = "ui")]
     pub fn load_state(&mut self, cart: Self) {
         self.romctrl = cart.romctrl;
         self.auxspicnt = cart.auxspicnt;
         self.command = cart.command;
+        self.romseed = cart.romseed;
         self.exmemcnt = cart.exmemcnt;
         self.exmemstat = cart.exmemstat;
+
+        self.key2 = cart.key2;
+        self.card = cart.card;
+        self.response = cart.response;
     }
 
     pub fn reset(&mut self) {
         self.romctrl = RomCtrl::default();
         self.auxspicnt = AuxSpiCnt::default();
         self.command = Command::default();
+        self.romseed = [0; 12];
         self.exmemcnt = ExMem::default();
         self.exmemstat = ExMem::default();
+        self.backup.reset();
+
+        self.key2 = Key2::default();
+        self.card = Card::default();
+        self.response = Vec::new();
+    }
+
+    // for when there's no override, the known games go first and then the detector
+    pub fn detect_backup_kind(&mut self) {
+        self.backup.detect_kind();
+        if self.backup.kind == BackupKind::None {
+            if let Some(kind) = detect::lookup_game_code(&self.metadata.game_code) {
+                self.backup.set_kind(kind);
+            }
+        }
+    }
+
+    pub fn set_key1_table(&mut self, arm7_bios: &[u8]) {
+        self.key1_table = arm7_bios
+            .get(Key1::TABLE_START..Key1::TABLE_END)
+            .map(|table| table.to_vec())
+            .unwrap_or_default();
+
+        // with the table we can tell for sure
+        if self.loaded {
+            self.metadata.secure_area =
+                secure_area::detect(&self.rom, self.metadata.arm9_rom_offset, &self.key1_table);
+        }
+    }
+
+    // direct boot needs the secure area decrypted, the BIOS needs it encrypted
+    pub fn prepare_secure_area(&mut self, firmware_boot: bool) {
+        let state = self.metadata.secure_area;
+        let target = if firmware_boot {
+            SecureAreaState::Encrypted
+        } else {
+            SecureAreaState::Decrypted
+        };
+        if state == SecureAreaState::None || state == target {
+            return;
+        }
+
+        if self.key1_table.is_empty() {
+            logger::error(
+                logger::LogSource::Cart,
+                format!(
+                    "The secure area is {} and needs an ARM7 BIOS to convert it",
+                    state
+                ),
+            );
+            return;
+        }
+
+        let arm9_rom_offset = self.metadata.arm9_rom_offset;
+        let success = match target {
+            SecureAreaState::Encrypted => {
+                secure_area::encrypt(&mut self.rom, arm9_rom_offset, &self.key1_table)
+            }
+            _ => secure_area::decrypt(&mut self.rom, arm9_rom_offset, &self.key1_table),
+        };
+
+        if success {
+            self.metadata.secure_area = target;
+            logger::info(
+                logger::LogSource::Cart,
+                format!("Secure area is now {}", target),
+            );
+        }
+    }
+
+    // skips everything the BIOS would have done with the cartridge
+    pub fn direct_boot(&mut self) {
+        let seed0 = Key2::seed0(0, *self.rom.get(0x13).unwrap_or(&0));
+        self.romseed[0..4].copy_from_slice(&(seed0 as u32).to_le_bytes());
+        self.romseed[4..8].copy_from_slice(&(Key2::SEED1 as u32).to_le_bytes());
+        self.romseed[8..10].copy_from_slice(&((seed0 >> 32) as u16).to_le_bytes());
+        self.romseed[10..12].copy_from_slice(&((Key2::SEED1 >> 32) as u16).to_le_bytes());
+
+        self.key2 = Key2::new(seed0, Key2::SEED1);
+        self.card.direct_boot(self.key2);
+    }
+
+    pub fn chip_id(&self) -> u32 {
+        // manufacturer is always macronix, followed by the size in MB - 1
+        // the size is the header's device capacity (128KB << n), trimmed ROMs are smaller than the chip
+        let capacity = *self.rom.get(0x14).unwrap_or(&0) as u32;
+        let size_mb = 1 << capacity.saturating_sub(3).min(8);
+        0xC2 | ((size_mb - 1) & 0xFF) << 8
+    }
+
+    pub fn write_romseed<const T: usize>(&mut self, offset: usize, value: [u8; T]) {
+        let end = (offset + T).min(self.romseed.len());
+        self.romseed[offset..end].copy_from_slice(&value[..end - offset]);
+    }
+
+    pub fn write_romctrl(&mut self, value: u32) {
+        if value.get_bit(RomCtrl::KEY2_APPLY_SEED_OFFSET) {
+            let seed = |lo: usize, hi: usize| {
+                let mut bytes = [0; 8];
+                bytes[0..4].copy_from_slice(&self.romseed[lo..lo + 4]);
+                bytes[4..6].copy_from_slice(&self.romseed[hi..hi + 2]);
+                u64::from_le_bytes(bytes) & 0x7F_FFFF_FFFF
+            };
+            self.key2 = Key2::new(seed(0, 8), seed(4, 10));
+        }
+
+        if self.romctrl.set(value) {
+            self.start_command();
+        }
+    }
+
+    fn start_command(&mut self) {
+        let key2_command = self.romctrl.get_key2_command();
+        let key2_data = self.romctrl.get_key2_data();
+
+        let mut command = self.command.0.to_be_bytes();
+        if key2_command {
+            self.key2.process_slice(&mut command);
+        }
+
+        let ctx = CardContext {
+            rom: &self.rom,
+            key1_table: &self.key1_table,
+            chip_id: self.chip_id(),
+            key2_command,
+            key2_data,
+        };
+        self.response = self.card.command(
+            u64::from_be_bytes(command),
+            self.romctrl.get_transfer_length(),
+            ctx,
+        );
+
+        if key2_data {
+            self.key2.process_slice(&mut self.response);
+        }
     }
 
     pub fn clock(&mut self, bus9: &mut Bus9, bus7: &mut Bus7) {
         let interrupts = if self.exmemcnt.get_nds_slot_access_rights() {
-            &mut bus9.interrupts
-        } else {
             &mut bus7.interrupts
+        } else {
+            &mut bus9.interrupts
         };
         self.romctrl.clock(interrupts);
     }
 
+    pub fn write_auxspidata(&mut self, value: u8) {
+        if !self.auxspicnt.get_slot_enable() || !self.auxspicnt.get_slot_mode() {
+            return;
+        }
+
+        let hold = self.auxspicnt.get_chip_select_hold();
+        self.backup.transfer(value, hold);
+    }
+
     pub fn read_bus(&mut self) -> u32 {
         // TODO: i might need to return 0s if data is not actually ready
         // currently i just assume that the game won't read if data is not ready
+        if !self.romctrl.get_block_status() {
+            return 0xFFFFFFFF;
+        }
 
-        // TODO: this sucks
-        let data = match self.command.get_command() {
-            0xB7 => {
-                let base_addr = self.command.get_read_address() as usize;
-                let start_addr = base_addr + self.romctrl.words_read as usize * 4;
-                // println!("Reading from {:X} {}", start_addr, self.romctrl.words_read);
-                let data = self.rom[start_addr..start_addr + 4].to_vec();
-                u32::from_le_bytes(data.try_into().unwrap())
-            }
-            0xB8 => 0x00000FC2,
-            _ => 0,
+        let offset = self.romctrl.words_read as usize * 4;
+        let data = match self.response.get(offset..offset + 4) {
+            Some(bytes) => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            None => 0xFFFFFFFF,
         };
 
         self.romctrl.word_read();
@@ -4,6 +4,10 @@ use crate::nds::Bits;
 pub struct AuxSpiCnt(u16);
 
 impl AuxSpiCnt {
+    const CHIP_SELECT_HOLD_OFFSET: u16 = 6;
+    const SLOT_MODE_OFFSET: u16 = 13;
+    const SLOT_ENABLE_OFFSET: u16 = 15;
+
     pub fn value(&self) -> u16 {
         self.0
     }
@@ -17,4 +21,17 @@ impl AuxSpiCnt {
     pub fn set_hi(&mut self, data: u16) {
         self.0.set_bits(8, 15, data);
     }
+
+    pub fn get_chip_select_hold(&self) -> bool {
+        self.0.get_bit(Self::CHIP_SELECT_HOLD_OFFSET)
+    }
+
+    // false: parallel ROM, true: serial backup SPI
+    pub fn get_slot_mode(&self) -> bool {
+        self.0.get_bit(Self::SLOT_MODE_OFFSET)
+    }
+
+    pub fn get_slot_enable(&self) -> bool {
+        self.0.get_bit(Self::SLOT_ENABLE_OFFSET)
+    }
 }
@@ -1,5 +1,3 @@
-use crate::nds::Bits;
-
 #[derive(Default, serde::Deserialize, serde::Serialize)]
 pub struct Command(pub u64);
 
@@ -21,13 +19,4 @@ impl Command {
         //     logger::format_debug!("Appended to command: {} {:#018X}", data, self.0),
         // );
     }
-
-    pub fn get_command(&self) -> u8 {
-        self.0.get_bits(56, 63) as u8
-    }
-
-    pub fn get_read_address(&self) -> u32 {
-        // TODO: check the b7 command, there are edgecases for this
-        self.0.get_bits(24, 55) as u32
-    }
 }
@@ -7,7 +7,7 @@ use crate::nds::Bits;
 pub struct ExMem(pub u16);
 
 impl ExMem {
-    // true: arm9, false: arm7
+    // false: arm9, true: arm7
     pub fn get_nds_slot_access_rights(&self) -> bool {
         self.0.get_bit(11)
     }
@@ -1,4 +1,7 @@
-use crate::nds::logger::{self, Logger, LoggerTrait};
+use crate::nds::{
+    cart::secure_area::{self, SecureAreaState},
+    logger::{self, Logger, LoggerTrait},
+};
 
 // this is mostly my stuff for my emulator
 pub struct Metadata {
@@ -6,6 +9,7 @@ pub struct Metadata {
     pub parse_error: bool,
 
     pub game_title: String,
+    pub game_code: String,
 
     pub arm9_rom_offset: u32,
     pub arm9_entry_address: u32,
@@ -16,6 +20,8 @@ pub struct Metadata {
     pub arm7_entry_address: u32,
     pub arm7_load_address: u32,
     pub arm7_size: u32,
+
+    pub secure_area: SecureAreaState,
 }
 
 impl Default for Metadata {
@@ -24,6 +30,7 @@ impl Default for Metadata {
             logger: Logger(logger::LogSource::Cart),
             parse_error: false,
             game_title: String::new(),
+            game_code: String::new(),
             arm9_rom_offset: 0,
             arm9_entry_address: 0,
             arm9_load_address: 0,
@@ -32,12 +39,13 @@ impl Default for Metadata {
             arm7_entry_address: 0,
             arm7_load_address: 0,
             arm7_size: 0,
+            secure_area: SecureAreaState::None,
         }
     }
 }
 
 impl Metadata {
-    pub fn parse(&mut self, rom: &[u8]) -> bool {
+    pub fn parse(&mut self, rom: &[u8], key1_table: &[u8]) -> bool {
         self.logger.log_info("=== Parsing ROM ===");
         self.logger
             .log_info(format!("ROM size: {} bytes", rom.len()));
@@ -46,6 +54,10 @@ impl Metadata {
         self.logger
             .log_info(format!("Game Title: {}", self.game_title));
 
+        self.game_code = String::from_utf8_lossy(&rom[0x00C..0x010]).to_string();
+        self.logger
+            .log_info(format!("Game Code: {}", self.game_code));
+
         self.arm9_rom_offset = self.parse_u32(rom, 0x020);
         self.arm9_entry_address = self.parse_u32(rom, 0x024);
         self.arm9_load_address = self.parse_u32(rom, 0x028);
@@ -80,6 +92,10 @@ impl Metadata {
         self.logger
             .log_info(format!("ARM7 Size: {} bytes", self.arm7_size));
 
+        self.secure_area = secure_area::detect(rom, self.arm9_rom_offset, key1_table);
+        self.logger
+            .log_info(format!("Secure Area: {}", self.secure_area));
+
         logger::info(logger::LogSource::Cart, "=== End ROM ===");
 
         if self.parse_error {
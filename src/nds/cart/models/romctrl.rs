@@ -8,21 +8,28 @@ pub struct RomCtrl {
     cycles: u8,
     words_to_read: u32,
     just_finished: bool,
+    dma_requested: bool,
     pub words_read: u32,
 }
 
 impl RomCtrl {
+    const KEY2_DATA_OFFSET: u32 = 13;
+    pub const KEY2_APPLY_SEED_OFFSET: u32 = 15;
+    const KEY2_COMMAND_OFFSET: u32 = 22;
+
     pub fn value(&self) -> u32 {
         self.value
     }
 
-    pub fn set(&mut self, data: u32) {
+    // returns true if a new transfer was started
+    pub fn set(&mut self, data: u32) -> bool {
         let bit23 = self.value.get_bit(23);
         let bit29 = self.value.get_bit(29);
         let bit31 = self.value.get_bit(31);
         self.value = data;
         self.value.set_bit(23, bit23);
         self.value.set_bit(29, bit29 | self.value.get_bit(29));
+        self.value.set_bit(Self::KEY2_APPLY_SEED_OFFSET, false); // write-only
 
         if self.value.get_bit(31) && !bit31 {
             self.words_read = 0;
@@ -36,7 +43,29 @@ impl RomCtrl {
             } / 4;
 
             self.set_data_word_ready(false);
+
+            if self.words_to_read == 0 {
+                self.value.set_bit(31, false);
+                self.just_finished = true;
+            }
+
+            return true;
         }
+
+        false
+    }
+
+    pub fn get_key2_data(&self) -> bool {
+        self.value.get_bit(Self::KEY2_DATA_OFFSET)
+    }
+
+    pub fn get_key2_command(&self) -> bool {
+        self.value.get_bit(Self::KEY2_COMMAND_OFFSET)
+    }
+
+    // length of the current transfer in bytes
+    pub fn get_transfer_length(&self) -> usize {
+        self.words_to_read as usize * 4
     }
 
     // data-word status
@@ -44,6 +73,15 @@ impl RomCtrl {
         self.value.set_bit(23, value);
     }
 
+    pub fn get_data_word_ready(&self) -> bool {
+        self.value.get_bit(23)
+    }
+
+    // a new word became ready, cartridge DMAs transfer on this
+    pub fn take_dma_request(&mut self) -> bool {
+        std::mem::take(&mut self.dma_requested)
+    }
+
     // block start/status
     pub fn get_block_status(&self) -> bool {
         self.value.get_bit(31)
@@ -67,7 +105,10 @@ impl RomCtrl {
     pub fn clock(&mut self, interrupts: &mut Interrupts) {
         self.cycles = self.cycles.saturating_sub(1);
 
-        self.set_data_word_ready(self.get_block_status() && self.cycles == 0);
+        let was_ready = self.get_data_word_ready();
+        let ready = self.get_block_status() && self.cycles == 0;
+        self.set_data_word_ready(ready);
+        self.dma_requested |= ready && !was_ready;
 
         interrupts
             .f
@@ -0,0 +1,174 @@
+use crate::nds::{logger, Bits};
+
+use super::{key1::Key1, key2::Key2};
+
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum CardMode {
+    #[default]
+    Raw, // unencrypted, straight after power on
+    Key1,     // secure area loading, commands are Blowfish encrypted
+    MainData, // normal reads, everything goes through KEY2
+}
+
+// the cartridge's side of the ROM protocol, what the chip itself would do with a command
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct Card {
+    pub mode: CardMode,
+    pub key2: Key2,
+    #[serde(skip)]
+    pub key1: Option<Key1>,
+}
+
+pub struct CardContext<'a> {
+    pub rom: &'a [u8],
+    pub key1_table: &'a [u8],
+    pub chip_id: u32,
+    pub key2_command: bool,
+    pub key2_data: bool,
+}
+
+impl Card {
+    // puts the card in the state the BIOS leaves it in, for direct boot
+    pub fn direct_boot(&mut self, key2: Key2) {
+        self.mode = CardMode::MainData;
+        self.key2 = key2;
+        self.key1 = None;
+    }
+
+    pub fn command(&mut self, command: u64, len: usize, ctx: CardContext) -> Vec<u8> {
+        let mut command_bytes = command.to_be_bytes();
+        if self.mode != CardMode::Raw && ctx.key2_command {
+            self.key2.process_slice(&mut command_bytes);
+        }
+        let command = u64::from_be_bytes(command_bytes);
+
+        let mut data = match self.mode {
+            CardMode::Raw => self.raw_command(command, len, &ctx),
+            CardMode::Key1 => self.key1_command(command, len, &ctx),
+            CardMode::MainData => self.main_data_command(command, len, &ctx),
+        };
+
+        if self.mode != CardMode::Raw && ctx.key2_data {
+            self.key2.process_slice(&mut data);
+        }
+
+        data
+    }
+
+    fn raw_command(&mut self, command: u64, len: usize, ctx: &CardContext) -> Vec<u8> {
+        match command.get_bits(56, 63) {
+            0x9F => vec![0xFF; len], // dummy
+            0x00 => (0..len)
+                .map(|i| *ctx.rom.get(i & 0xFFF).unwrap_or(&0xFF))
+                .collect(), // header, mirrored every 4KB
+            0x90 => Self::chip_id_data(ctx.chip_id, len),
+            0x3C => {
+                if self.init_key1(ctx) {
+                    self.mode = CardMode::Key1;
+                }
+                vec![0xFF; len]
+            }
+            _ => {
+                logger::warn_once(
+                    logger::LogSource::Cart,
+                    format!("Unknown raw cartridge command {:#018X}", command),
+                );
+                vec![0xFF; len]
+            }
+        }
+    }
+
+    fn key1_command(&mut self, command: u64, len: usize, ctx: &CardContext) -> Vec<u8> {
+        // the key isn't kept in save states, so it may need to be rebuilt
+        if self.key1.is_none() {
+            self.init_key1(ctx);
+        }
+        let Some(key1) = &self.key1 else {
+            return vec![0xFF; len];
+        };
+
+        let mut words = [command as u32, (command >> 32) as u32];
+        key1.decrypt_64bit(&mut words);
+        let command = ((words[1] as u64) << 32) | words[0] as u64;
+
+        match command.get_bits(60, 63) {
+            0x4 => {
+                // activate KEY2, 4llllmmmnnnkkkkk
+                let mmmnnn = command.get_bits(20, 43) as u32;
+                let seed0 = Key2::seed0(mmmnnn, *ctx.rom.get(0x13).unwrap_or(&0));
+                self.key2 = Key2::new(seed0, Key2::SEED1);
+                vec![0xFF; len]
+            }
+            0x1 => Self::chip_id_data(ctx.chip_id, len),
+            0x2 => {
+                // secure area block, 2bbbbiiijjjkkkkk
+                let address = command.get_bits(44, 59) as usize * 0x1000;
+                (0..len)
+                    .map(|i| *ctx.rom.get(address + (i & 0xFFF)).unwrap_or(&0xFF))
+                    .collect()
+            }
+            0xA => {
+                self.mode = CardMode::MainData;
+                self.key1 = None;
+                vec![0xFF; len]
+            }
+            _ => {
+                logger::warn_once(
+                    logger::LogSource::Cart,
+                    format!("Unknown KEY1 cartridge command {:#018X}", command),
+                );
+                vec![0xFF; len]
+            }
+        }
+    }
+
+    fn main_data_command(&mut self, command: u64, len: usize, ctx: &CardContext) -> Vec<u8> {
+        match command.get_bits(56, 63) {
+            0xB7 => {
+                let address = command.get_bits(24, 55) as usize;
+                let mask = ctx.rom.len().next_power_of_two() - 1;
+                (0..len)
+                    .map(|i| {
+                        let mut address = (address + i) & mask;
+                        if address < 0x8000 {
+                            // the secure area can't be read in this mode
+                            address = 0x8000 + (address & 0x1FF);
+                        }
+                        *ctx.rom.get(address).unwrap_or(&0xFF)
+                    })
+                    .collect()
+            }
+            0xB8 => Self::chip_id_data(ctx.chip_id, len),
+            _ => {
+                logger::warn_once(
+                    logger::LogSource::Cart,
+                    format!("Unknown KEY2 cartridge command {:#018X}", command),
+                );
+                vec![0xFF; len]
+            }
+        }
+    }
+
+    fn init_key1(&mut self, ctx: &CardContext) -> bool {
+        if ctx.key1_table.len() != Key1::TABLE_END - Key1::TABLE_START {
+            logger::error(
+                logger::LogSource::Cart,
+                "KEY1 requested without an ARM7 BIOS loaded",
+            );
+            return false;
+        }
+
+        let idcode = Key1::idcode(ctx.rom);
+        self.key1 = Some(Key1::new(ctx.key1_table, idcode, 2, 8));
+        true
+    }
+
+    fn chip_id_data(chip_id: u32, len: usize) -> Vec<u8> {
+        chip_id
+            .to_le_bytes()
+            .into_iter()
+            .cycle()
+            .take(len)
+            .collect()
+    }
+}
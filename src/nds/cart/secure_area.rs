@@ -0,0 +1,221 @@
+use std::fmt::Display;
+
+use crate::nds::{logger, Bytes};
+
+use super::key1::Key1;
+
+// the first 2KB of the ARM9 binary, which retail cartridges hand out KEY1 encrypted
+// dumps come either way, so we convert it to whatever the boot method needs
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SecureAreaState {
+    #[default]
+    None, // homebrew, the ARM9 binary doesn't start in the secure area
+    Decrypted,
+    Encrypted,
+}
+
+impl Display for SecureAreaState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecureAreaState::None => write!(f, "None"),
+            SecureAreaState::Decrypted => write!(f, "Decrypted"),
+            SecureAreaState::Encrypted => write!(f, "Encrypted"),
+        }
+    }
+}
+
+const START: usize = 0x4000;
+const END: usize = 0x8000;
+const ENCRYPTED_SIZE: usize = 0x800;
+
+// what a decrypted secure area starts with
+const MARKER: u32 = 0xE7FFDEFF;
+// what the first 8 bytes decrypt to, before they get replaced with the marker
+const ENCRYPTED_ID: &[u8; 8] = b"encryObj";
+
+// without the KEY1 table we can only guess, homebrew usually starts its ARM9 binary at 0x4000 too
+pub fn detect(rom: &[u8], arm9_rom_offset: u32, key1_table: &[u8]) -> SecureAreaState {
+    let offset = arm9_rom_offset as usize;
+    if !(START..END).contains(&offset) {
+        return SecureAreaState::None;
+    }
+
+    let Some(id) = rom.get(offset..offset + 8) else {
+        return SecureAreaState::None;
+    };
+
+    let first = [id[0], id[1], id[2], id[3]].into_word();
+    let second = [id[4], id[5], id[6], id[7]].into_word();
+    if first == MARKER && second == MARKER {
+        return SecureAreaState::Decrypted;
+    }
+
+    if !key1_table.is_empty() {
+        let mut id = [id[0], id[1], id[2], id[3], id[4], id[5], id[6], id[7]];
+        decrypt_id(&mut id, Key1::idcode(rom), key1_table);
+        return match &id == ENCRYPTED_ID {
+            true => SecureAreaState::Encrypted,
+            false => SecureAreaState::None,
+        };
+    }
+
+    // encrypted data is pretty much random, plain ARM code almost always has the "always" condition
+    let is_code = |word: u32| word >> 28 == 0xE;
+    if (first == 0 && second == 0) || (is_code(first) && is_code(second)) {
+        SecureAreaState::None
+    } else {
+        SecureAreaState::Encrypted
+    }
+}
+
+// the ID gets an extra pass at level 2 on top of the level 3 everything else gets
+fn decrypt_id(id: &mut [u8], idcode: u32, key1_table: &[u8]) {
+    let key1 = Key1::new(key1_table, idcode, 2, 8);
+    process_block(id, |data| key1.decrypt_64bit(data));
+    let key1 = Key1::new(key1_table, idcode, 3, 8);
+    process_block(id, |data| key1.decrypt_64bit(data));
+}
+
+// for direct boot, as nothing is going to decrypt it for us
+pub fn decrypt(rom: &mut [u8], arm9_rom_offset: u32, key1_table: &[u8]) -> bool {
+    let offset = arm9_rom_offset as usize;
+    let idcode = Key1::idcode(rom);
+    let Some(area) = rom.get_mut(offset..offset + ENCRYPTED_SIZE) else {
+        return false;
+    };
+    let mut area = area.to_vec();
+
+    decrypt_id(&mut area[0..8], idcode, key1_table);
+
+    let key1 = Key1::new(key1_table, idcode, 3, 8);
+    area[8..]
+        .chunks_exact_mut(8)
+        .for_each(|block| process_block(block, |data| key1.decrypt_64bit(data)));
+
+    if &area[0..8] != ENCRYPTED_ID {
+        logger::error(
+            logger::LogSource::Cart,
+            "Failed to decrypt the secure area, the ROM may be corrupt",
+        );
+        return false;
+    }
+
+    area[0..4].copy_from_slice(&MARKER.to_le_bytes());
+    area[4..8].copy_from_slice(&MARKER.to_le_bytes());
+    rom[offset..offset + ENCRYPTED_SIZE].copy_from_slice(&area);
+    true
+}
+
+// for firmware boot, the BIOS expects the data the cartridge would really give it
+pub fn encrypt(rom: &mut [u8], arm9_rom_offset: u32, key1_table: &[u8]) -> bool {
+    let offset = arm9_rom_offset as usize;
+    let idcode = Key1::idcode(rom);
+    let Some(area) = rom.get_mut(offset..offset + ENCRYPTED_SIZE) else {
+        return false;
+    };
+
+    area[0..8].copy_from_slice(ENCRYPTED_ID);
+
+    let key1 = Key1::new(key1_table, idcode, 3, 8);
+    area.chunks_exact_mut(8)
+        .for_each(|block| process_block(block, |data| key1.encrypt_64bit(data)));
+
+    let key1 = Key1::new(key1_table, idcode, 2, 8);
+    process_block(&mut area[0..8], |data| key1.encrypt_64bit(data));
+    true
+}
+
+fn process_block(block: &mut [u8], f: impl Fn(&mut [u32; 2])) {
+    let mut data = [
+        [block[0], block[1], block[2], block[3]].into_word(),
+        [block[4], block[5], block[6], block[7]].into_word(),
+    ];
+    f(&mut data);
+    block[0..4].copy_from_slice(&data[0].to_le_bytes());
+    block[4..8].copy_from_slice(&data[1].to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> Vec<u8> {
+        (Key1::TABLE_START..Key1::TABLE_END)
+            .map(|i| (i as u32).wrapping_mul(2_654_435_761).to_be_bytes()[0])
+            .collect()
+    }
+
+    // a decrypted dump, game code ADAE, with the ARM9 binary at the start of the secure area
+    fn decrypted_rom() -> Vec<u8> {
+        let mut rom = vec![0; END];
+        rom[0x0C..0x10].copy_from_slice(b"ADAE");
+        rom[START..START + 4].copy_from_slice(&MARKER.to_le_bytes());
+        rom[START + 4..START + 8].copy_from_slice(&MARKER.to_le_bytes());
+        for (i, byte) in rom[START + 8..].iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        rom
+    }
+
+    #[test]
+    fn encrypt_and_decrypt_round_trip() {
+        let table = table();
+        let original = decrypted_rom();
+        let mut rom = original.clone();
+
+        assert!(encrypt(&mut rom, START as u32, &table));
+        assert_ne!(
+            rom[START..START + ENCRYPTED_SIZE],
+            original[START..START + ENCRYPTED_SIZE]
+        );
+        // only the first 2KB is encrypted
+        assert_eq!(
+            rom[START + ENCRYPTED_SIZE..],
+            original[START + ENCRYPTED_SIZE..]
+        );
+        assert_eq!(
+            detect(&rom, START as u32, &table),
+            SecureAreaState::Encrypted
+        );
+
+        assert!(decrypt(&mut rom, START as u32, &table));
+        assert_eq!(rom, original);
+        assert_eq!(
+            detect(&rom, START as u32, &table),
+            SecureAreaState::Decrypted
+        );
+    }
+
+    #[test]
+    fn the_id_has_to_decrypt_to_encryobj() {
+        let table = table();
+        let mut rom = decrypted_rom();
+        encrypt(&mut rom, START as u32, &table);
+
+        // the same bytes under another game code are just noise
+        rom[0x0C..0x10].copy_from_slice(b"ADAJ");
+        assert_eq!(detect(&rom, START as u32, &table), SecureAreaState::None);
+        assert!(!decrypt(&mut rom, START as u32, &table));
+    }
+
+    #[test]
+    fn guesses_without_a_table() {
+        let mut rom = decrypted_rom();
+        assert_eq!(detect(&rom, START as u32, &[]), SecureAreaState::Decrypted);
+        // homebrew ARM9 binaries often live somewhere else entirely
+        assert_eq!(detect(&rom, 0x200, &[]), SecureAreaState::None);
+
+        rom[START..START + 8].fill(0);
+        assert_eq!(detect(&rom, START as u32, &[]), SecureAreaState::None);
+
+        // b 0x4100, mov r0, #0
+        rom[START..START + 4].copy_from_slice(&0xEA00_003Eu32.to_le_bytes());
+        rom[START + 4..START + 8].copy_from_slice(&0xE3A0_0000u32.to_le_bytes());
+        assert_eq!(detect(&rom, START as u32, &[]), SecureAreaState::None);
+
+        let table = table();
+        let mut rom = decrypted_rom();
+        encrypt(&mut rom, START as u32, &table);
+        assert_eq!(detect(&rom, START as u32, &[]), SecureAreaState::Encrypted);
+    }
+}
@@ -1,8 +1,23 @@
 mod models;
+mod timing;
 
+use bitflags::bitflags;
 use models::DmaChannel;
 
-use super::{arm::ArmKind, bus::BusTrait, shared::Shared, Bits, Bytes};
+use super::{bus::BusTrait, shared::Shared, Bits, Bytes};
+
+bitflags! {
+    // events that start DMAs which aren't immediate
+    #[derive(Clone, Copy, Default, PartialEq)]
+    pub struct DmaTriggers: u8 {
+        const VBLANK              = 1 << 0; // start of vblank
+        const HBLANK              = 1 << 1; // start of hblank, visible lines only
+        const DISPLAY_START       = 1 << 2; // start of the frame
+        const MAIN_MEMORY_DISPLAY = 1 << 3; // the display FIFO wants more data
+        const CARTRIDGE           = 1 << 4; // a word is ready in 0x04100010
+        const GX_FIFO             = 1 << 5; // the GX FIFO dropped below half full
+    }
+}
 
 // TODO: maybe merge dma9 and dma7 into this struct
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -115,25 +130,35 @@ impl Dma {
         success
     }
 
-    pub fn check_immediately<Bus: BusTrait>(&mut self, bus: &mut Bus, shared: &mut Shared) {
+    // both of these return how many bus cycles the CPU should be stalled for
+    pub fn check_immediately<Bus: BusTrait>(&mut self, bus: &mut Bus, shared: &mut Shared) -> u32 {
+        let mut cycles = 0;
         for channel in self.channel.iter_mut() {
-            let enabled = channel.dmacnt.get_dma_enable();
-            let start_timing = if Bus::KIND == ArmKind::Arm9 {
-                channel.dmacnt.get_dma9_start_timing()
-            } else {
-                channel.dmacnt.get_dma7_start_timing()
-            };
-
-            let run_immediately = start_timing == 0;
-
-            // TODO: does "paused during V-Blank" mean we have to pause mid-transfer?
-            let run_hblank = start_timing == 2
-                && shared.gpus.dispstat.get_hblank_flag()
-                && !shared.gpus.dispstat.get_vblank_flag();
-
-            if enabled && (run_immediately || run_hblank) {
-                channel.run(bus, shared);
+            if std::mem::take(&mut channel.rearm_gx_fifo) {
+                shared.gpus.gpu3d.rearm_dma();
+            }
+            if channel.dmacnt.get_dma_enable() && channel.get_start_timing::<Bus>() == 0 {
+                cycles += channel.run(bus, shared);
             }
         }
+
+        cycles
+    }
+
+    pub fn trigger<Bus: BusTrait>(
+        &mut self,
+        bus: &mut Bus,
+        shared: &mut Shared,
+        triggers: DmaTriggers,
+    ) -> u32 {
+        let mut cycles = 0;
+        for channel in self.channel.iter_mut() {
+            if channel.dmacnt.get_dma_enable() && triggers.intersects(channel.get_trigger::<Bus>())
+            {
+                cycles += channel.run(bus, shared);
+            }
+        }
+
+        cycles
     }
 }
@@ -1,9 +1,10 @@
 use crate::nds::{arm::ArmKind, bus::BusTrait, logger, shared::Shared, Bits};
 
-// TODO: cycle timing
+use super::{timing, DmaTriggers};
+
+const GX_FIFO_UNITS: u32 = 112;
+
 // TODO: GamePak DRQ
-// TODO: special start timing
-// TODO: IRQ upon end of word count
 // TODO: maybe some edge cases? idk read gbatek lmao
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -29,6 +30,11 @@ pub struct DmaChannel {
     internal_sad: u32,
     internal_dad: u32,
     internal_cnt_l: u32,
+
+    // the GX FIFO only asks for DMA when it drops below half full
+    // so a channel that starts (or stops a burst) while it's already there has to ask again
+    #[serde(skip)]
+    pub rearm_gx_fifo: bool,
 }
 
 impl DmaChannel {
@@ -44,6 +50,8 @@ impl DmaChannel {
             internal_sad: 0,
             internal_dad: 0,
             internal_cnt_l: 0,
+
+            rearm_gx_fifo: false,
         }
     }
 
@@ -76,27 +84,21 @@ impl DmaChannel {
             return;
         }
 
-        let start_timing = if Bus::KIND == ArmKind::Arm9 {
-            self.dmacnt.get_dma9_start_timing()
-        } else {
-            self.dmacnt.get_dma7_start_timing()
-        };
-        match start_timing {
-            0 | 2 => {}
-            _ => {
-                logger::error(
-                    self.log_source::<Bus>(),
-                    format!(
-                        "DMA{} has start timing {} which is not implemented",
-                        self.index, start_timing
-                    ),
-                );
-            }
+        let start_timing = self.get_start_timing::<Bus>();
+        if start_timing != 0 && self.get_trigger::<Bus>().is_empty() {
+            logger::error(
+                self.log_source::<Bus>(),
+                format!(
+                    "DMA{} has start timing {} which is not implemented",
+                    self.index, start_timing
+                ),
+            );
         }
 
         self.internal_sad = self.dmasad;
         self.internal_dad = self.dmadad;
         self.internal_cnt_l = self.get_word_count::<Bus>();
+        self.rearm_gx_fifo = self.get_trigger::<Bus>() == DmaTriggers::GX_FIFO;
         if self.internal_cnt_l == 0 {
             logger::error(
                 self.log_source::<Bus>(),
@@ -108,6 +110,7 @@ impl DmaChannel {
         }
     }
 
+    // returns how many bus cycles the transfer took
     pub fn run<Bus: BusTrait>(&mut self, bus: &mut Bus, shared: &mut Shared) -> u32 {
         logger::debug(
             self.log_source::<Bus>(),
@@ -122,13 +125,32 @@ impl DmaChannel {
         );
         let is_32bit_transfer = self.dmacnt.get_dma_transfer_type();
         let offset_amount = if is_32bit_transfer { 4 } else { 2 };
+        let mut cycles = timing::STARTUP_CYCLES;
+        let mut sequential = false;
+        // the GX FIFO is fed 112 words at a time, the rest waits for it to drain again
+        let mut units_left = if self.get_trigger::<Bus>() == DmaTriggers::GX_FIFO {
+            GX_FIFO_UNITS
+        } else {
+            u32::MAX
+        };
         loop {
             if self.internal_cnt_l == 0 {
-                bus.get_interrupts().f.set_dma(self.index, true);
+                if self.dmacnt.get_irq_upon_end() {
+                    bus.get_interrupts().f.set_dma(self.index, true);
+                }
                 break;
             }
+            if units_left == 0 {
+                shared.gpus.gpu3d.rearm_dma();
+                return cycles;
+            }
+            units_left -= 1;
             self.internal_cnt_l -= 1;
 
+            cycles += timing::access_cycles(self.internal_sad, is_32bit_transfer, sequential);
+            cycles += timing::access_cycles(self.internal_dad, is_32bit_transfer, sequential);
+            sequential = true;
+
             // TODO: maybe in the future, the match statements can be done better?
             // for now this is probably fine, but if the DMA can truly access all of its own registers
             // then i need to look into a better solution
@@ -166,7 +188,8 @@ impl DmaChannel {
             }
         }
 
-        if !self.dmacnt.get_dma_repeat() {
+        // immediate transfers can't repeat, there'd be nothing to wait for
+        if !self.dmacnt.get_dma_repeat() || self.get_start_timing::<Bus>() == 0 {
             self.dmacnt.set_dma_enable(false);
         } else {
             self.internal_cnt_l = self.get_word_count::<Bus>();
@@ -175,7 +198,29 @@ impl DmaChannel {
             }
         }
 
-        1 // TODO: this is wrong
+        cycles
+    }
+
+    pub fn get_start_timing<Bus: BusTrait>(&self) -> u32 {
+        if Bus::KIND == ArmKind::Arm9 {
+            self.dmacnt.get_dma9_start_timing()
+        } else {
+            self.dmacnt.get_dma7_start_timing()
+        }
+    }
+
+    // empty for immediate transfers, and the start timings we don't support
+    pub fn get_trigger<Bus: BusTrait>(&self) -> DmaTriggers {
+        match (Bus::KIND, self.get_start_timing::<Bus>()) {
+            (_, 1) => DmaTriggers::VBLANK,
+            (ArmKind::Arm9, 2) => DmaTriggers::HBLANK,
+            (ArmKind::Arm9, 3) => DmaTriggers::DISPLAY_START,
+            (ArmKind::Arm9, 4) => DmaTriggers::MAIN_MEMORY_DISPLAY,
+            (ArmKind::Arm9, 5) => DmaTriggers::CARTRIDGE,
+            (ArmKind::Arm9, 7) => DmaTriggers::GX_FIFO,
+            (ArmKind::Arm7, 2) => DmaTriggers::CARTRIDGE,
+            _ => DmaTriggers::empty(), // GBA slot and wifi
+        }
     }
 
     fn get_word_count<Bus: BusTrait>(&self) -> u32 {
@@ -214,6 +259,7 @@ impl DmaCnt {
     const SOURCE_ADDR_CONTROL_END: u32 = 16 + 8;
     const DMA_REPEAT_OFFSET: u32 = 16 + 9;
     const DMA_TRANSFER_TYPE_OFFSET: u32 = 16 + 10;
+    const IRQ_UPON_END_OFFSET: u32 = 16 + 14;
 
     const DMA9_START_TIMING_START: u32 = 16 + 11;
     const DMA9_START_TIMING_END: u32 = 16 + 13;
@@ -272,6 +318,10 @@ impl DmaCnt {
             .get_bits(Self::DMA7_START_TIMING_START, Self::DMA7_START_TIMING_END)
     }
 
+    pub fn get_irq_upon_end(&self) -> bool {
+        self.0.get_bit(Self::IRQ_UPON_END_OFFSET)
+    }
+
     pub fn get_dma_enable(&self) -> bool {
         self.0.get_bit(Self::DMA_ENABLE_OFFSET)
     }
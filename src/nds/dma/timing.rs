@@ -0,0 +1,56 @@
+// DMA access timings, in 33MHz bus cycles
+// the first unit of a transfer is non-sequential, everything after that is sequential
+// 32 bit accesses on a 16 bit bus are split into two halfword accesses
+
+struct Region {
+    width: u32, // in bits
+    n: u32,     // non-sequential access
+    s: u32,     // sequential access
+}
+
+// a DMA takes 2 internal cycles to get going
+pub const STARTUP_CYCLES: u32 = 2;
+
+fn region(addr: u32) -> Region {
+    match addr >> 24 {
+        0x02 => Region {
+            width: 16,
+            n: 9,
+            s: 1,
+        }, // main memory
+        0x03 | 0x04 | 0x07 => Region {
+            width: 32,
+            n: 1,
+            s: 1,
+        }, // shared/arm7 wram, io, oam
+        0x05 | 0x06 => Region {
+            width: 16,
+            n: 1,
+            s: 1,
+        }, // palettes, vram
+        0x08..=0x09 => Region {
+            width: 16,
+            n: 10,
+            s: 6,
+        }, // gba slot rom, default waitstates
+        0x0A => Region {
+            width: 8,
+            n: 10,
+            s: 10,
+        }, // gba slot ram
+        _ => Region {
+            width: 32,
+            n: 1,
+            s: 1,
+        }, // bios, tcm and whatever else
+    }
+}
+
+pub fn access_cycles(addr: u32, is_32bit: bool, sequential: bool) -> u32 {
+    let region = region(addr);
+    let bits: u32 = if is_32bit { 32 } else { 16 };
+    let units = bits.div_ceil(region.width);
+    let first = if sequential { region.s } else { region.n };
+
+    first + (units - 1) * region.s
+}
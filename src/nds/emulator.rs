@@ -1,17 +1,27 @@
 use std::sync::atomic::AtomicBool;
 
-use crate::ui::windows::debug::arm::disassembler::ArmDisassemblerWindow;
-
 use super::{
     arm::{Arm, ArmBool, ArmInternalRW},
     bus::{bus7::Bus7, bus9::Bus9, BusTrait},
-    dma::Dma,
+    dma::{Dma, DmaTriggers},
     logger::ONCE_LOGS,
     shared::Shared,
 };
 
 static IS_EMULATOR_RUNNING: AtomicBool = AtomicBool::new(false);
 
+// lets whatever is running the emulator pause it between instructions, like the debugger's breakpoints
+pub trait Breakpoints {
+    fn check_breakpoints<const ARM_BOOL: bool>(&mut self, emulator: &mut Emulator) -> bool;
+}
+
+// nothing to stop at
+impl Breakpoints for () {
+    fn check_breakpoints<const ARM_BOOL: bool>(&mut self, _emulator: &mut Emulator) -> bool {
+        false
+    }
+}
+
 #[derive(PartialEq, serde::Deserialize, serde::Serialize)]
 pub enum CycleState {
     Arm9_1,
@@ -34,6 +44,9 @@ pub struct Emulator {
     pub shared: Shared,
 
     pub cycle_state: CycleState,
+
+    #[serde(skip)]
+    pub firmware_boot: bool, // boot through the BIOS and firmware instead of loading the binaries directly
 }
 
 impl Default for Emulator {
@@ -51,16 +64,18 @@ impl Default for Emulator {
             shared: Shared::default(),
 
             cycle_state: CycleState::Arm9_1,
+
+            firmware_boot: false,
         }
     }
 }
 
 impl Emulator {
-    pub fn load_rom(&mut self, rom: Vec<u8>) {
+    pub fn load_rom(&mut self, rom: Vec<u8>, rom_path: Option<String>) {
         self.reset(false);
 
         let shared = &mut self.shared;
-        let success = shared.cart.load(rom);
+        let success = shared.cart.load(rom, rom_path);
         if !success {
             return;
         }
@@ -69,6 +84,18 @@ impl Emulator {
     }
 
     pub fn load_binary(&mut self) {
+        self.shared.cart.set_key1_table(&self.bus7.bios);
+        self.shared.cart.prepare_secure_area(self.firmware_boot);
+
+        if self.firmware_boot {
+            // the BIOS talks to the cartridge and loads everything itself
+            self.arm9.r[15] = 0xFFFF0000;
+            self.arm7.r[15] = 0x00000000;
+            return;
+        }
+
+        self.shared.cart.direct_boot();
+
         let arm9_load_address = self.shared.cart.metadata.arm9_load_address;
         let arm9_bin = self.shared.cart.rom[self.shared.cart.metadata.arm9_rom_offset as usize
             ..(self.shared.cart.metadata.arm9_rom_offset + self.shared.cart.metadata.arm9_size)
@@ -107,14 +134,11 @@ impl Emulator {
         );
 
         // write chip id into psram
-        self.bus9
-            .write_word(shared, &mut None, 0x027FF800, 0x00000FC2);
-        self.bus9
-            .write_word(shared, &mut None, 0x027FF804, 0x00000FC2);
-        self.bus9
-            .write_word(shared, &mut None, 0x027FFC00, 0x00000FC2);
-        self.bus9
-            .write_word(shared, &mut None, 0x027FFC04, 0x00000FC2);
+        let chip_id = shared.cart.chip_id();
+        self.bus9.write_word(shared, &mut None, 0x027FF800, chip_id);
+        self.bus9.write_word(shared, &mut None, 0x027FF804, chip_id);
+        self.bus9.write_word(shared, &mut None, 0x027FFC00, chip_id);
+        self.bus9.write_word(shared, &mut None, 0x027FFC04, chip_id);
 
         // thanks @atem.zip
         self.bus9.write_byte(shared, &mut None, 0x04000247, 0x03);
@@ -129,14 +153,16 @@ impl Emulator {
         self.bus9
             .write_halfword(shared, &mut None, 0x027FFC40, 0x0001);
 
-        self.bus9.write_bulk(
-            shared,
-            &mut None,
-            0x027FFC80,
-            self.bus7.firmware[0x3FE00..0x3FE70].into(),
-        );
+        // without a firmware there's nothing to copy, so the game gets the settings of a freshly set up DS
+        let user_settings = match self.bus7.firmware.get(0x3FE00..0x3FE70) {
+            Some(user_settings) => user_settings.to_vec(),
+            None => default_user_settings(),
+        };
+        self.bus9
+            .write_bulk(shared, &mut None, 0x027FFC80, user_settings);
     }
 
+    #[cfg(feature = "ui")]
     pub fn load_state(&mut self, emulator: Emulator) {
         self.arm9 = emulator.arm9;
         self.arm7 = emulator.arm7;
@@ -181,22 +207,82 @@ impl Emulator {
 
     pub fn pause(&mut self) {
         set_emulator_running(false);
+        self.shared.cart.backup.flush();
     }
 
     pub fn is_running(&self) -> bool {
         is_emulator_running()
     }
 
+    fn clock_cart(&mut self) {
+        self.shared.cart.clock(&mut self.bus9, &mut self.bus7);
+
+        if self.shared.cart.romctrl.take_dma_request() {
+            if self.shared.cart.exmemcnt.get_nds_slot_access_rights() {
+                let cycles =
+                    self.dma7
+                        .trigger(&mut self.bus7, &mut self.shared, DmaTriggers::CARTRIDGE);
+                self.arm7.stall(cycles);
+            } else {
+                let cycles =
+                    self.dma9
+                        .trigger(&mut self.bus9, &mut self.shared, DmaTriggers::CARTRIDGE);
+                self.arm9.stall(cycles);
+            }
+        }
+    }
+
+    fn clock_spu(&mut self, arm7_cycles: u32) {
+        if !self.bus7.spu.clock(arm7_cycles) {
+            return;
+        }
+
+        // the channels and capture units go through the ARM7's bus, which the SPU lives in
+        let mut spu = std::mem::take(&mut self.bus7.spu);
+        spu.generate_samples(&mut self.bus7, &mut self.shared);
+        self.bus7.spu = spu;
+    }
+
+    fn run_dma_triggers(&mut self) {
+        let triggers = std::mem::take(&mut self.shared.dma_triggers);
+        if triggers.is_empty() {
+            return;
+        }
+
+        let cycles = self
+            .dma9
+            .trigger(&mut self.bus9, &mut self.shared, triggers);
+        self.arm9.stall(cycles);
+        let cycles = self
+            .dma7
+            .trigger(&mut self.bus7, &mut self.shared, triggers);
+        self.arm7.stall(cycles);
+    }
+
+    fn check_dma_immediately(&mut self) {
+        let cycles = self
+            .dma9
+            .check_immediately(&mut self.bus9, &mut self.shared);
+        self.arm9.stall(cycles);
+        let cycles = self
+            .dma7
+            .check_immediately(&mut self.bus7, &mut self.shared);
+        self.arm7.stall(cycles);
+    }
+
     // NOTE: do not use this in a loop, it is slow
+    #[cfg(feature = "ui")]
     pub fn step(&mut self) -> u32 {
         let cycles = match self.cycle_state {
             CycleState::Arm9_1 | CycleState::Arm9_2 => {
                 let cycles = self
                     .arm9
                     .clock(&mut self.bus9, &mut self.shared, &mut self.dma9);
-                self.dma9
+                let dma_cycles = self
+                    .dma9
                     .check_immediately(&mut self.bus9, &mut self.shared);
-                self.shared.cart.clock(&mut self.bus9, &mut self.bus7);
+                self.arm9.stall(dma_cycles);
+                self.clock_cart();
 
                 cycles
             }
@@ -204,12 +290,23 @@ impl Emulator {
                 let cycles = self
                     .arm7
                     .clock(&mut self.bus7, &mut self.shared, &mut self.dma7);
-                self.shared.gpus.clock(&mut self.bus9, &mut self.bus7);
-                self.dma7
+                self.shared.gpus.clock(
+                    &mut self.bus9,
+                    &mut self.bus7,
+                    &mut self.shared.dma_triggers,
+                );
+                self.run_dma_triggers();
+                let dma_cycles = self
+                    .dma7
                     .check_immediately(&mut self.bus7, &mut self.shared);
+                self.arm7.stall(dma_cycles);
 
                 self.bus9.timers.clock(cycles, &mut self.bus9.interrupts);
                 self.bus7.timers.clock(cycles, &mut self.bus7.interrupts);
+                self.bus7
+                    .rtc
+                    .clock(cycles, &mut self.bus7.interrupts, self.bus7.rcnt);
+                self.clock_spu(cycles);
                 self.bus9.div.clock(cycles);
                 self.bus9.sqrt.clock();
 
@@ -237,7 +334,7 @@ impl Emulator {
         &mut self,
         target_cycles_arm9: u64,
         last_cycle_arm7_discrepency: i32,
-        disassembler_windows: (&mut ArmDisassemblerWindow, &mut ArmDisassemblerWindow),
+        breakpoints: &mut impl Breakpoints,
     ) -> (u64, i32, u64) {
         let mut cycles_ran_arm9 = 0;
         let mut cycles_ran_arm7 = last_cycle_arm7_discrepency;
@@ -278,23 +375,27 @@ impl Emulator {
                 self.bus7
                     .timers
                     .clock(arm7_cycles, &mut self.bus7.interrupts);
+                self.bus7
+                    .rtc
+                    .clock(arm7_cycles, &mut self.bus7.interrupts, self.bus7.rcnt);
+                self.clock_spu(arm7_cycles);
                 self.bus9.div.clock(arm7_cycles);
                 self.bus9.sqrt.clock();
 
-                disassembler_windows
-                    .1
-                    .check_breakpoints::<{ ArmBool::ARM7 }>(self);
+                breakpoints.check_breakpoints::<{ ArmBool::ARM7 }>(self);
             }
 
             while cycles_ran_gpu < target_cycles_gpu {
-                self.shared.gpus.clock(&mut self.bus9, &mut self.bus7);
+                self.shared.gpus.clock(
+                    &mut self.bus9,
+                    &mut self.bus7,
+                    &mut self.shared.dma_triggers,
+                );
                 cycles_ran_gpu += 1;
             }
-
-            self.dma9
-                .check_immediately(&mut self.bus9, &mut self.shared);
-            self.dma7
-                .check_immediately(&mut self.bus7, &mut self.shared);
+            // any DMA cycles are paid for the next time each CPU is clocked
+            self.run_dma_triggers();
+            self.check_dma_immediately();
 
             self.shared
                 .ipcsync
@@ -303,17 +404,42 @@ impl Emulator {
                 .ipcfifo
                 .update_interrupts(&mut self.bus9.interrupts, &mut self.bus7.interrupts);
 
-            self.shared.cart.clock(&mut self.bus9, &mut self.bus7);
+            self.clock_cart();
 
-            disassembler_windows
-                .0
-                .check_breakpoints::<{ ArmBool::ARM9 }>(self);
+            breakpoints.check_breakpoints::<{ ArmBool::ARM9 }>(self);
         }
 
         (cycles_ran_arm9, cycles_ran_arm7, cycles_ran_gpu)
     }
 }
 
+// laid out the same as the firmware's user settings
+fn default_user_settings() -> Vec<u8> {
+    let mut settings = vec![0; 0x70];
+    settings[0x00] = 5; // version
+    settings[0x03] = 1; // birthday month
+    settings[0x04] = 1; // birthday day
+
+    let nickname = "Nitrous";
+    for (i, c) in nickname.encode_utf16().enumerate() {
+        settings[0x06 + i * 2..0x08 + i * 2].copy_from_slice(&c.to_le_bytes());
+    }
+    settings[0x1A] = nickname.len() as u8;
+
+    // touch screen calibration, 16 ADC steps a pixel
+    settings[0x58..0x5A].copy_from_slice(&0x0200u16.to_le_bytes());
+    settings[0x5A..0x5C].copy_from_slice(&0x0200u16.to_le_bytes());
+    settings[0x5C] = 0x20;
+    settings[0x5D] = 0x20;
+    settings[0x5E..0x60].copy_from_slice(&0x0E00u16.to_le_bytes());
+    settings[0x60..0x62].copy_from_slice(&0x0800u16.to_le_bytes());
+    settings[0x62] = 0xE0;
+    settings[0x63] = 0x80;
+
+    settings[0x64] = 1; // english
+    settings
+}
+
 pub fn is_emulator_running() -> bool {
     IS_EMULATOR_RUNNING.load(std::sync::atomic::Ordering::Relaxed)
 }
@@ -0,0 +1,105 @@
+use crate::nds::Bits;
+
+// 6 bits per channel, which is what the LCDs are actually sent
+pub type Rgb6 = [u8; 3];
+
+// how frontends want the 18 bit colours turned into 8 bit ones
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum ColourOutput {
+    Raw, // each channel is only shifted up, so white isn't quite white
+    #[default]
+    Accurate, // each channel is stretched over the full 8 bit range
+    LcdCorrected, // roughly what the colours look like on a DS Lite's screens
+}
+
+// one screen's worth of 18 bit pixels
+pub struct Framebuffer {
+    pixels: Vec<Rgb6>,
+}
+
+impl Default for Framebuffer {
+    fn default() -> Self {
+        Self {
+            pixels: vec![[0; 3]; Self::WIDTH * Self::HEIGHT],
+        }
+    }
+}
+
+impl Framebuffer {
+    pub const WIDTH: usize = 256;
+    pub const HEIGHT: usize = 192;
+
+    pub fn set_line(&mut self, line: usize, pixels: &[Rgb6]) {
+        self.pixels[line * Self::WIDTH..(line + 1) * Self::WIDTH].copy_from_slice(pixels);
+    }
+
+    // 8 bit RGBA, 4 bytes a pixel going left to right then top to bottom, alpha is always 255
+    pub fn to_rgba(&self, output: ColourOutput) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|&pixel| {
+                let [r, g, b] = rgb6_to_rgb8(pixel, output);
+                [r, g, b, 255]
+            })
+            .collect()
+    }
+}
+
+// the 2D engines expand their colours like this, so 0 stays black and 31 becomes full white
+pub fn bgr555_to_rgb6(color: u16) -> Rgb6 {
+    let expand = |c: u16| (c * 2 + (c != 0) as u16) as u8;
+    [
+        expand(color.get_bits(0, 4)),
+        expand(color.get_bits(5, 9)),
+        expand(color.get_bits(10, 14)),
+    ]
+}
+
+pub fn rgb6_to_bgr555(pixel: Rgb6) -> u16 {
+    let mut color = 0u16;
+    color.set_bits(0, 4, (pixel[0] >> 1) as u16);
+    color.set_bits(5, 9, (pixel[1] >> 1) as u16);
+    color.set_bits(10, 14, (pixel[2] >> 1) as u16);
+    color
+}
+
+// for anything that shows raw 2D colours, like the debug viewers
+#[cfg(feature = "ui")]
+pub fn bgr555_to_rgb8(color: u16) -> [u8; 3] {
+    rgb6_to_rgb8(bgr555_to_rgb6(color), ColourOutput::Accurate)
+}
+
+pub fn rgb6_to_rgb8(pixel: Rgb6, output: ColourOutput) -> [u8; 3] {
+    match output {
+        ColourOutput::Raw => pixel.map(|c| c << 2),
+        ColourOutput::Accurate => pixel.map(|c| (c << 2) | (c >> 4)),
+        ColourOutput::LcdCorrected => {
+            // each channel bleeds into the others a bit, the weights are out of 256 and each row adds up to 256
+            let [r, g, b] = pixel.map(|c| ((c << 2) | (c >> 4)) as i32);
+            let mix =
+                |wr: i32, wg: i32, wb: i32| ((wr * r + wg * g + wb * b) / 256).clamp(0, 255) as u8;
+            [mix(214, 69, -27), mix(26, 163, 67), mix(27, 45, 184)]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bgr555_to_rgb6, rgb6_to_bgr555};
+
+    #[test]
+    fn bgr555_expands_to_the_full_6_bit_range() {
+        assert_eq!(bgr555_to_rgb6(0x0000), [0, 0, 0]);
+        assert_eq!(bgr555_to_rgb6(0x7FFF), [63, 63, 63]);
+        assert_eq!(bgr555_to_rgb6(0x0001), [3, 0, 0]);
+        assert_eq!(bgr555_to_rgb6(0x7C00), [0, 0, 63]);
+        assert_eq!(bgr555_to_rgb6(0x8000 | 0x03E0), [0, 63, 0]);
+    }
+
+    #[test]
+    fn bgr555_round_trips() {
+        for color in 0..0x8000 {
+            assert_eq!(rgb6_to_bgr555(bgr555_to_rgb6(color)), color);
+        }
+    }
+}
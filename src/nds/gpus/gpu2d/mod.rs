@@ -1,6 +1,18 @@
-use models::{BGxCNT, BldAlpha, BldCnt, DispCnt, DisplayMode};
-
-use crate::nds::shared::Shared;
+use std::collections::VecDeque;
+
+use models::{
+    BGxCNT, BgAffine, BldAlpha, BldCnt, DispCapCnt, DispCnt, DisplayMode, MasterBright, Mosaic,
+    Windows,
+};
+
+use crate::nds::{
+    gpus::{
+        framebuffer::{bgr555_to_rgb6, Framebuffer, Rgb6},
+        gpu3d::rendering::Pixel3d,
+        vram::VramBanks,
+    },
+    logger,
+};
 
 pub mod models;
 pub mod rendering;
@@ -13,15 +25,27 @@ pub struct Gpu2d<const ENGINE_A: bool> {
     pub bgxcnt: [BGxCNT; 4],
 
     pub bgofs: [u32; 4],
+    pub bg_affine: [BgAffine; 2], // BG2 and BG3
+    pub windows: Windows,
+    pub mosaic: Mosaic,
     pub bldcnt: BldCnt,
     pub bldalpha: [BldAlpha; 2],
     pub bldy: [u8; 2],
+    pub master_bright: MasterBright,
+    pub dispcapcnt: DispCapCnt,      // engine A only
+    capturing: bool,                 // display capture only starts at the top of a frame
+    main_memory_fifo: VecDeque<u16>, // DISP_MMEM_FIFO, pixels waiting for main memory display
 
     pub palette: Vec<u8>,
     pub oam: Vec<u8>,
 
+    // drawn a line at a time as the frame goes
+    #[serde(skip)]
+    pub framebuffer: Framebuffer,
+    #[serde(skip)]
+    pub bg_layers: BackgroundResults, // each BG on its own, for the map viewer
+
     // custom stuff
-    pub mode_safety: bool,
     pub show_bgs: [bool; 4],
     pub show_objs: [bool; 4],
 }
@@ -33,14 +57,22 @@ impl<const ENGINE_A: bool> Default for Gpu2d<ENGINE_A> {
             bgxcnt: core::array::from_fn(|_| BGxCNT::default()),
 
             bgofs: [0; 4],
+            bg_affine: [BgAffine::default(); 2],
+            windows: Windows::default(),
+            mosaic: Mosaic::default(),
             bldcnt: BldCnt::default(),
             bldalpha: core::array::from_fn(|_| BldAlpha::default()),
             bldy: [0; 2],
+            master_bright: MasterBright::default(),
+            dispcapcnt: DispCapCnt::default(),
+            capturing: false,
+            main_memory_fifo: VecDeque::new(),
 
             palette: vec![0; 1024],
             oam: vec![0; 1024],
 
-            mode_safety: true,
+            framebuffer: Framebuffer::default(),
+            bg_layers: vec![(vec![vec![0; 192]; 256], true); 4],
             show_bgs: [true; 4],
             show_objs: [true; 4],
         }
@@ -48,20 +80,29 @@ impl<const ENGINE_A: bool> Default for Gpu2d<ENGINE_A> {
 }
 
 impl<const ENGINE_A: bool> Gpu2d<ENGINE_A> {
+    #[cfg(feature = "ui")]
     pub fn new_fake() -> Self {
         Self {
             dispcnt: DispCnt::default(),
             bgxcnt: core::array::from_fn(|_| BGxCNT::default()),
 
             bgofs: [0; 4],
+            bg_affine: [BgAffine::default(); 2],
+            windows: Windows::default(),
+            mosaic: Mosaic::default(),
             bldcnt: BldCnt::default(),
             bldalpha: core::array::from_fn(|_| BldAlpha::default()),
             bldy: [0; 2],
+            master_bright: MasterBright::default(),
+            dispcapcnt: DispCapCnt::default(),
+            capturing: false,
+            main_memory_fifo: VecDeque::new(),
 
             palette: vec![0; 0],
             oam: vec![0; 0],
 
-            mode_safety: true,
+            framebuffer: Framebuffer::default(),
+            bg_layers: vec![(vec![vec![0; 192]; 256], true); 4],
             show_bgs: [true; 4],
             show_objs: [true; 4],
         }
@@ -71,57 +112,119 @@ impl<const ENGINE_A: bool> Gpu2d<ENGINE_A> {
 pub type BackgroundResult = (Vec<Vec<u16>>, bool);
 pub type BackgroundResults = Vec<BackgroundResult>;
 
+// everything the debug windows want to show
+#[cfg(feature = "ui")]
 pub struct GpuRenderResult {
-    pub image_data: egui::ImageData,
     pub bgs: Option<BackgroundResults>,
     pub tiles: (Vec<Vec<u16>>, usize),
 }
 
+#[cfg(feature = "ui")]
 impl GpuRenderResult {
-    pub fn new(
-        image_data: egui::ImageData,
-        bgs: BackgroundResults,
-        tiles: (Vec<Vec<u16>>, usize),
-    ) -> Self {
+    pub fn new(bgs: BackgroundResults, tiles: (Vec<Vec<u16>>, usize)) -> Self {
         Self {
-            image_data,
             bgs: Some(bgs),
             tiles,
         }
     }
-
-    pub fn new_empty(image_data: egui::ImageData) -> Self {
-        Self {
-            image_data,
-            bgs: None,
-            tiles: (vec![vec![0; 1]; 1], 0),
-        }
-    }
 }
 
 impl<const ENGINE_A: bool> Gpu2d<ENGINE_A> {
-    pub fn render(&self, shared: &Shared) -> GpuRenderResult {
+    // the line is drawn as soon as it finishes, using whatever the registers are at that moment
+    pub fn hblank(&mut self, line: u16, vram_banks: &mut VramBanks, framebuffer_3d: &[Pixel3d]) {
+        let line = line as usize;
+        if ENGINE_A && line == 0 {
+            self.capturing = self.dispcapcnt.get_capture_enable();
+        }
+
+        // the graphics are still drawn for display capture when they aren't being shown
         let display_mode = self.dispcnt.get_display_mode();
-        match display_mode {
-            DisplayMode::DISPLAY_OFF => {
-                return GpuRenderResult::new_empty(egui::ImageData::from(egui::ColorImage {
-                    pixels: vec![egui::Color32::WHITE; 256 * 192],
-                    size: [256, 192],
-                }));
+        let mut graphics = vec![];
+        if display_mode == DisplayMode::GRAPHICS_DISPLAY || self.capturing {
+            let bg_lines;
+            (graphics, bg_lines) = self.render_graphics_line(vram_banks, framebuffer_3d, line);
+            for (layer, bg_line) in self.bg_layers.iter_mut().zip(bg_lines) {
+                layer.1 = bg_line.is_some();
+                let bg_line = bg_line.unwrap_or_else(|| vec![0; 256]);
+                for (x, pixel) in bg_line.into_iter().enumerate() {
+                    layer.0[x][line] = pixel;
+                }
+            }
+        }
+
+        // the FIFO is emptied even when it's only being captured
+        let uses_fifo = self.capturing && self.dispcapcnt.get_source_b();
+        let main_memory =
+            match ENGINE_A && display_mode == DisplayMode::MAIN_MEMORY_DISPLAY || uses_fifo {
+                true => self.pop_main_memory_line(),
+                false => vec![],
+            };
+
+        if self.capturing {
+            self.capture_line(vram_banks, line, &graphics, &main_memory, framebuffer_3d);
+            if line + 1 == self.dispcapcnt.get_capture_size().1 {
+                self.capturing = false;
+                self.dispcapcnt.set_capture_enable(false);
             }
-            DisplayMode::GRAPHICS_DISPLAY => {} // continue as normal
-            DisplayMode::VRAM_DISPLAY => {
-                return GpuRenderResult::new_empty(self.render_vram(shared));
+        }
+
+        let pixels: Vec<Rgb6> = match display_mode {
+            DisplayMode::DISPLAY_OFF => vec![[63; 3]; 256],
+            DisplayMode::GRAPHICS_DISPLAY => graphics,
+            DisplayMode::VRAM_DISPLAY if ENGINE_A => {
+                let pixels = self.render_vram_line(vram_banks, line);
+                pixels.into_iter().map(bgr555_to_rgb6).collect()
             }
-            DisplayMode::MAIN_MEMORY_DISPLAY => {
-                return GpuRenderResult::new_empty(egui::ImageData::from(egui::ColorImage {
-                    pixels: vec![egui::Color32::DARK_RED; 256 * 192],
-                    size: [256, 192],
-                }));
+            DisplayMode::MAIN_MEMORY_DISPLAY if ENGINE_A => {
+                main_memory.into_iter().map(bgr555_to_rgb6).collect()
+            }
+            // engine B only has the first two, show the backdrop instead
+            _ => {
+                logger::warn_once(
+                    logger::LogSource::Gpu2d,
+                    format!(
+                        "Engine B can't use display mode {}, showing the backdrop",
+                        display_mode.bits()
+                    ),
+                );
+                vec![bgr555_to_rgb6(self.read_palette(0)); 256]
             }
-            _ => unreachable!("if you see this then i'm wrong. this is very much reachable"),
         };
 
-        self.render_graphics(&shared.gpus.vram_banks)
+        // master brightness is the very last thing, whatever the display mode is
+        let pixels: Vec<Rgb6> = pixels
+            .into_iter()
+            .map(|pixel| self.master_bright.apply(pixel))
+            .collect();
+        self.framebuffer.set_line(line, &pixels);
+
+        self.bg_affine.iter_mut().for_each(BgAffine::advance);
+    }
+
+    // each word is 2 pixels, the first one in the low half
+    // the real FIFO is only 16 words but it's drained while the line is drawn, we drain it at hblank instead so it holds a whole line
+    pub fn write_main_memory_fifo(&mut self, value: u32) {
+        for pixel in [value as u16, (value >> 16) as u16] {
+            if self.main_memory_fifo.len() < 256 {
+                self.main_memory_fifo.push_back(pixel & 0x7FFF);
+            }
+        }
+    }
+
+    // whatever didn't arrive in time is black
+    fn pop_main_memory_line(&mut self) -> Vec<u16> {
+        (0..256)
+            .map(|_| self.main_memory_fifo.pop_front().unwrap_or(0))
+            .collect()
+    }
+
+    pub fn vblank(&mut self) {
+        self.bg_affine.iter_mut().for_each(BgAffine::latch);
+    }
+
+    // the screen itself is in framebuffer
+    #[cfg(feature = "ui")]
+    pub fn render_debug(&self, vram_banks: &VramBanks) -> GpuRenderResult {
+        GpuRenderResult::new(self.bg_layers.clone(), self.generate_tilemap(vram_banks))
     }
 }
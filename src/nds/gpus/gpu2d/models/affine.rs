@@ -0,0 +1,59 @@
+use crate::nds::Bits;
+
+// BGxPA-BGxPD, BGxX and BGxY for BG2 and BG3
+#[derive(Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
+pub struct BgAffine {
+    pub pa: i16, // dx, 8.8
+    pub pb: i16, // dmx
+    pub pc: i16, // dy
+    pub pd: i16, // dmy
+
+    x: u32, // 20.8, only 28 bits are used
+    y: u32,
+
+    // the reference point the current line starts from
+    // reloaded at vblank and whenever BGxX/BGxY are written, and moved along by dmx/dmy every line
+    pub internal_x: i32,
+    pub internal_y: i32,
+}
+
+impl BgAffine {
+    // offset is from BGxPA
+    pub fn write_byte(&mut self, offset: usize, value: u8) {
+        fn set_byte(target: &mut i16, offset: usize, value: u8) {
+            let mut bits = *target as u16;
+            bits.set_part::<1>((offset & 1) as u16, value as u16);
+            *target = bits as i16;
+        }
+
+        match offset {
+            0x0..=0x1 => set_byte(&mut self.pa, offset, value),
+            0x2..=0x3 => set_byte(&mut self.pb, offset, value),
+            0x4..=0x5 => set_byte(&mut self.pc, offset, value),
+            0x6..=0x7 => set_byte(&mut self.pd, offset, value),
+            0x8..=0xB => {
+                self.x.set_part::<1>((offset & 3) as u32, value as u32);
+                self.internal_x = sign_extend_28(self.x);
+            }
+            0xC..=0xF => {
+                self.y.set_part::<1>((offset & 3) as u32, value as u32);
+                self.internal_y = sign_extend_28(self.y);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn latch(&mut self) {
+        self.internal_x = sign_extend_28(self.x);
+        self.internal_y = sign_extend_28(self.y);
+    }
+
+    pub fn advance(&mut self) {
+        self.internal_x = self.internal_x.wrapping_add(self.pb as i32);
+        self.internal_y = self.internal_y.wrapping_add(self.pd as i32);
+    }
+}
+
+fn sign_extend_28(value: u32) -> i32 {
+    ((value << 4) as i32) >> 4
+}
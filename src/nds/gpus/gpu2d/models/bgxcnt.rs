@@ -14,11 +14,14 @@ impl BGxCNT {
     const PRIORITY_END: u16 = 1;
     const CHARACTER_BASE_BLOCK_START: u16 = 2;
     const CHARACTER_BASE_BLOCK_END: u16 = 5;
+    const DIRECT_COLOR_OFFSET: u16 = 2; // extended bitmaps only, overlaps the character base
+    const MOSAIC_OFFSET: u16 = 6;
 
     const COLOR_PALETTE_OFFSET: u16 = 7;
     const SCREEN_BASE_BLOCK_START: u16 = 8;
     const SCREEN_BASE_BLOCK_END: u16 = 12;
     const EXT_PALETTE_SLOT_OFFSET: u16 = 13;
+    const WRAPAROUND_OFFSET: u16 = 13; // affine BGs only, overlaps the ext palette slot
     const SCREEN_SIZE_START: u16 = 14;
     const SCREEN_SIZE_END: u16 = 15;
 
@@ -37,6 +40,10 @@ impl BGxCNT {
         )
     }
 
+    pub fn get_mosaic(&self) -> bool {
+        self.0.get_bit(Self::MOSAIC_OFFSET)
+    }
+
     pub fn get_color_palette(&self, extended_palettes: bool) -> ColorPalette {
         match self.0.get_bit(Self::COLOR_PALETTE_OFFSET) {
             false => ColorPalette::Is16x16,
@@ -60,6 +67,28 @@ impl BGxCNT {
         self.0
             .get_bits(Self::SCREEN_SIZE_START, Self::SCREEN_SIZE_END) as u8
     }
+
+    pub fn get_wraparound(&self) -> bool {
+        self.0.get_bit(Self::WRAPAROUND_OFFSET)
+    }
+
+    pub fn get_extended_kind(&self) -> ExtendedBackground {
+        match (
+            self.0.get_bit(Self::COLOR_PALETTE_OFFSET),
+            self.0.get_bit(Self::DIRECT_COLOR_OFFSET),
+        ) {
+            (false, _) => ExtendedBackground::Tiled,
+            (true, false) => ExtendedBackground::Bitmap256,
+            (true, true) => ExtendedBackground::DirectColor,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExtendedBackground {
+    Tiled,       // affine, but with text style 16 bit tile entries
+    Bitmap256,   // 8 bit palette indices
+    DirectColor, // 15 bit colours, bit 15 is opaque
 }
 
 #[derive(PartialEq)]
@@ -1,4 +1,4 @@
-use crate::nds::Bits;
+use crate::nds::{gpus::framebuffer::Rgb6, Bits};
 
 #[derive(Default, serde::Deserialize, serde::Serialize)]
 pub struct BldCnt(pub u16);
@@ -48,7 +48,8 @@ pub enum ColorSpecialEffect {
 }
 
 impl ColorSpecialEffect {
-    pub fn alpha_blend(first: u16, second: u16, eva: f32, evb: f32) -> u16 {
+    // the weights are in 16ths, same as the hardware
+    pub fn alpha_blend(first: u16, second: u16, eva: u16, evb: u16) -> u16 {
         if !first.get_bit(15) || !second.get_bit(15) {
             if !first.get_bit(15) {
                 return second;
@@ -56,20 +57,46 @@ impl ColorSpecialEffect {
             return first;
         }
 
-        let r = 31_f32.min(first.get_bits(0, 4) as f32 * eva + second.get_bits(0, 4) as f32 * evb)
-            as u16;
-        let g = 31_f32.min(first.get_bits(5, 9) as f32 * eva + second.get_bits(5, 9) as f32 * evb)
-            as u16;
-        let b = 31_f32
-            .min(first.get_bits(10, 14) as f32 * eva + second.get_bits(10, 14) as f32 * evb)
-            as u16;
+        let blend = |start: u16, end: u16| {
+            31.min((first.get_bits(start, end) * eva + second.get_bits(start, end) * evb) >> 4)
+        };
         let mut result = 0;
-        result.set_bits(0, 4, r);
-        result.set_bits(5, 9, g);
-        result.set_bits(10, 14, b);
+        result.set_bits(0, 4, blend(0, 4));
+        result.set_bits(5, 9, blend(5, 9));
+        result.set_bits(10, 14, blend(10, 14));
         result.set_bit(15, false);
         result
     }
+
+    // moves each channel towards white (or black when decreasing) by evy/16
+    pub fn brightness_increase(color: u16, evy: u16) -> u16 {
+        Self::map_channels(color, |c| c + (((31 - c) * evy) >> 4))
+    }
+
+    pub fn brightness_decrease(color: u16, evy: u16) -> u16 {
+        Self::map_channels(color, |c| c - ((c * evy) >> 4))
+    }
+
+    // the same again for 3D pixels, which keep the 6 bits they were drawn with
+    pub fn alpha_blend_rgb6(first: Rgb6, second: Rgb6, eva: u16, evb: u16) -> Rgb6 {
+        std::array::from_fn(|i| 63.min((first[i] as u16 * eva + second[i] as u16 * evb) >> 4) as u8)
+    }
+
+    pub fn brightness_increase_rgb6(color: Rgb6, evy: u16) -> Rgb6 {
+        color.map(|c| c + (((63 - c as u16) * evy) >> 4) as u8)
+    }
+
+    pub fn brightness_decrease_rgb6(color: Rgb6, evy: u16) -> Rgb6 {
+        color.map(|c| c - ((c as u16 * evy) >> 4) as u8)
+    }
+
+    fn map_channels(color: u16, f: impl Fn(u16) -> u16) -> u16 {
+        let mut result = color;
+        result.set_bits(0, 4, f(color.get_bits(0, 4)));
+        result.set_bits(5, 9, f(color.get_bits(5, 9)));
+        result.set_bits(10, 14, f(color.get_bits(10, 14)));
+        result
+    }
 }
 
 #[derive(Default, serde::Deserialize, serde::Serialize)]
@@ -86,9 +113,64 @@ impl BldAlpha {
         self.0
     }
 
-    pub fn ev(&self) -> f32 {
-        let ev = self.0.get_bits(0, 4) as f32;
-        let n = 16_f32.min(ev);
-        n / 16.0
+    // anything over 16 acts like 16
+    pub fn ev(&self) -> u16 {
+        16.min(self.0.get_bits(0, 4) as u16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ColorSpecialEffect;
+
+    const WHITE: u16 = 0xFFFF;
+    const BLACK: u16 = 0x8000;
+
+    #[test]
+    fn alpha_blend_weighs_in_16ths() {
+        assert_eq!(ColorSpecialEffect::alpha_blend(WHITE, BLACK, 8, 8), 0x3DEF);
+        assert_eq!(ColorSpecialEffect::alpha_blend(WHITE, BLACK, 16, 0), 0x7FFF);
+        assert_eq!(ColorSpecialEffect::alpha_blend(WHITE, BLACK, 0, 16), 0x0000);
+        // weights over 16 in total clamp each channel at 31
+        assert_eq!(
+            ColorSpecialEffect::alpha_blend(WHITE, WHITE, 16, 16),
+            0x7FFF
+        );
+    }
+
+    #[test]
+    fn alpha_blend_skips_transparent_pixels() {
+        assert_eq!(ColorSpecialEffect::alpha_blend(0x001F, BLACK, 8, 8), BLACK);
+        assert_eq!(ColorSpecialEffect::alpha_blend(WHITE, 0x001F, 8, 8), WHITE);
+    }
+
+    #[test]
+    fn brightness_rounds_down() {
+        assert_eq!(ColorSpecialEffect::brightness_increase(0x0000, 16), 0x7FFF);
+        assert_eq!(ColorSpecialEffect::brightness_increase(0x0000, 8), 0x3DEF);
+        assert_eq!(ColorSpecialEffect::brightness_increase(0x7FFF, 8), 0x7FFF);
+        assert_eq!(ColorSpecialEffect::brightness_decrease(0x7FFF, 16), 0x0000);
+        // 31 - (31 * 8 >> 4) = 16
+        assert_eq!(ColorSpecialEffect::brightness_decrease(0x7FFF, 8), 0x4210);
+    }
+
+    #[test]
+    fn rgb6_effects_keep_6_bits() {
+        assert_eq!(
+            ColorSpecialEffect::alpha_blend_rgb6([63, 0, 32], [0, 63, 32], 8, 8),
+            [31, 31, 32]
+        );
+        assert_eq!(
+            ColorSpecialEffect::alpha_blend_rgb6([63, 63, 63], [63, 63, 63], 16, 16),
+            [63, 63, 63]
+        );
+        assert_eq!(
+            ColorSpecialEffect::brightness_increase_rgb6([0, 32, 63], 8),
+            [31, 47, 63]
+        );
+        assert_eq!(
+            ColorSpecialEffect::brightness_decrease_rgb6([0, 32, 63], 8),
+            [0, 16, 32]
+        );
     }
 }
@@ -0,0 +1,111 @@
+use crate::nds::Bits;
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct DispCapCnt(pub u32);
+
+impl From<u32> for DispCapCnt {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl DispCapCnt {
+    const EVA_START: u32 = 0;
+    const EVA_END: u32 = 4;
+    const EVB_START: u32 = 8;
+    const EVB_END: u32 = 12;
+    const VRAM_WRITE_BLOCK_START: u32 = 16;
+    const VRAM_WRITE_BLOCK_END: u32 = 17;
+    const VRAM_WRITE_OFFSET_START: u32 = 18;
+    const VRAM_WRITE_OFFSET_END: u32 = 19;
+    const CAPTURE_SIZE_START: u32 = 20;
+    const CAPTURE_SIZE_END: u32 = 21;
+    const SOURCE_A: u32 = 24;
+    const SOURCE_B: u32 = 25;
+    const VRAM_READ_OFFSET_START: u32 = 26;
+    const VRAM_READ_OFFSET_END: u32 = 27;
+    const CAPTURE_SOURCE_START: u32 = 29;
+    const CAPTURE_SOURCE_END: u32 = 30;
+    const CAPTURE_ENABLE: u32 = 31;
+
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+
+    // anything above 16 acts like 16
+    pub fn get_eva(&self) -> u32 {
+        16.min(self.0.get_bits(Self::EVA_START, Self::EVA_END))
+    }
+
+    pub fn get_evb(&self) -> u32 {
+        16.min(self.0.get_bits(Self::EVB_START, Self::EVB_END))
+    }
+
+    // which of VRAM A-D to write to
+    pub fn get_vram_write_block(&self) -> u32 {
+        self.0
+            .get_bits(Self::VRAM_WRITE_BLOCK_START, Self::VRAM_WRITE_BLOCK_END)
+    }
+
+    // in steps of 0x8000 bytes
+    pub fn get_vram_write_offset(&self) -> u32 {
+        self.0
+            .get_bits(Self::VRAM_WRITE_OFFSET_START, Self::VRAM_WRITE_OFFSET_END)
+    }
+
+    // width and height
+    pub fn get_capture_size(&self) -> (usize, usize) {
+        match self
+            .0
+            .get_bits(Self::CAPTURE_SIZE_START, Self::CAPTURE_SIZE_END)
+        {
+            0 => (128, 128),
+            1 => (256, 64),
+            2 => (256, 128),
+            3 => (256, 192),
+            _ => unreachable!(),
+        }
+    }
+
+    // false: engine A's graphics output, true: only the 3D engine's output
+    pub fn get_source_a(&self) -> bool {
+        self.0.get_bit(Self::SOURCE_A)
+    }
+
+    // false: VRAM, true: the main memory display FIFO
+    pub fn get_source_b(&self) -> bool {
+        self.0.get_bit(Self::SOURCE_B)
+    }
+
+    // in steps of 0x8000 bytes, ignored when VRAM display mode is on
+    pub fn get_vram_read_offset(&self) -> u32 {
+        self.0
+            .get_bits(Self::VRAM_READ_OFFSET_START, Self::VRAM_READ_OFFSET_END)
+    }
+
+    pub fn get_capture_source(&self) -> CaptureSource {
+        match self
+            .0
+            .get_bits(Self::CAPTURE_SOURCE_START, Self::CAPTURE_SOURCE_END)
+        {
+            0 => CaptureSource::A,
+            1 => CaptureSource::B,
+            _ => CaptureSource::Blended,
+        }
+    }
+
+    pub fn get_capture_enable(&self) -> bool {
+        self.0.get_bit(Self::CAPTURE_ENABLE)
+    }
+
+    pub fn set_capture_enable(&mut self, value: bool) {
+        self.0.set_bit(Self::CAPTURE_ENABLE, value);
+    }
+}
+
+#[derive(PartialEq)]
+pub enum CaptureSource {
+    A,
+    B,
+    Blended,
+}
@@ -16,11 +16,18 @@ impl DispCnt {
     const BG_MODE_END: u32 = 2;
     const BG0_2D_3D_SELECTION: u32 = 3;
     const TILE_OBJ_MAPPING: u32 = 4;
+    const BITMAP_OBJ_MAPPING_START: u32 = 5;
+    const BITMAP_OBJ_MAPPING_END: u32 = 6;
 
     const SCREEN_DISPLAY_BG0: u32 = 8;
     const SCREEN_DISPLAY_BG1: u32 = 9;
     const SCREEN_DISPLAY_BG2: u32 = 10;
     const SCREEN_DISPLAY_BG3: u32 = 11;
+    const SCREEN_DISPLAY_OBJ: u32 = 12;
+
+    const WINDOW_0_DISPLAY_FLAG: u32 = 13;
+    const WINDOW_1_DISPLAY_FLAG: u32 = 14;
+    const OBJ_WINDOW_DISPLAY_FLAG: u32 = 15;
 
     const DISPLAY_MODE_START: u32 = 16;
     const DISPLAY_MODE_END: u32 = 17;
@@ -28,6 +35,7 @@ impl DispCnt {
     const VRAM_BLOCK_END: u32 = 19;
     const TILE_OBJ_1D_BOUNARY_START: u32 = 20;
     const TILE_OBJ_1D_BOUNARY_END: u32 = 21;
+    const BITMAP_OBJ_1D_BOUNDARY: u32 = 22;
 
     const CHARACTER_BASE_START: u32 = 24;
     const CHARACTER_BASE_END: u32 = 26;
@@ -52,6 +60,12 @@ impl DispCnt {
         self.0.get_bit(Self::TILE_OBJ_MAPPING)
     }
 
+    // 0: 2D with a 128 dot wide bitmap, 1: 2D 256 dots wide, 2: 1D
+    pub fn get_bitmap_obj_mapping(&self) -> u32 {
+        self.0
+            .get_bits(Self::BITMAP_OBJ_MAPPING_START, Self::BITMAP_OBJ_MAPPING_END)
+    }
+
     pub fn get_screen_display_bg0(&self) -> bool {
         self.0.get_bit(Self::SCREEN_DISPLAY_BG0)
     }
@@ -68,6 +82,22 @@ impl DispCnt {
         self.0.get_bit(Self::SCREEN_DISPLAY_BG3)
     }
 
+    pub fn get_screen_display_obj(&self) -> bool {
+        self.0.get_bit(Self::SCREEN_DISPLAY_OBJ)
+    }
+
+    pub fn get_window_0_display_flag(&self) -> bool {
+        self.0.get_bit(Self::WINDOW_0_DISPLAY_FLAG)
+    }
+
+    pub fn get_window_1_display_flag(&self) -> bool {
+        self.0.get_bit(Self::WINDOW_1_DISPLAY_FLAG)
+    }
+
+    pub fn get_obj_window_display_flag(&self) -> bool {
+        self.0.get_bit(Self::OBJ_WINDOW_DISPLAY_FLAG)
+    }
+
     pub fn get_display_mode(&self) -> DisplayMode {
         DisplayMode::from_bits_truncate(
             self.0
@@ -87,6 +117,10 @@ impl DispCnt {
         )
     }
 
+    pub fn get_bitmap_obj_1d_boundary(&self) -> bool {
+        self.0.get_bit(Self::BITMAP_OBJ_1D_BOUNDARY)
+    }
+
     pub fn get_character_base(&self) -> u32 {
         self.0
             .get_bits(Self::CHARACTER_BASE_START, Self::CHARACTER_BASE_END)
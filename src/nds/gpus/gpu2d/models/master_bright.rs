@@ -0,0 +1,51 @@
+use crate::nds::{gpus::framebuffer::Rgb6, Bits};
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct MasterBright(pub u16);
+
+impl From<u16> for MasterBright {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl MasterBright {
+    const FACTOR_START: u16 = 0;
+    const FACTOR_END: u16 = 4;
+    const MODE_START: u16 = 14;
+    const MODE_END: u16 = 15;
+
+    pub fn value(&self) -> u16 {
+        self.0
+    }
+
+    // out of 16, anything above 16 acts like 16
+    pub fn get_factor(&self) -> u16 {
+        16.min(self.0.get_bits(Self::FACTOR_START, Self::FACTOR_END))
+    }
+
+    pub fn get_mode(&self) -> MasterBrightMode {
+        match self.0.get_bits(Self::MODE_START, Self::MODE_END) {
+            1 => MasterBrightMode::Up,
+            2 => MasterBrightMode::Down,
+            _ => MasterBrightMode::Disabled, // 3 is reserved
+        }
+    }
+
+    // done at 6 bits per channel, after everything else
+    pub fn apply(&self, pixel: Rgb6) -> Rgb6 {
+        let factor = self.get_factor();
+        match self.get_mode() {
+            MasterBrightMode::Disabled => pixel,
+            MasterBrightMode::Up => pixel.map(|c| (c as u16 + (63 - c as u16) * factor / 16) as u8),
+            MasterBrightMode::Down => pixel.map(|c| (c as u16 - c as u16 * factor / 16) as u8),
+        }
+    }
+}
+
+#[derive(PartialEq)]
+pub enum MasterBrightMode {
+    Disabled,
+    Up,
+    Down,
+}
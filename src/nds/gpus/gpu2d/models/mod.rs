@@ -1,7 +1,17 @@
+mod affine;
 mod bgxcnt;
 mod colorfx;
+mod dispcapcnt;
 mod dispcnt;
+mod master_bright;
+mod mosaic;
+mod window;
 
+pub use affine::*;
 pub use bgxcnt::*;
 pub use colorfx::*;
+pub use dispcapcnt::*;
 pub use dispcnt::*;
+pub use master_bright::*;
+pub use mosaic::*;
+pub use window::*;
@@ -0,0 +1,41 @@
+use crate::nds::Bits;
+
+// MOSAIC, only the low 16 bits do anything
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct Mosaic(pub u16);
+
+impl From<u16> for Mosaic {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl Mosaic {
+    const BG_H_SIZE_START: u16 = 0;
+    const BG_H_SIZE_END: u16 = 3;
+    const BG_V_SIZE_START: u16 = 4;
+    const BG_V_SIZE_END: u16 = 7;
+    const OBJ_H_SIZE_START: u16 = 8;
+    const OBJ_H_SIZE_END: u16 = 11;
+    const OBJ_V_SIZE_START: u16 = 12;
+    const OBJ_V_SIZE_END: u16 = 15;
+
+    // width and height of each block in pixels, the register stores them minus one
+    pub fn get_bg_size(&self) -> (usize, usize) {
+        (
+            self.0.get_bits(Self::BG_H_SIZE_START, Self::BG_H_SIZE_END) as usize + 1,
+            self.0.get_bits(Self::BG_V_SIZE_START, Self::BG_V_SIZE_END) as usize + 1,
+        )
+    }
+
+    pub fn get_obj_size(&self) -> (usize, usize) {
+        (
+            self.0
+                .get_bits(Self::OBJ_H_SIZE_START, Self::OBJ_H_SIZE_END) as usize
+                + 1,
+            self.0
+                .get_bits(Self::OBJ_V_SIZE_START, Self::OBJ_V_SIZE_END) as usize
+                + 1,
+        )
+    }
+}
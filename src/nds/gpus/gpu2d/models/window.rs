@@ -0,0 +1,67 @@
+use crate::nds::Bits;
+
+// WIN0H-WIN1V, WININ and WINOUT
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct Windows {
+    pub h: [u16; 2], // right edge in the low byte, left edge in the high byte
+    pub v: [u16; 2], // bottom edge in the low byte, top edge in the high byte
+    pub winin: u16,  // window 0 in the low byte, window 1 in the high byte
+    pub winout: u16, // outside of the windows in the low byte, the OBJ window in the high byte
+}
+
+impl Windows {
+    // offset is from WIN0H
+    pub fn write_byte(&mut self, offset: usize, value: u8) {
+        let part = (offset & 1) as u16;
+        match offset {
+            0x0..=0x1 => self.h[0].set_part::<1>(part, value as u16),
+            0x2..=0x3 => self.h[1].set_part::<1>(part, value as u16),
+            0x4..=0x5 => self.v[0].set_part::<1>(part, value as u16),
+            0x6..=0x7 => self.v[1].set_part::<1>(part, value as u16),
+            0x8..=0x9 => self.winin.set_part::<1>(part, (value & 0x3F) as u16),
+            0xA..=0xB => self.winout.set_part::<1>(part, (value & 0x3F) as u16),
+            _ => {}
+        }
+    }
+
+    // only WININ and WINOUT can be read back
+    pub fn read_byte(&self, offset: usize) -> u8 {
+        match offset {
+            0x8..=0x9 => self.winin.to_le_bytes()[offset & 1],
+            0xA..=0xB => self.winout.to_le_bytes()[offset & 1],
+            _ => 0,
+        }
+    }
+
+    pub fn contains_line(&self, window: usize, line: usize) -> bool {
+        Self::in_range(self.v[window], line)
+    }
+
+    pub fn contains_x(&self, window: usize, x: usize) -> bool {
+        Self::in_range(self.h[window], x)
+    }
+
+    // the bits are BG0-BG3, OBJ and then colour special effects
+    pub fn get_window_control(&self, window: usize) -> u8 {
+        self.winin.to_le_bytes()[window]
+    }
+
+    pub fn get_outside_control(&self) -> u8 {
+        self.winout.to_le_bytes()[0]
+    }
+
+    pub fn get_obj_window_control(&self) -> u8 {
+        self.winout.to_le_bytes()[1]
+    }
+
+    // the second coordinate is exclusive, and if it's before the first one the window wraps around the edge
+    fn in_range(edges: u16, position: usize) -> bool {
+        let start = edges.get_bits(8, 15) as usize;
+        let end = edges.get_bits(0, 7) as usize;
+        if start <= end {
+            (start..end).contains(&position)
+        } else {
+            position >= start || position < end
+        }
+    }
+}
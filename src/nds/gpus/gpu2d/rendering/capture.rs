@@ -0,0 +1,89 @@
+use crate::nds::{
+    gpus::{
+        framebuffer::{rgb6_to_bgr555, Rgb6},
+        gpu2d::{
+            models::{CaptureSource, DisplayMode},
+            Gpu2d,
+        },
+        gpu3d::rendering::Pixel3d,
+        vram::VramBanks,
+    },
+    Bits,
+};
+
+impl<const ENGINE_A: bool> Gpu2d<ENGINE_A> {
+    // Display Capture: copies a line into VRAM A-D while they're mapped to LCDC
+    // graphics is engine A's output without master brightness, main_memory is the line from the display FIFO
+    pub fn capture_line(
+        &self,
+        vram_banks: &mut VramBanks,
+        line: usize,
+        graphics: &[Rgb6],
+        main_memory: &[u16],
+        framebuffer_3d: &[Pixel3d],
+    ) {
+        let dispcapcnt = &self.dispcapcnt;
+        let (width, _) = dispcapcnt.get_capture_size();
+        let capture_source = dispcapcnt.get_capture_source();
+
+        // bit 15 is the alpha of each pixel
+        let source_a = |x: usize| -> u16 {
+            if !dispcapcnt.get_source_a() {
+                return rgb6_to_bgr555(graphics[x]) | 0x8000;
+            }
+            if framebuffer_3d.len() != 256 * 192 {
+                return 0; // nothing has been rendered yet
+            }
+
+            let [r, g, b, alpha] = framebuffer_3d[line * 256 + x];
+            let mut pixel = rgb6_to_bgr555([r, g, b]);
+            pixel.set_bit(15, alpha != 0);
+            pixel
+        };
+        let vram_read_base = 0x06800000 + self.dispcnt.get_vram_block() as usize * 0x20000;
+        let vram_read_offset = match self.dispcnt.get_display_mode() {
+            DisplayMode::VRAM_DISPLAY => 0, // the offset is ignored when the same bank is being displayed
+            _ => dispcapcnt.get_vram_read_offset() as usize * 0x8000,
+        };
+        let source_b = |x: usize| -> u16 {
+            if dispcapcnt.get_source_b() {
+                return main_memory[x] | 0x8000;
+            }
+
+            let addr = vram_read_base + (vram_read_offset + (line * 256 + x) * 2) % 0x20000;
+            u16::from_le_bytes(vram_banks.read_slice::<2>(addr).unwrap_or([0; 2]))
+        };
+
+        let eva = dispcapcnt.get_eva() as u16;
+        let evb = dispcapcnt.get_evb() as u16;
+        let pixels: Vec<u16> = (0..width)
+            .map(|x| match capture_source {
+                CaptureSource::A => source_a(x),
+                CaptureSource::B => source_b(x),
+                CaptureSource::Blended => {
+                    let (a, b) = (source_a(x), source_b(x));
+                    let eva = eva * a.get_bit(15) as u16;
+                    let evb = evb * b.get_bit(15) as u16;
+                    let blend = |start, end| {
+                        let channel = a.get_bits(start, end) * eva + b.get_bits(start, end) * evb;
+                        31.min((channel + 8) / 16)
+                    };
+
+                    let mut pixel = 0u16;
+                    pixel.set_bits(0, 4, blend(0, 4));
+                    pixel.set_bits(5, 9, blend(5, 9));
+                    pixel.set_bits(10, 14, blend(10, 14));
+                    pixel.set_bit(15, eva != 0 || evb != 0);
+                    pixel
+                }
+            })
+            .collect();
+
+        let vram_write_base = 0x06800000 + dispcapcnt.get_vram_write_block() as usize * 0x20000;
+        let vram_write_offset = dispcapcnt.get_vram_write_offset() as usize * 0x8000;
+        for (x, pixel) in pixels.into_iter().enumerate() {
+            let addr = vram_write_base + (vram_write_offset + (line * width + x) * 2) % 0x20000;
+            vram_banks.write_slice::<2>(addr, pixel.to_le_bytes());
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::nds::{
+    gpus::{
+        gpu2d::{models::ExtendedBackground, Gpu2d},
+        vram::{VirtualLocation, VramBanks},
+    },
+    Bits,
+};
+
+use super::BackgroundKind;
+
+#[derive(Clone, Copy, PartialEq)]
+enum AffineSource {
+    Tiles,         // 8 bit tile entries
+    ExtendedTiles, // 16 bit tile entries, with flips and extended palettes
+    Bitmap256,
+    DirectColor,
+}
+
+impl<const ENGINE_A: bool> Gpu2d<ENGINE_A> {
+    // one line of an affine, extended or large bitmap BG, these are positioned by their reference point instead of BGxHOFS/BGxVOFS
+    pub(super) fn render_affine_background_line<const BG: u8>(
+        &self,
+        vram_banks: &VramBanks,
+        kind: BackgroundKind,
+        mosaic_y: usize,
+    ) -> Vec<u16> {
+        let bgcnt = &self.bgxcnt[BG as usize];
+        let affine = &self.bg_affine[BG as usize - 2];
+        let bg_vram_base = if ENGINE_A { 0x06000000 } else { 0x06200000 };
+        let character_base = if ENGINE_A {
+            bgcnt.get_character_base_block() as usize * (1024 * 16)
+                + self.dispcnt.get_character_base() as usize * (1024 * 64)
+        } else {
+            bgcnt.get_character_base_block() as usize * (1024 * 16)
+        };
+        let screen_base = if ENGINE_A {
+            bgcnt.get_screen_base_block() as usize * (1024 * 2)
+                + self.dispcnt.get_screen_base() as usize * (1024 * 64)
+        } else {
+            bgcnt.get_screen_base_block() as usize * (1024 * 2)
+        };
+        // bitmaps don't use the DISPCNT offsets, and the large one always starts at the bottom of BG VRAM
+        let bitmap_base = match kind {
+            BackgroundKind::LargeBitmap => 0,
+            _ => bgcnt.get_screen_base_block() as usize * (1024 * 16),
+        };
+
+        let source = match kind {
+            BackgroundKind::Extended => match bgcnt.get_extended_kind() {
+                ExtendedBackground::Tiled => AffineSource::ExtendedTiles,
+                ExtendedBackground::Bitmap256 => AffineSource::Bitmap256,
+                ExtendedBackground::DirectColor => AffineSource::DirectColor,
+            },
+            BackgroundKind::LargeBitmap => AffineSource::Bitmap256,
+            _ => AffineSource::Tiles,
+        };
+        let screen_size = bgcnt.get_screen_size();
+        let (width, height) = match (kind, source) {
+            (BackgroundKind::LargeBitmap, _) => {
+                [(512, 1024), (1024, 512), (512, 1024), (1024, 512)][screen_size as usize]
+            }
+            (_, AffineSource::Bitmap256 | AffineSource::DirectColor) => {
+                [(128, 128), (256, 256), (512, 256), (512, 512)][screen_size as usize]
+            }
+            _ => {
+                let size = 128 << screen_size;
+                (size, size)
+            }
+        };
+        let wraparound = bgcnt.get_wraparound();
+        let extended_palettes = self.dispcnt.get_bg_extended_palettes();
+
+        let read_byte = |addr: usize| {
+            vram_banks
+                .read_slice::<1>(bg_vram_base + addr)
+                .map_or(0, |bytes| bytes[0])
+        };
+        let read_halfword = |addr: usize| {
+            u16::from_le_bytes(
+                vram_banks
+                    .read_slice::<2>(bg_vram_base + addr)
+                    .unwrap_or([0; 2]),
+            )
+        };
+        let palette_color = |index: usize| self.read_palette(index);
+        let extended_palette_color = |palette_number: usize, index: usize| {
+            let slot_offset = if BG == 2 { 1024 * 16 } else { 1024 * 24 };
+            let virtual_location = match ENGINE_A {
+                true => VirtualLocation::BgExtendedPaletteA,
+                false => VirtualLocation::BgExtendedPaletteB,
+            };
+            let address = slot_offset + (palette_number * 256 + index) * 2;
+            u16::from_le_bytes(
+                vram_banks
+                    .read_virtual_slice::<2>(virtual_location, address)
+                    .unwrap_or([0; 2]),
+            )
+        };
+
+        // bit 15 is set for opaque pixels, like the text BGs
+        let fetch = |x: usize, y: usize| -> u16 {
+            let (color, opaque) = match source {
+                AffineSource::Tiles => {
+                    let tile_number = read_byte(screen_base + (y / 8) * (width / 8) + x / 8);
+                    let index =
+                        read_byte(character_base + tile_number as usize * 64 + (y % 8) * 8 + x % 8)
+                            as usize;
+                    (palette_color(index), index != 0)
+                }
+                AffineSource::ExtendedTiles => {
+                    let map_tile = read_halfword(screen_base + ((y / 8) * (width / 8) + x / 8) * 2);
+                    let tile_number = map_tile.get_bits(0, 9) as usize;
+                    let tile_x = if map_tile.get_bit(10) {
+                        7 - x % 8
+                    } else {
+                        x % 8
+                    };
+                    let tile_y = if map_tile.get_bit(11) {
+                        7 - y % 8
+                    } else {
+                        y % 8
+                    };
+                    let palette_number = map_tile.get_bits(12, 15) as usize;
+
+                    let index =
+                        read_byte(character_base + tile_number * 64 + tile_y * 8 + tile_x) as usize;
+                    let color = match extended_palettes {
+                        true => extended_palette_color(palette_number, index),
+                        false => palette_color(index),
+                    };
+                    (color, index != 0)
+                }
+                AffineSource::Bitmap256 => {
+                    let index = read_byte(bitmap_base + y * width + x) as usize;
+                    (palette_color(index), index != 0)
+                }
+                AffineSource::DirectColor => {
+                    let color = read_halfword(bitmap_base + (y * width + x) * 2);
+                    (color, color.get_bit(15))
+                }
+            };
+
+            let mut color = color;
+            color.set_bit(15, opaque);
+            color
+        };
+
+        // the internal reference point is where this line starts, it moves on after the line is drawn
+        // with vertical mosaic we go back to where the first line of the block started
+        let origin_x = affine.internal_x - affine.pb as i32 * mosaic_y as i32;
+        let origin_y = affine.internal_y - affine.pd as i32 * mosaic_y as i32;
+        let mut pixels = vec![0; 256];
+        for (screen_x, pixel) in pixels.iter_mut().enumerate() {
+            let x = (origin_x + affine.pa as i32 * screen_x as i32) >> 8;
+            let y = (origin_y + affine.pc as i32 * screen_x as i32) >> 8;
+
+            let (x, y) = if wraparound {
+                (x.rem_euclid(width as i32), y.rem_euclid(height as i32))
+            } else if (0..width as i32).contains(&x) && (0..height as i32).contains(&y) {
+                (x, y)
+            } else {
+                continue;
+            };
+
+            *pixel = fetch(x as usize, y as usize);
+        }
+
+        pixels
+    }
+}
@@ -0,0 +1,26 @@
+use crate::nds::{
+    gpus::{gpu2d::Gpu2d, gpu3d::rendering::Pixel3d},
+    Bits,
+};
+
+impl<const ENGINE_A: bool> Gpu2d<ENGINE_A> {
+    // the 3D engine's output takes BG0's place, it can only be scrolled horizontally and doesn't wrap
+    // the pixels keep their full 6 bit colour and alpha, translucent pixels blend with whatever is below them
+    pub fn render_3d_layer_line(&self, framebuffer: &[Pixel3d], line: usize) -> Vec<Pixel3d> {
+        let mut pixels = vec![[0; 4]; 256];
+        if framebuffer.len() != 256 * 192 {
+            return pixels; // nothing has been rendered yet
+        }
+
+        // 9 bit signed
+        let scroll = ((self.bgofs[0].get_bits(0, 8) as i32) << 23) >> 23;
+        for (x, pixel) in pixels.iter_mut().enumerate() {
+            let source_x = x as i32 + scroll;
+            if (0..256).contains(&source_x) {
+                *pixel = framebuffer[line * 256 + source_x as usize];
+            }
+        }
+
+        pixels
+    }
+}
@@ -1,159 +1,252 @@
+mod affine;
 mod background;
+mod layer3d;
 mod obj;
+mod window;
 
 use crate::nds::{
     gpus::{
-        gpu2d::{models::ColorSpecialEffect, BackgroundResults, Gpu2d, GpuRenderResult},
+        framebuffer::{bgr555_to_rgb6, rgb6_to_bgr555, Rgb6},
+        gpu2d::{models::ColorSpecialEffect, Gpu2d},
+        gpu3d::rendering::Pixel3d,
         vram::VramBanks,
     },
-    Bits, IfElse,
+    Bits,
 };
 
-const COLOUR_MULT: f32 = 255.0 / 31.0;
+use obj::ObjBlend;
+
+// one of the layers at a pixel, target is its bit in BLDCNT (BG0-BG3, OBJ, backdrop)
+struct Layer {
+    color: u16,
+    target: u16,
+    blend: ObjBlend,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum BackgroundKind {
+    None,
+    Text,
+    Affine,
+    Extended,
+    LargeBitmap,
+}
 
 impl<const ENGINE_A: bool> Gpu2d<ENGINE_A> {
     // Display Mode: Graphics Display
-    pub fn render_graphics(&self, vram_banks: &VramBanks) -> GpuRenderResult {
-        let bg_mode = self.dispcnt.get_bg_mode();
-        // should be a match statement, but we only really support bg mode 0
-        if bg_mode == 0 || !self.mode_safety {
-            let mut ids: Vec<usize> = (0..=3).collect();
-            ids.sort_by(|&a, &b| {
-                self.bgxcnt[b]
-                    .get_priority()
-                    .cmp(&self.bgxcnt[a].get_priority())
-                    .then(b.cmp(&a))
-            });
-
-            let mut pixel_layers: BackgroundResults = vec![(vec![vec![]], false); 4];
-            if self.dispcnt.get_screen_display_bg0()
-                && self.show_bgs[0]
-                && !self.dispcnt.get_bg0_2d_3d_selection()
-            {
-                pixel_layers[0] = self.render_background::<0>(vram_banks);
-            }
-            if self.dispcnt.get_screen_display_bg1() && self.show_bgs[1] {
-                pixel_layers[1] = self.render_background::<1>(vram_banks);
+    // returns the finished line and each BG's line on its own, for the map viewer
+    pub fn render_graphics_line(
+        &self,
+        vram_banks: &VramBanks,
+        framebuffer_3d: &[Pixel3d],
+        line: usize,
+    ) -> (Vec<Rgb6>, [Option<Vec<u16>>; 4]) {
+        // front to back, lower BG numbers win when the priorities are the same
+        let mut ids: Vec<usize> = (0..=3).collect();
+        ids.sort_by_key(|&id| (self.bgxcnt[id].get_priority(), id));
+
+        // only engine A has a 3D engine to show
+        let is_3d = ENGINE_A && self.dispcnt.get_bg0_2d_3d_selection();
+        let mut layer_3d = vec![];
+
+        let kinds = self.get_background_kinds();
+        let mut bg_lines: [Option<Vec<u16>>; 4] = Default::default();
+        if self.dispcnt.get_screen_display_bg0() && self.show_bgs[0] {
+            if is_3d {
+                layer_3d = self.render_3d_layer_line(framebuffer_3d, line);
+                let pixels = layer_3d.iter().map(|&[r, g, b, alpha]| {
+                    let mut pixel = rgb6_to_bgr555([r, g, b]);
+                    pixel.set_bit(15, alpha != 0);
+                    pixel
+                });
+                bg_lines[0] = Some(pixels.collect());
+            } else {
+                bg_lines[0] = self.render_layer_line::<0>(vram_banks, kinds[0], line);
             }
-            if self.dispcnt.get_screen_display_bg2() && self.show_bgs[2] {
-                pixel_layers[2] = self.render_background::<2>(vram_banks);
-            }
-            if self.dispcnt.get_screen_display_bg3() && self.show_bgs[3] {
-                pixel_layers[3] = self.render_background::<3>(vram_banks);
-            }
-            let obj_layers = self.render_objs(vram_banks);
-
-            let colorfx = self.bldcnt.get_color_special_effect();
-            let eva = self.bldalpha[0].ev();
-            let evb = self.bldalpha[1].ev();
-
-            let mut backdrop_colour_bytes = [0; 2];
-            backdrop_colour_bytes.copy_from_slice(&self.palette[0..2]);
-            let backdrop_colour = u16::from_le_bytes(backdrop_colour_bytes);
-            let mut pixels: Vec<u16> = vec![backdrop_colour; 256 * 192];
-            for (i, id) in ids.iter().enumerate() {
-                let id: usize = *id;
-                if pixel_layers[id].1 {
-                    let bg = &pixel_layers[id].0;
-                    let bg_width = bg.len();
-                    let bg_height = bg[0].len();
-
-                    let (bg_x_offset, bg_y_offset) = (
-                        self.bgofs[id].get_bits(0, 15) as usize,
-                        self.bgofs[id].get_bits(16, 31) as usize,
-                    );
-
-                    let colorfx = match colorfx {
-                        ColorSpecialEffect::AlphaBlending => {
-                            if i != 0
-                                && self.bldcnt.get_first_target_pixel(ids[i] as u16)
-                                && self.bldcnt.get_second_target_pixel(ids[i - 1] as u16)
-                            {
-                                ColorSpecialEffect::AlphaBlending
-                            } else {
-                                ColorSpecialEffect::None
-                            }
+        }
+        if self.dispcnt.get_screen_display_bg1() && self.show_bgs[1] {
+            bg_lines[1] = self.render_layer_line::<1>(vram_banks, kinds[1], line);
+        }
+        if self.dispcnt.get_screen_display_bg2() && self.show_bgs[2] {
+            bg_lines[2] = self.render_layer_line::<2>(vram_banks, kinds[2], line);
+        }
+        if self.dispcnt.get_screen_display_bg3() && self.show_bgs[3] {
+            bg_lines[3] = self.render_layer_line::<3>(vram_banks, kinds[3], line);
+        }
+        let objs = self.render_objs_line(vram_banks, line);
+        let show_objs = self.dispcnt.get_screen_display_obj();
+        let window_masks = self.get_window_masks(line, &objs);
+
+        let colorfx = self.bldcnt.get_color_special_effect();
+        let eva = self.bldalpha[0].ev();
+        let evb = self.bldalpha[1].ev();
+        let evy = 16.min(self.bldy[0].get_bits(0, 4) as u16);
+
+        let mut backdrop_colour = self.read_palette(0);
+        backdrop_colour.set_bit(15, true);
+
+        let pixels = (0..256)
+            .map(|x| {
+                let window_mask = window_masks[x];
+
+                // find the two front-most layers at this pixel, the backdrop is always behind everything
+                let mut layers = (0..4)
+                    .flat_map(|priority| {
+                        // an OBJ is in front of BGs with the same priority
+                        let obj = Some(objs[x])
+                            .filter(|obj| {
+                                obj.color.get_bit(15) && obj.priority as usize == priority
+                            })
+                            .filter(|_| show_objs && window_mask.get_bit(4))
+                            .map(|obj| Layer {
+                                color: obj.color,
+                                target: 4,
+                                blend: obj.blend,
+                            });
+                        let bgs = ids
+                            .iter()
+                            .filter(move |&&id| self.bgxcnt[id].get_priority() as usize == priority)
+                            .filter(|&&id| window_mask.get_bit(id as u8))
+                            .filter_map(|&id| {
+                                let color = bg_lines[id].as_ref()?[x];
+                                color.get_bit(15).then_some(Layer {
+                                    color,
+                                    target: id as u16,
+                                    blend: ObjBlend::None,
+                                })
+                            });
+                        obj.into_iter().chain(bgs)
+                    })
+                    .chain(std::iter::once(Layer {
+                        color: backdrop_colour,
+                        target: 5,
+                        blend: ObjBlend::None,
+                    }));
+                let top = layers.next().unwrap();
+
+                // 3D pixels keep their 6 bit colour, and any effect on them works at 6 bits too
+                let top_3d = is_3d && top.target == 0;
+                let unchanged = match top_3d {
+                    true => [layer_3d[x][0], layer_3d[x][1], layer_3d[x][2]],
+                    false => bgr555_to_rgb6(top.color),
+                };
+                let Some(bottom) = layers.next() else {
+                    return unchanged;
+                };
+                if !window_mask.get_bit(5) {
+                    return unchanged;
+                }
+
+                // semi-transparent and bitmap OBJs and translucent 3D pixels blend by themselves
+                if self.bldcnt.get_second_target_pixel(bottom.target) {
+                    let alpha = match top.blend {
+                        ObjBlend::SemiTransparent => Some((eva, evb)),
+                        ObjBlend::Bitmap(alpha) => {
+                            let eva = alpha as u16 + 1;
+                            Some((eva, 16 - eva))
                         }
-                        _ => ColorSpecialEffect::None,
+                        ObjBlend::None => None,
                     };
-
-                    (0..256).for_each(|x| {
-                        (0..192).for_each(|y| {
-                            let i = y * 256 + x;
-                            let x = (x + bg_x_offset) % bg_width;
-                            let y = (y + bg_y_offset) % bg_height;
-
-                            let new_pixel = bg[x][y];
-                            let existing_pixel = pixels[i];
-                            match colorfx {
-                                ColorSpecialEffect::AlphaBlending => {
-                                    pixels[i] = ColorSpecialEffect::alpha_blend(
-                                        new_pixel,
-                                        existing_pixel,
-                                        eva,
-                                        evb,
-                                    );
-                                }
-                                _ => {
-                                    let is_transparent = !new_pixel.get_bit(15); // transparent: 0, normal: 1
-                                    pixels[i] = is_transparent.if_else(existing_pixel, new_pixel);
-                                }
-                            }
-                        });
-                    });
+                    if let Some((eva, evb)) = alpha {
+                        let color =
+                            ColorSpecialEffect::alpha_blend(top.color, bottom.color, eva, evb);
+                        return bgr555_to_rgb6(color);
+                    }
+
+                    // 3D alpha goes up to 31, the hardware halves it to get 16ths
+                    if top_3d && layer_3d[x][3] < 31 {
+                        let eva = (layer_3d[x][3] as u16).div_ceil(2);
+                        let bottom = bgr555_to_rgb6(bottom.color);
+                        return ColorSpecialEffect::alpha_blend_rgb6(
+                            unchanged,
+                            bottom,
+                            eva,
+                            16 - eva,
+                        );
+                    }
                 }
 
-                if self.show_objs[3 - i] {
-                    let obj_layer = &obj_layers[3 - i].0;
-                    (0..256).for_each(|x| {
-                        (0..192).for_each(|y| {
-                            let i = y * 256 + x;
-
-                            let new_pixel = obj_layer[x][y];
-                            let existing_pixel = pixels[i];
-                            let is_transparent = !new_pixel.get_bit(15); // transparent: 0, normal: 1
-                            pixels[i] = is_transparent.if_else(existing_pixel, new_pixel);
-                        });
-                    });
-
-                    // hack for games like sushi the cat where everything is on 1 priority
-                    // i'm not in the mood to make this better. this whole thing needs refactoring
-                    let priority = self.bgxcnt[id].get_priority() as usize;
-                    if priority != i {
-                        let obj_layer = &obj_layers[priority].0;
-                        (0..256).for_each(|x| {
-                            (0..192).for_each(|y| {
-                                let i = y * 256 + x;
-
-                                let new_pixel = obj_layer[x][y];
-                                let existing_pixel = pixels[i];
-                                let is_transparent = !new_pixel.get_bit(15); // transparent: 0, normal: 1
-                                pixels[i] = is_transparent.if_else(existing_pixel, new_pixel);
-                            });
-                        });
+                if !self.bldcnt.get_first_target_pixel(top.target) {
+                    return unchanged;
+                }
+                if top_3d {
+                    return match colorfx {
+                        ColorSpecialEffect::AlphaBlending
+                            if self.bldcnt.get_second_target_pixel(bottom.target) =>
+                        {
+                            let bottom = bgr555_to_rgb6(bottom.color);
+                            ColorSpecialEffect::alpha_blend_rgb6(unchanged, bottom, eva, evb)
+                        }
+                        ColorSpecialEffect::BrightnessIncrease => {
+                            ColorSpecialEffect::brightness_increase_rgb6(unchanged, evy)
+                        }
+                        ColorSpecialEffect::BrightnessDecrease => {
+                            ColorSpecialEffect::brightness_decrease_rgb6(unchanged, evy)
+                        }
+                        _ => unchanged,
                     };
                 }
-            }
+                let color = match colorfx {
+                    ColorSpecialEffect::None => return unchanged,
+                    ColorSpecialEffect::AlphaBlending => {
+                        match self.bldcnt.get_second_target_pixel(bottom.target) {
+                            true => {
+                                ColorSpecialEffect::alpha_blend(top.color, bottom.color, eva, evb)
+                            }
+                            false => return unchanged,
+                        }
+                    }
+                    ColorSpecialEffect::BrightnessIncrease => {
+                        ColorSpecialEffect::brightness_increase(top.color, evy)
+                    }
+                    ColorSpecialEffect::BrightnessDecrease => {
+                        ColorSpecialEffect::brightness_decrease(top.color, evy)
+                    }
+                };
+                bgr555_to_rgb6(color)
+            })
+            .collect();
+
+        (pixels, bg_lines)
+    }
 
-            let image_data = egui::ImageData::from(egui::ColorImage {
-                pixels: pixels
-                    .iter()
-                    .map(|&pixel| {
-                        let r = ((pixel.get_bits(0, 4) as f32) * COLOUR_MULT) as u8;
-                        let g = ((pixel.get_bits(5, 9) as f32) * COLOUR_MULT) as u8;
-                        let b = ((pixel.get_bits(10, 14) as f32) * COLOUR_MULT) as u8;
-                        egui::Color32::from_rgb(r, g, b)
-                    })
-                    .collect(),
-                size: [256, 192],
-            });
-
-            GpuRenderResult::new(image_data, pixel_layers, self.generate_tilemap(vram_banks))
-        } else {
-            GpuRenderResult::new_empty(egui::ImageData::from(egui::ColorImage {
-                pixels: vec![egui::Color32::from_rgb(0, 0, 100 + 10 * bg_mode); 256 * 192],
-                size: [256, 192],
-            }))
+    // what each BG is in the current BG mode
+    fn get_background_kinds(&self) -> [BackgroundKind; 4] {
+        use BackgroundKind::*;
+        match self.dispcnt.get_bg_mode() {
+            0 => [Text, Text, Text, Text],
+            1 => [Text, Text, Text, Affine],
+            2 => [Text, Text, Affine, Affine],
+            3 => [Text, Text, Text, Extended],
+            4 => [Text, Text, Affine, Extended],
+            5 => [Text, Text, Extended, Extended],
+            6 if ENGINE_A => [Text, None, LargeBitmap, None],
+            _ => [None; 4], // engine B doesn't have mode 6, and 7 is invalid
         }
     }
+
+    fn render_layer_line<const BG: u8>(
+        &self,
+        vram_banks: &VramBanks,
+        kind: BackgroundKind,
+        line: usize,
+    ) -> Option<Vec<u16>> {
+        // mosaic repeats the top left pixel of each block
+        let (mosaic_width, mosaic_height) = match self.bgxcnt[BG as usize].get_mosaic() {
+            true => self.mosaic.get_bg_size(),
+            false => (1, 1),
+        };
+        let mosaic_y = line % mosaic_height;
+
+        let mut pixels = match kind {
+            BackgroundKind::None => return None,
+            BackgroundKind::Text => self.render_background_line::<BG>(vram_banks, line - mosaic_y),
+            _ => self.render_affine_background_line::<BG>(vram_banks, kind, mosaic_y),
+        };
+        for x in 0..256 {
+            pixels[x] = pixels[x - x % mosaic_width];
+        }
+
+        Some(pixels)
+    }
 }
@@ -1,34 +1,58 @@
 use crate::nds::{
     gpus::{gpu2d::Gpu2d, vram::VramBanks},
-    Bits, Bytes, IfElse,
+    Bits, Bytes,
 };
 
+// how an OBJ pixel mixes with whatever is below it
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum ObjBlend {
+    #[default]
+    None,
+    SemiTransparent, // always alpha blended with BLDALPHA, whatever BLDCNT says
+    Bitmap(u8),      // alpha blended with its own 4 bit alpha
+}
+
+// one pixel of the OBJ line buffer, bit 15 of the colour is set for opaque pixels
+// the OBJ window is tracked separately from the colour, OBJ window OBJs never draw anything
+#[derive(Clone, Copy, Default)]
+pub struct ObjPixel {
+    pub color: u16,
+    pub priority: u8,
+    pub blend: ObjBlend,
+    pub window: bool,
+}
+
 impl<const ENGINE_A: bool> Gpu2d<ENGINE_A> {
-    pub fn render_objs(&self, vram_banks: &VramBanks) -> Vec<(Vec<Vec<u16>>, bool)> {
-        let mut pixels: Vec<(Vec<Vec<u16>>, bool)> = vec![(vec![vec![0; 255]; 511], true); 4];
+    // one line of OBJs, only the front-most OBJ at each pixel is kept
+    pub fn render_objs_line(&self, vram_banks: &VramBanks, line: usize) -> Vec<ObjPixel> {
+        let mut pixels = vec![ObjPixel::default(); 256];
         let obj_vram_base: usize = if ENGINE_A { 0x06400000 } else { 0x06600000 };
         let tile_obj_mapping = self.dispcnt.get_tile_obj_mapping();
         let tile_obj_1d_boundary = self.dispcnt.get_tile_obj_1d_boundary();
         let is_extended_palette = self.dispcnt.get_obj_extended_palettes();
-        let boundary_value = if !tile_obj_mapping {
-            32
-        } else {
-            match tile_obj_1d_boundary {
-                0 => 32,
-                1 => 64,
-                2 => 128,
-                3 => {
-                    if ENGINE_A {
-                        256
-                    } else {
-                        128
-                    }
+        let boundary_value = match tile_obj_1d_boundary {
+            0 => 32,
+            1 => 64,
+            2 => 128,
+            3 => {
+                if ENGINE_A {
+                    256
+                } else {
+                    128
                 }
-                _ => unreachable!(),
             }
+            _ => unreachable!(),
+        };
+        let bitmap_obj_mapping = self.dispcnt.get_bitmap_obj_mapping();
+        let bitmap_boundary_value = match self.dispcnt.get_bitmap_obj_1d_boundary() {
+            false => 128,
+            true => 256,
         };
+        let (mosaic_width, mosaic_height) = self.mosaic.get_obj_size();
 
-        for i in (0..128).rev() {
+        // like the hardware, OBJs are drawn in OAM order and a pixel is only replaced by one with a higher priority
+        // so lower OAM indices win when the priorities are the same
+        for i in 0..128 {
             let addr = i * 8;
 
             let mut oam0 = [0; 2];
@@ -41,17 +65,13 @@ impl<const ENGINE_A: bool> Gpu2d<ENGINE_A> {
             let oam1 = oam1.into_halfword();
             let oam2 = oam2.into_halfword();
 
-            if oam0.get_bit(9) {
+            // bit 9 is double size for affine OBJs and disables the rest
+            let is_affine = oam0.get_bit(8);
+            let is_double_size = is_affine && oam0.get_bit(9);
+            if !is_affine && oam0.get_bit(9) {
                 continue;
             }
 
-            // if !ENGINE_A && i == 55 {
-            //     println!("{:08X},{:08X},{:08X}", oam0, oam1, oam2);
-            // }
-
-            let character_name = oam2.get_bits(0, 9) as usize;
-            let palette_number = oam2.get_bits(12, 15);
-            let palette_offset_address = (palette_number as usize) << 4;
             let (width, height) = match (oam0.get_bits(14, 15), oam1.get_bits(14, 15)) {
                 // shape, size
                 (0, 0) => (8, 8),
@@ -66,103 +86,163 @@ impl<const ENGINE_A: bool> Gpu2d<ENGINE_A> {
                 (2, 1) => (8, 32),
                 (2, 2) => (16, 32),
                 (2, 3) => (32, 64),
-                (3, 0..=3) => continue,
+                (3, 0..=3) => continue, // prohibited
                 _ => unreachable!(),
             };
+            // double size OBJs take up twice the space, but the OBJ itself stays the same size
+            let (box_width, box_height) = match is_double_size {
+                true => (width * 2, height * 2),
+                false => (width, height),
+            };
 
+            // OBJs wrap around at the bottom of the 256 line area
+            let y = oam0.get_bits(0, 7) as usize;
+            let box_y = (line + 256 - y) % 256;
+            if box_y >= box_height {
+                continue;
+            }
+
+            let character_name = oam2.get_bits(0, 9) as usize;
+            let priority = oam2.get_bits(10, 11) as u8;
+            let palette_number = oam2.get_bits(12, 15) as usize;
+            let is_mosaic = oam0.get_bit(12);
             let is_256x1 = oam0.get_bit(13);
-            let horizontal_flip = oam1.get_bit(12);
-            let vertical_flip = oam1.get_bit(13);
             let x = oam1.get_bits(0, 8) as usize;
-            let y = oam0.get_bits(0, 7) as usize;
-            for obj_quad_y in 0..(height / 8) {
-                for obj_quad_x in 0..(width / 8) {
-                    // 16/16 vs 256/1
-                    if is_256x1 {
-                        if is_extended_palette {
-                            // TODO: obj extended palette
-                            // println!("obj extended palette");
-                        } else {
-                            let tile_address = (character_name * (boundary_value / 32)
-                                + obj_quad_x
-                                + (obj_quad_y * (width / 8)))
-                                * 64;
-                            let obj_bytes =
-                                vram_banks.read_slice::<64>(obj_vram_base + tile_address);
-                            let obj_bytes = obj_bytes.unwrap_or([0; 64]);
-                            (0..obj_bytes.len()).for_each(|obj_byte_i| {
-                                let obj_byte = obj_bytes[obj_byte_i];
-                                let palette_address = 512 + obj_byte as usize * 2;
-                                let mut obj_bytes = [0; 2];
-                                obj_bytes.copy_from_slice(
-                                    &self.palette[palette_address..palette_address + 2],
-                                );
-                                let mut color = u16::from_le_bytes(obj_bytes);
-                                color.set_bit(15, obj_byte != 0);
-
-                                let obj_pixel_x = obj_byte_i % 8;
-                                let obj_pixel_y = obj_byte_i / 8;
-                                let pixel_x = x + (obj_quad_x * 8) + obj_pixel_x;
-                                let pixel_y = y + (obj_quad_y * 8) + obj_pixel_y;
-
-                                let priority = oam2.get_bits(10, 11) as usize;
-                                pixels[priority].0[pixel_x % 511][pixel_y % 255] = (obj_byte != 0)
-                                    .if_else(
-                                        color,
-                                        pixels[priority].0[pixel_x % 511][pixel_y % 255],
-                                    );
-                            });
-                        }
-                    } else {
-                        let tile_address = (character_name * (boundary_value / 32)
-                            + obj_quad_x
-                            + (obj_quad_y * (width / 8)))
-                            * 32;
-                        let obj_bytes = vram_banks.read_slice::<32>(obj_vram_base + tile_address);
-                        let obj_bytes = obj_bytes.unwrap_or([0; 32]);
-                        (0..obj_bytes.len()).for_each(|obj_byte_i| {
-                            let obj_byte = obj_bytes[obj_byte_i];
-                            let l_obj_palette_i = obj_byte.get_bits(0, 3) as usize;
-                            let r_obj_palette_i = obj_byte.get_bits(4, 7) as usize;
-                            let l_palette_address =
-                                512 + ((l_obj_palette_i | palette_offset_address) * 2);
-                            let r_palette_address =
-                                512 + ((r_obj_palette_i | palette_offset_address) * 2);
-
-                            let mut l_obj_bytes = [0; 2];
-                            let mut r_obj_bytes = [0; 2];
-                            l_obj_bytes.copy_from_slice(
-                                &self.palette[l_palette_address..l_palette_address + 2],
-                            );
-                            r_obj_bytes.copy_from_slice(
-                                &self.palette[r_palette_address..r_palette_address + 2],
-                            );
-                            let mut l_color = u16::from_le_bytes(l_obj_bytes);
-                            let mut r_color = u16::from_le_bytes(r_obj_bytes);
-                            l_color.set_bit(15, obj_byte != 0 && l_obj_palette_i != 0);
-                            r_color.set_bit(15, obj_byte != 0 && r_obj_palette_i != 0);
-
-                            let l_obj_pixel_x = (obj_byte_i * 2) % 8;
-                            let r_obj_pixel_x = ((obj_byte_i * 2) + 1) % 8;
-                            let obj_pixel_y = obj_byte_i * 2 / 8;
-
-                            let l_pixel_x = x + (obj_quad_x * 8) + l_obj_pixel_x;
-                            let r_pixel_x = x + (obj_quad_x * 8) + r_obj_pixel_x;
-                            let pixel_y = y + (obj_quad_y * 8) + obj_pixel_y;
-
-                            let priority = oam2.get_bits(10, 11) as usize;
-                            pixels[priority].0[l_pixel_x % 511][pixel_y % 255] =
-                                (obj_byte != 0 && l_obj_palette_i != 0).if_else(
-                                    l_color,
-                                    pixels[priority].0[l_pixel_x % 511][pixel_y % 255],
-                                );
-                            pixels[priority].0[r_pixel_x % 511][pixel_y % 255] =
-                                (obj_byte != 0 && r_obj_palette_i != 0).if_else(
-                                    r_color,
-                                    pixels[priority].0[r_pixel_x % 511][pixel_y % 255],
-                                );
-                        });
-                    }
+            let obj_mode = oam0.get_bits(10, 11);
+            let is_obj_window = obj_mode == 2; // never drawn, only marks the window
+            if !is_obj_window && !self.show_objs[priority as usize] {
+                continue;
+            }
+            let blend = match obj_mode {
+                1 => ObjBlend::SemiTransparent,
+                3 => ObjBlend::Bitmap(palette_number as u8), // the palette number is the alpha instead
+                _ => ObjBlend::None,
+            };
+
+            let box_y = match is_mosaic {
+                true => box_y - box_y % mosaic_height,
+                false => box_y,
+            };
+
+            // the parameters are spread across the unused 4th halfword of 4 OBJs in a row
+            // affine OBJs are rotated around their centre, and can't be flipped
+            let (pa, pb, pc, pd) = if is_affine {
+                let group = oam1.get_bits(9, 13) as usize * 32;
+                let read_parameter = |i: usize| {
+                    i16::from_le_bytes([self.oam[group + i * 8 + 6], self.oam[group + i * 8 + 7]])
+                        as i32
+                };
+                (
+                    read_parameter(0),
+                    read_parameter(1),
+                    read_parameter(2),
+                    read_parameter(3),
+                )
+            } else {
+                let horizontal_flip = oam1.get_bit(12);
+                let vertical_flip = oam1.get_bit(13);
+                (
+                    if horizontal_flip { -0x100 } else { 0x100 },
+                    0,
+                    0,
+                    if vertical_flip { -0x100 } else { 0x100 },
+                )
+            };
+            let (half_width, half_height) = (width as i32 / 2, height as i32 / 2);
+            let (box_half_width, box_half_height) = (box_width as i32 / 2, box_height as i32 / 2);
+            let dy = box_y as i32 - box_half_height;
+            // flipping mirrors around the middle of the OBJ, which is between two pixels
+            let flip_adjust = |p: i32| (!is_affine && p < 0) as i32;
+
+            // bit 15 is set for opaque pixels
+            let fetch = |obj_x: usize, obj_y: usize| -> u16 {
+                // bitmap OBJs are direct colour, bit 15 of each pixel is whether it's drawn
+                if obj_mode == 3 {
+                    let (bitmap_address, row_pitch) = match bitmap_obj_mapping {
+                        0 => (
+                            (character_name & 0x0F) * 0x10 + (character_name & 0x3F0) * 0x80,
+                            128 * 2,
+                        ),
+                        1 => (
+                            (character_name & 0x1F) * 0x10 + (character_name & 0x3E0) * 0x80,
+                            256 * 2,
+                        ),
+                        _ => (character_name * bitmap_boundary_value, width * 2),
+                    };
+                    let address = obj_vram_base + bitmap_address + obj_y * row_pitch + obj_x * 2;
+                    return u16::from_le_bytes(
+                        vram_banks.read_slice::<2>(address).unwrap_or([0; 2]),
+                    );
+                }
+
+                let tile_size = if is_256x1 { 64 } else { 32 };
+                let (quad_x, quad_y) = (obj_x / 8, obj_y / 8);
+                let tile_address = if tile_obj_mapping {
+                    character_name * boundary_value + (quad_y * (width / 8) + quad_x) * tile_size
+                } else {
+                    // 2D mapping lays tiles out in a 32x32 grid
+                    (character_name + quad_y * 32 + quad_x * (tile_size / 32)) * 32
+                };
+                let pixel_i = (obj_y % 8) * 8 + obj_x % 8;
+
+                let index = if is_256x1 {
+                    let address = obj_vram_base + tile_address + pixel_i;
+                    vram_banks.read_slice::<1>(address).map_or(0, |b| b[0]) as usize
+                } else {
+                    let address = obj_vram_base + tile_address + pixel_i / 2;
+                    let byte = vram_banks.read_slice::<1>(address).map_or(0, |b| b[0]);
+                    (byte >> ((pixel_i & 1) * 4)) as usize & 0xF
+                };
+                if index == 0 {
+                    return 0;
+                }
+
+                let palette_index = if is_256x1 {
+                    index
+                } else {
+                    palette_number << 4 | index
+                };
+                let mut color = self.read_palette(256 + palette_index);
+                color.set_bit(15, true);
+                color
+            };
+
+            // alpha 0 hides bitmap OBJs, and 2D mapping with both bits set isn't allowed
+            if obj_mode == 3 && (palette_number == 0 || bitmap_obj_mapping == 3) {
+                continue;
+            }
+            if obj_mode != 3 && is_256x1 && is_extended_palette {
+                // TODO: obj extended palette
+                continue;
+            }
+
+            for box_x in 0..box_width {
+                let screen_x = (x + box_x) % 512;
+                if screen_x >= 256 {
+                    continue;
+                }
+
+                let box_x = match is_mosaic {
+                    true => box_x - box_x % mosaic_width,
+                    false => box_x,
+                };
+                let dx = box_x as i32 - box_half_width;
+                let obj_x = ((pa * dx + pb * dy) >> 8) + half_width - flip_adjust(pa);
+                let obj_y = ((pc * dx + pd * dy) >> 8) + half_height - flip_adjust(pd);
+                if !(0..width as i32).contains(&obj_x) || !(0..height as i32).contains(&obj_y) {
+                    continue;
+                }
+
+                let color = fetch(obj_x as usize, obj_y as usize);
+                if !color.get_bit(15) {
+                    continue;
+                }
+
+                let pixel = &mut pixels[screen_x];
+                if is_obj_window {
+                    pixel.window = true;
+                } else if !pixel.color.get_bit(15) || priority < pixel.priority {
+                    (pixel.color, pixel.priority, pixel.blend) = (color, priority, blend);
                 }
             }
         }
@@ -171,6 +251,7 @@ impl<const ENGINE_A: bool> Gpu2d<ENGINE_A> {
     }
 
     // this sucks
+    #[cfg(feature = "ui")]
     pub fn generate_tilemap(&self, vram_banks: &VramBanks) -> (Vec<Vec<u16>>, usize) {
         let tile_obj_mapping = self.dispcnt.get_tile_obj_mapping();
         let tile_obj_1d_boundary = self.dispcnt.get_tile_obj_1d_boundary();
@@ -0,0 +1,33 @@
+use crate::nds::gpus::gpu2d::Gpu2d;
+
+use super::obj::ObjPixel;
+
+impl<const ENGINE_A: bool> Gpu2d<ENGINE_A> {
+    // which layers (and colour special effects) are allowed at each pixel of the line, see Windows::get_window_control
+    // window 0 takes priority over window 1, which takes priority over the OBJ window
+    pub(super) fn get_window_masks(&self, line: usize, objs: &[ObjPixel]) -> Vec<u8> {
+        let win0 = self.dispcnt.get_window_0_display_flag();
+        let win1 = self.dispcnt.get_window_1_display_flag();
+        let obj_win = self.dispcnt.get_obj_window_display_flag();
+        if !win0 && !win1 && !obj_win {
+            return vec![0x3F; 256];
+        }
+
+        let windows = &self.windows;
+        let win0 = win0 && windows.contains_line(0, line);
+        let win1 = win1 && windows.contains_line(1, line);
+        (0..256)
+            .map(|x| {
+                if win0 && windows.contains_x(0, x) {
+                    windows.get_window_control(0)
+                } else if win1 && windows.contains_x(1, x) {
+                    windows.get_window_control(1)
+                } else if obj_win && objs[x].window {
+                    windows.get_obj_window_control()
+                } else {
+                    windows.get_outside_control()
+                }
+            })
+            .collect()
+    }
+}
@@ -1,2 +1,3 @@
+mod capture;
 mod graphics;
 mod vram_display;
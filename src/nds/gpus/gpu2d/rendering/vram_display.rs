@@ -1,33 +1,20 @@
-use crate::nds::{gpus::gpu2d::Gpu2d, shared::Shared, Bits};
-
-const COLOUR_MULT: f32 = 255.0 / 31.0;
+use crate::nds::gpus::{gpu2d::Gpu2d, vram::VramBanks};
 
 impl<const ENGINE_A: bool> Gpu2d<ENGINE_A> {
     // Display Mode: VRAM Display
-    pub fn render_vram(&self, shared: &Shared) -> egui::ImageData {
-        let mut pixels = Vec::with_capacity(256 * 192);
-        for y in 0..=191 {
-            for x in 0..=255 {
-                let vram_block = self.dispcnt.get_vram_block();
-                let addr_offset = (vram_block * 0x20000) as usize;
-                let addr = addr_offset + (y * 256 + x) as usize * 2;
-                let bytes = shared.gpus.vram_banks.read_slice::<2>(0x06800000 + addr);
-                let halfword = match bytes {
-                    Some(bytes) => u16::from_le_bytes(bytes),
-                    None => 0,
-                };
-                let r = ((halfword.get_bits(0, 4) as f32) * COLOUR_MULT) as u8;
-                let g = ((halfword.get_bits(5, 9) as f32) * COLOUR_MULT) as u8;
-                let b = ((halfword.get_bits(10, 14) as f32) * COLOUR_MULT) as u8;
+    pub fn render_vram_line(&self, vram_banks: &VramBanks, line: usize) -> Vec<u16> {
+        let vram_block = self.dispcnt.get_vram_block();
+        let addr_offset = (vram_block * 0x20000) as usize;
 
-                let pixel = egui::Color32::from_rgb(r, g, b);
-                pixels.push(pixel);
-            }
-        }
-
-        egui::ImageData::from(egui::ColorImage {
-            pixels,
-            size: [256, 192],
-        })
+        (0..256)
+            .map(|x| {
+                let addr = addr_offset + (line * 256 + x) * 2;
+                let bytes = vram_banks.read_slice::<2>(0x06800000 + addr);
+                match bytes {
+                    Some(bytes) => u16::from_le_bytes(bytes) & 0x7FFF,
+                    None => 0,
+                }
+            })
+            .collect()
     }
 }
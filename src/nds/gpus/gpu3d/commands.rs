@@ -0,0 +1,256 @@
+use super::{
+    lighting::{rgb555, unpack_direction},
+    matrix::{Matrix, MatrixMode},
+    models::TextureTransform,
+    polygon::{clip_polygon, PolygonState, PrimitiveType, Vertex},
+    Gpu3d,
+};
+
+// parameter count and how many cycles each command keeps the geometry engine busy for
+fn command_info(command: u8) -> Option<(u8, u32)> {
+    match command {
+        0x10 => Some((1, 1)),   // MTX_MODE
+        0x11 => Some((0, 17)),  // MTX_PUSH
+        0x12 => Some((1, 36)),  // MTX_POP
+        0x13 => Some((1, 17)),  // MTX_STORE
+        0x14 => Some((1, 36)),  // MTX_RESTORE
+        0x15 => Some((0, 19)),  // MTX_IDENTITY
+        0x16 => Some((16, 34)), // MTX_LOAD_4x4
+        0x17 => Some((12, 30)), // MTX_LOAD_4x3
+        0x18 => Some((16, 35)), // MTX_MULT_4x4
+        0x19 => Some((12, 31)), // MTX_MULT_4x3
+        0x1A => Some((9, 28)),  // MTX_MULT_3x3
+        0x1B => Some((3, 22)),  // MTX_SCALE
+        0x1C => Some((3, 22)),  // MTX_TRANS
+        0x20 => Some((1, 1)),   // COLOR
+        0x21 => Some((1, 9)),   // NORMAL
+        0x22 => Some((1, 1)),   // TEXCOORD
+        0x23 => Some((2, 9)),   // VTX_16
+        0x24 => Some((1, 8)),   // VTX_10
+        0x25 => Some((1, 8)),   // VTX_XY
+        0x26 => Some((1, 8)),   // VTX_XZ
+        0x27 => Some((1, 8)),   // VTX_YZ
+        0x28 => Some((1, 8)),   // VTX_DIFF
+        0x29 => Some((1, 1)),   // POLYGON_ATTR
+        0x2A => Some((1, 1)),   // TEXIMAGE_PARAM
+        0x2B => Some((1, 1)),   // PLTT_BASE
+        0x30 => Some((1, 4)),   // DIF_AMB
+        0x31 => Some((1, 4)),   // SPE_EMI
+        0x32 => Some((1, 6)),   // LIGHT_VECTOR
+        0x33 => Some((1, 1)),   // LIGHT_COLOR
+        0x34 => Some((32, 32)), // SHININESS
+        0x40 => Some((1, 1)),   // BEGIN_VTXS
+        0x41 => Some((0, 1)),   // END_VTXS
+        0x50 => Some((1, 392)), // SWAP_BUFFERS
+        0x60 => Some((1, 1)),   // VIEWPORT
+        0x70 => Some((3, 103)), // BOX_TEST
+        0x71 => Some((2, 9)),   // POS_TEST
+        0x72 => Some((1, 5)),   // VEC_TEST
+        _ => None,
+    }
+}
+
+pub fn parameter_count(command: u8) -> Option<u8> {
+    command_info(command).map(|(params, _)| params)
+}
+
+pub fn cycles(command: u8) -> u32 {
+    command_info(command).map_or(0, |(_, cycles)| cycles)
+}
+
+impl Gpu3d {
+    pub(super) fn execute(&mut self, command: u8, params: &[u32]) {
+        match command {
+            0x10 => self.matrices.mode = MatrixMode::from(params[0]),
+            0x11 => self.matrices.push(),
+            0x12 => self.matrices.pop(params[0]),
+            0x13 => self.matrices.store(params[0]),
+            0x14 => self.matrices.restore(params[0]),
+            0x15 => self.matrices.load(Matrix::IDENTITY),
+            0x16 => self.matrices.load(Matrix::from_4x4(params)),
+            0x17 => self.matrices.load(Matrix::from_4x3(params)),
+            0x18 => self.matrices.multiply(Matrix::from_4x4(params), true),
+            0x19 => self.matrices.multiply(Matrix::from_4x3(params), true),
+            0x1A => self.matrices.multiply(Matrix::from_3x3(params), true),
+            0x1B => {
+                let [x, y, z] = [params[0], params[1], params[2]].map(|p| p as i32);
+                self.matrices.multiply(Matrix::scale(x, y, z), false);
+            }
+            0x1C => {
+                let [x, y, z] = [params[0], params[1], params[2]].map(|p| p as i32);
+                self.matrices.multiply(Matrix::translate(x, y, z), true);
+            }
+
+            0x20 => self.color = rgb555(params[0]),
+            0x21 => self.set_normal(params[0]),
+            0x22 => self.set_texcoord(params[0]),
+            0x23 => {
+                self.vertex = [params[0] as i16, (params[0] >> 16) as i16, params[1] as i16];
+                self.submit_vertex();
+            }
+            0x24 => {
+                // 4.6 fixed point, shifted up to the usual 4.12
+                self.vertex = [0, 10, 20].map(|shift| ((params[0] >> shift) << 6) as i16);
+                self.submit_vertex();
+            }
+            0x25 => {
+                self.vertex[0] = params[0] as i16;
+                self.vertex[1] = (params[0] >> 16) as i16;
+                self.submit_vertex();
+            }
+            0x26 => {
+                self.vertex[0] = params[0] as i16;
+                self.vertex[2] = (params[0] >> 16) as i16;
+                self.submit_vertex();
+            }
+            0x27 => {
+                self.vertex[1] = params[0] as i16;
+                self.vertex[2] = (params[0] >> 16) as i16;
+                self.submit_vertex();
+            }
+            0x28 => {
+                for (i, shift) in [0, 10, 20].into_iter().enumerate() {
+                    let diff = (((params[0] >> shift) << 22) as i32 >> 22) as i16;
+                    self.vertex[i] = self.vertex[i].wrapping_add(diff);
+                }
+                self.submit_vertex();
+            }
+
+            0x29 => self.polygon_attr = params[0].into(),
+            0x2A => self.teximage_param = params[0].into(),
+            0x2B => self.palette_base = params[0] & 0x1FFF,
+
+            0x30 => {
+                if let Some(color) = self.lighting.set_diffuse_ambient(params[0]) {
+                    self.color = color;
+                }
+            }
+            0x31 => self.lighting.set_specular_emission(params[0]),
+            0x32 => self
+                .lighting
+                .set_light_vector(params[0], &self.matrices.vector),
+            0x33 => self.lighting.set_light_color(params[0]),
+            0x34 => self.lighting.set_shininess(params),
+
+            0x40 => self
+                .assembler
+                .begin(PrimitiveType::from(params[0]), self.polygon_attr),
+            0x41 => {} // doesn't actually do anything
+
+            0x50 => {
+                self.swap_params = params[0] & 3;
+                self.swap_pending = true;
+            }
+            0x60 => self.viewport = params[0].into(),
+
+            0x70 => self.box_test(params),
+            0x71 => {
+                self.vertex = [params[0] as i16, (params[0] >> 16) as i16, params[1] as i16];
+                let [x, y, z] = self.vertex.map(|c| c as i32);
+                self.pos_test_result = self.matrices.clip.transform([x, y, z, 0x1000]);
+            }
+            0x72 => {
+                let vector = self
+                    .matrices
+                    .vector
+                    .transform_direction(unpack_direction(params[0]));
+                // 1.3.12, with the sign expanded to fill the halfword
+                self.vec_test_result = vector.map(|c| ((c as i16) << 3) >> 3);
+            }
+
+            _ => unreachable!("invalid geometry command {:#04X}", command),
+        }
+    }
+
+    fn set_normal(&mut self, value: u32) {
+        let normal = unpack_direction(value);
+
+        if self.teximage_param.get_transform() == TextureTransform::Normal {
+            let sums = self.matrices.texture.transform_direction_unshifted(normal);
+            self.texcoord =
+                std::array::from_fn(|i| self.raw_texcoord[i].wrapping_add((sums[i] >> 21) as i16));
+        }
+
+        let normal = self.matrices.vector.transform_direction(normal);
+        self.color = self.lighting.apply(normal, self.assembler.attr);
+    }
+
+    fn set_texcoord(&mut self, value: u32) {
+        self.raw_texcoord = [value as i16, (value >> 16) as i16];
+
+        self.texcoord = if self.teximage_param.get_transform() == TextureTransform::TexCoord {
+            // (s, t, 1/16, 1/16) through the texture matrix, s and t are 1.11.4 so 1/16 is just 1
+            let [s, t] = self.raw_texcoord.map(|c| c as i32);
+            let transformed = self.matrices.texture.transform([s, t, 1, 1]);
+            [transformed[0] as i16, transformed[1] as i16]
+        } else {
+            self.raw_texcoord
+        };
+    }
+
+    // whether any face of the box ends up inside the view volume after clipping
+    fn box_test(&mut self, params: &[u32]) {
+        let [x, y, z, width, height, depth] = [
+            params[0],
+            params[0] >> 16,
+            params[1],
+            params[1] >> 16,
+            params[2],
+            params[2] >> 16,
+        ]
+        .map(|p| p as i16 as i32);
+
+        let corners: [Vertex; 8] = std::array::from_fn(|i| {
+            let corner = [
+                x + if i & 1 != 0 { width } else { 0 },
+                y + if i & 2 != 0 { height } else { 0 },
+                z + if i & 4 != 0 { depth } else { 0 },
+                0x1000,
+            ];
+            Vertex {
+                clip: self.matrices.clip.transform(corner),
+                ..Default::default()
+            }
+        });
+
+        const FACES: [[usize; 4]; 6] = [
+            [0, 1, 3, 2],
+            [4, 5, 7, 6],
+            [0, 1, 5, 4],
+            [2, 3, 7, 6],
+            [0, 2, 6, 4],
+            [1, 3, 7, 5],
+        ];
+        self.box_test_result = FACES.iter().any(|face| {
+            let vertices = face.map(|i| corners[i]);
+            clip_polygon(&vertices, true).is_some()
+        });
+    }
+
+    fn submit_vertex(&mut self) {
+        let [x, y, z] = self.vertex.map(|c| c as i32);
+
+        if self.teximage_param.get_transform() == TextureTransform::Vertex {
+            let sums = self
+                .matrices
+                .texture
+                .transform_direction_unshifted([x, y, z]);
+            self.texcoord =
+                std::array::from_fn(|i| self.raw_texcoord[i].wrapping_add((sums[i] >> 24) as i16));
+        }
+
+        let vertex = Vertex {
+            clip: self.matrices.clip.transform([x, y, z, 0x1000]),
+            screen: [0, 0],
+            color: self.color,
+            texcoord: self.texcoord,
+        };
+        let state = PolygonState {
+            teximage_param: self.teximage_param,
+            palette_base: self.palette_base,
+            viewport: self.viewport,
+        };
+        self.assembler
+            .add_vertex(vertex, &state, &mut self.geometry);
+    }
+}
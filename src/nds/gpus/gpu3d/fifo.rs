@@ -0,0 +1,99 @@
+use std::collections::VecDeque;
+
+use super::commands;
+
+// the real thing is a 256 entry FIFO with a 4 entry PIPE in front of it, we treat them as one
+const FIFO_CAPACITY: usize = 256;
+
+#[derive(Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub struct FifoEntry {
+    pub command: u8,
+    pub param: u32,
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct GxFifo {
+    entries: VecDeque<FifoEntry>,
+
+    // state for packed commands written to 0x04000400
+    packed_commands: u32, // the command bytes we haven't got to yet, lowest first
+    packed_count: u8,     // how many of them are left
+    packed_params: u8,    // parameters the current command is still waiting for
+}
+
+impl GxFifo {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.entries.len() >= FIFO_CAPACITY
+    }
+
+    pub fn is_less_than_half_full(&self) -> bool {
+        self.entries.len() < FIFO_CAPACITY / 2
+    }
+
+    // the caller has to make room first
+    fn push(&mut self, command: u8, param: u32) {
+        self.entries.push_back(FifoEntry { command, param });
+    }
+
+    // written to 0x04000400, one word holds up to 4 commands and the words after it are their parameters
+    pub fn write_packed(&mut self, value: u32) {
+        if self.packed_count == 0 {
+            self.packed_commands = value;
+            self.packed_count = 4;
+        } else {
+            self.push(self.packed_commands as u8, value);
+            self.packed_params -= 1;
+            if self.packed_params > 0 {
+                return;
+            }
+
+            self.packed_commands >>= 8;
+            self.packed_count -= 1;
+        }
+
+        // commands without parameters go in straight away, the trailing zeros are just padding
+        while self.packed_count > 0 && self.packed_commands != 0 {
+            let command = self.packed_commands as u8;
+            match commands::parameter_count(command) {
+                Some(0) => self.push(command, 0),
+                Some(params) => {
+                    self.packed_params = params;
+                    return;
+                }
+                None => {} // NOPs and invalid commands are skipped
+            }
+
+            self.packed_commands >>= 8;
+            self.packed_count -= 1;
+        }
+        self.packed_count = 0;
+    }
+
+    // written to 0x04000440..=0x040005FF, every write is one entry
+    pub fn write_unpacked(&mut self, command: u8, value: u32) {
+        if commands::parameter_count(command).is_some() {
+            self.push(command, value);
+        }
+    }
+
+    // takes the next command and its parameters, if they've all arrived
+    pub fn pop_command(&mut self, params: &mut Vec<u32>) -> Option<u8> {
+        let command = self.entries.front()?.command;
+        let count = commands::parameter_count(command)?.max(1) as usize;
+        if self.entries.len() < count {
+            return None;
+        }
+
+        params.clear();
+        params.extend(self.entries.drain(..count).map(|entry| entry.param));
+        Some(command)
+    }
+}
@@ -0,0 +1,118 @@
+use super::{matrix::Matrix, models::PolygonAttr};
+
+#[derive(Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
+pub struct Light {
+    direction: [i32; 3], // already through the vector matrix, .12
+    half: [i32; 3], // halfway between the light and the line of sight, for the specular highlight
+    color: [u8; 3],
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Lighting {
+    lights: [Light; 4],
+
+    diffuse: [u8; 3],
+    ambient: [u8; 3],
+    specular: [u8; 3],
+    emission: [u8; 3],
+    shininess_enabled: bool,
+    shininess: Vec<u8>, // 128 entries
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self {
+            lights: [Light::default(); 4],
+
+            diffuse: [0; 3],
+            ambient: [0; 3],
+            specular: [0; 3],
+            emission: [0; 3],
+            shininess_enabled: false,
+            shininess: vec![0; 128],
+        }
+    }
+}
+
+pub fn rgb555(value: u32) -> [u8; 3] {
+    [
+        (value & 0x1F) as u8,
+        ((value >> 5) & 0x1F) as u8,
+        ((value >> 10) & 0x1F) as u8,
+    ]
+}
+
+// 10 bit signed components in 1.0.9, we keep them at .12 like everything else
+pub fn unpack_direction(value: u32) -> [i32; 3] {
+    [0, 10, 20].map(|shift| (((value >> shift) << 22) as i32 >> 22) << 3)
+}
+
+impl Lighting {
+    // DIF_AMB, returns the diffuse colour if it should also become the vertex colour
+    pub fn set_diffuse_ambient(&mut self, value: u32) -> Option<[u8; 3]> {
+        self.diffuse = rgb555(value);
+        self.ambient = rgb555(value >> 16);
+        (value & 0x8000 != 0).then_some(self.diffuse)
+    }
+
+    // SPE_EMI
+    pub fn set_specular_emission(&mut self, value: u32) {
+        self.specular = rgb555(value);
+        self.shininess_enabled = value & 0x8000 != 0;
+        self.emission = rgb555(value >> 16);
+    }
+
+    // LIGHT_VECTOR, the direction goes through the vector matrix as it is at the time
+    pub fn set_light_vector(&mut self, value: u32, vector: &Matrix) {
+        let light = &mut self.lights[(value >> 30) as usize];
+        light.direction = vector.transform_direction(unpack_direction(value));
+        // the line of sight is always (0, 0, -1)
+        light.half = [
+            light.direction[0] / 2,
+            light.direction[1] / 2,
+            (light.direction[2] - 0x1000) / 2,
+        ];
+    }
+
+    // LIGHT_COLOR
+    pub fn set_light_color(&mut self, value: u32) {
+        self.lights[(value >> 30) as usize].color = rgb555(value);
+    }
+
+    // SHININESS, 32 words of 4 entries each
+    pub fn set_shininess(&mut self, params: &[u32]) {
+        for (i, param) in params.iter().enumerate() {
+            self.shininess[i * 4..i * 4 + 4].copy_from_slice(&param.to_le_bytes());
+        }
+    }
+
+    // what NORMAL does to the vertex colour
+    pub fn apply(&self, normal: [i32; 3], attr: PolygonAttr) -> [u8; 3] {
+        let dot = |a: [i32; 3], b: [i32; 3]| -> i64 {
+            (0..3).map(|i| a[i] as i64 * b[i] as i64).sum::<i64>() >> 16 // .24 -> .8
+        };
+
+        let mut color: [i32; 3] = self.emission.map(|c| c as i32);
+        for (i, light) in self.lights.iter().enumerate() {
+            if !attr.get_light_enabled(i) {
+                continue;
+            }
+
+            let diffuse_level = (-dot(light.direction, normal)).clamp(0, 255) as i32;
+            let shine = (-dot(light.half, normal)).clamp(0, 255) as i32;
+            let mut specular_level = (shine * shine) >> 8;
+            if self.shininess_enabled {
+                specular_level = self.shininess[(specular_level >> 1) as usize] as i32;
+            }
+
+            for (c, color) in color.iter_mut().enumerate() {
+                let light_color = light.color[c] as i32;
+                *color += (self.specular[c] as i32 * light_color * specular_level) >> 13;
+                *color += (self.diffuse[c] as i32 * light_color * diffuse_level) >> 13;
+                *color += (self.ambient[c] as i32 * light_color) >> 5;
+            }
+        }
+
+        color.map(|c| c.min(31) as u8)
+    }
+}
@@ -0,0 +1,268 @@
+// 4x4 matrices in 20.12 fixed point, vectors are rows multiplied on the left like the hardware does
+#[derive(Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Matrix(pub [[i32; 4]; 4]);
+
+impl Default for Matrix {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Matrix {
+    pub const IDENTITY: Matrix = Matrix([
+        [0x1000, 0, 0, 0],
+        [0, 0x1000, 0, 0],
+        [0, 0, 0x1000, 0],
+        [0, 0, 0, 0x1000],
+    ]);
+
+    pub fn from_4x4(params: &[u32]) -> Self {
+        Self(std::array::from_fn(|row| {
+            std::array::from_fn(|col| params[row * 4 + col] as i32)
+        }))
+    }
+
+    // the last column is always 0, 0, 0, 1
+    pub fn from_4x3(params: &[u32]) -> Self {
+        Self(std::array::from_fn(|row| {
+            std::array::from_fn(|col| match col {
+                3 if row == 3 => 0x1000,
+                3 => 0,
+                _ => params[row * 3 + col] as i32,
+            })
+        }))
+    }
+
+    // the translation row is left alone
+    pub fn from_3x3(params: &[u32]) -> Self {
+        Self(std::array::from_fn(|row| {
+            std::array::from_fn(|col| match (row, col) {
+                (3, 3) => 0x1000,
+                (3, _) | (_, 3) => 0,
+                _ => params[row * 3 + col] as i32,
+            })
+        }))
+    }
+
+    pub fn scale(x: i32, y: i32, z: i32) -> Self {
+        Self([[x, 0, 0, 0], [0, y, 0, 0], [0, 0, z, 0], [0, 0, 0, 0x1000]])
+    }
+
+    pub fn translate(x: i32, y: i32, z: i32) -> Self {
+        Self([
+            [0x1000, 0, 0, 0],
+            [0, 0x1000, 0, 0],
+            [0, 0, 0x1000, 0],
+            [x, y, z, 0x1000],
+        ])
+    }
+
+    // self * other, which is how the MTX_MULT commands apply the parameter to the current matrix
+    pub fn multiply(&self, other: &Matrix) -> Matrix {
+        Matrix(std::array::from_fn(|row| {
+            std::array::from_fn(|col| {
+                let sum: i64 = (0..4)
+                    .map(|i| self.0[row][i] as i64 * other.0[i][col] as i64)
+                    .sum();
+                (sum >> 12) as i32
+            })
+        }))
+    }
+
+    pub fn transform(&self, vector: [i32; 4]) -> [i32; 4] {
+        std::array::from_fn(|col| {
+            let sum: i64 = (0..4)
+                .map(|i| vector[i] as i64 * self.0[i][col] as i64)
+                .sum();
+            (sum >> 12) as i32
+        })
+    }
+
+    // only the rotation part, for directions like normals and light vectors
+    pub fn transform_direction(&self, vector: [i32; 3]) -> [i32; 3] {
+        self.transform_direction_unshifted(vector)
+            .map(|sum| (sum >> 12) as i32)
+    }
+
+    // for the texture coordinate transforms, which don't shift by the usual 12
+    pub fn transform_direction_unshifted(&self, vector: [i32; 3]) -> [i64; 3] {
+        std::array::from_fn(|col| {
+            (0..3)
+                .map(|i| vector[i] as i64 * self.0[i][col] as i64)
+                .sum()
+        })
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum MatrixMode {
+    #[default]
+    Projection,
+    Position,
+    PositionVector,
+    Texture,
+}
+
+impl From<u32> for MatrixMode {
+    fn from(value: u32) -> Self {
+        match value & 3 {
+            0 => MatrixMode::Projection,
+            1 => MatrixMode::Position,
+            2 => MatrixMode::PositionVector,
+            _ => MatrixMode::Texture,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct MatrixStacks {
+    pub mode: MatrixMode,
+
+    pub projection: Matrix,
+    pub position: Matrix,
+    pub vector: Matrix, // the "directional" matrix, used for normals and lights
+    pub texture: Matrix,
+
+    projection_stack: Matrix,
+    position_stack: [Matrix; 32], // the 32nd entry only exists so overflowing writes have somewhere to go
+    vector_stack: [Matrix; 32],
+    texture_stack: Matrix,
+    pub projection_pointer: u8, // 0..=1
+    pub position_pointer: u8,   // 0..=63, anything above 30 is an overflow
+
+    pub overflow: bool, // GXSTAT bit 15, sticky until acknowledged
+
+    // projection * position, recalculated whenever either of them changes
+    pub clip: Matrix,
+}
+
+impl Default for MatrixStacks {
+    fn default() -> Self {
+        Self {
+            mode: MatrixMode::default(),
+
+            projection: Matrix::IDENTITY,
+            position: Matrix::IDENTITY,
+            vector: Matrix::IDENTITY,
+            texture: Matrix::IDENTITY,
+
+            projection_stack: Matrix::IDENTITY,
+            position_stack: [Matrix::IDENTITY; 32],
+            vector_stack: [Matrix::IDENTITY; 32],
+            texture_stack: Matrix::IDENTITY,
+            projection_pointer: 0,
+            position_pointer: 0,
+
+            overflow: false,
+
+            clip: Matrix::IDENTITY,
+        }
+    }
+}
+
+impl MatrixStacks {
+    fn update_clip(&mut self) {
+        self.clip = self.position.multiply(&self.projection);
+    }
+
+    pub fn push(&mut self) {
+        match self.mode {
+            MatrixMode::Projection => {
+                self.overflow |= self.projection_pointer != 0;
+                self.projection_stack = self.projection;
+                self.projection_pointer = (self.projection_pointer + 1) & 1;
+            }
+            MatrixMode::Position | MatrixMode::PositionVector => {
+                let index = (self.position_pointer & 31) as usize;
+                self.position_stack[index] = self.position;
+                self.vector_stack[index] = self.vector;
+                self.position_pointer = (self.position_pointer + 1) & 63;
+                self.overflow |= self.position_pointer > 30;
+            }
+            MatrixMode::Texture => self.texture_stack = self.texture,
+        }
+    }
+
+    // offset is a signed 6 bit number, only used by the position stack
+    pub fn pop(&mut self, offset: u32) {
+        match self.mode {
+            MatrixMode::Projection => {
+                self.overflow |= self.projection_pointer == 0;
+                self.projection_pointer = self.projection_pointer.wrapping_sub(1) & 1;
+                self.projection = self.projection_stack;
+                self.update_clip();
+            }
+            MatrixMode::Position | MatrixMode::PositionVector => {
+                let offset = ((offset << 26) as i32 >> 26) as i8;
+                self.position_pointer =
+                    (self.position_pointer as i8).wrapping_sub(offset) as u8 & 63;
+                self.overflow |= self.position_pointer > 30;
+
+                let index = (self.position_pointer & 31) as usize;
+                self.position = self.position_stack[index];
+                self.vector = self.vector_stack[index];
+                self.update_clip();
+            }
+            MatrixMode::Texture => self.texture = self.texture_stack,
+        }
+    }
+
+    pub fn store(&mut self, index: u32) {
+        match self.mode {
+            MatrixMode::Projection => self.projection_stack = self.projection,
+            MatrixMode::Position | MatrixMode::PositionVector => {
+                let index = (index & 31) as usize;
+                self.overflow |= index == 31;
+                self.position_stack[index] = self.position;
+                self.vector_stack[index] = self.vector;
+            }
+            MatrixMode::Texture => self.texture_stack = self.texture,
+        }
+    }
+
+    pub fn restore(&mut self, index: u32) {
+        match self.mode {
+            MatrixMode::Projection => {
+                self.projection = self.projection_stack;
+                self.update_clip();
+            }
+            MatrixMode::Position | MatrixMode::PositionVector => {
+                let index = (index & 31) as usize;
+                self.overflow |= index == 31;
+                self.position = self.position_stack[index];
+                self.vector = self.vector_stack[index];
+                self.update_clip();
+            }
+            MatrixMode::Texture => self.texture = self.texture_stack,
+        }
+    }
+
+    pub fn load(&mut self, matrix: Matrix) {
+        match self.mode {
+            MatrixMode::Projection => self.projection = matrix,
+            MatrixMode::Position => self.position = matrix,
+            MatrixMode::PositionVector => {
+                self.position = matrix;
+                self.vector = matrix;
+            }
+            MatrixMode::Texture => self.texture = matrix,
+        }
+        self.update_clip();
+    }
+
+    // the vector matrix is left alone by MTX_SCALE, so it has its own flag
+    pub fn multiply(&mut self, matrix: Matrix, include_vector: bool) {
+        match self.mode {
+            MatrixMode::Projection => self.projection = matrix.multiply(&self.projection),
+            MatrixMode::Position => self.position = matrix.multiply(&self.position),
+            MatrixMode::PositionVector => {
+                self.position = matrix.multiply(&self.position);
+                if include_vector {
+                    self.vector = matrix.multiply(&self.vector);
+                }
+            }
+            MatrixMode::Texture => self.texture = matrix.multiply(&self.texture),
+        }
+        self.update_clip();
+    }
+}
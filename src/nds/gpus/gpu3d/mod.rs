@@ -0,0 +1,235 @@
+mod commands;
+mod fifo;
+mod lighting;
+mod matrix;
+pub mod models;
+pub mod polygon;
+pub mod rendering;
+
+use std::collections::VecDeque;
+
+use fifo::GxFifo;
+use lighting::Lighting;
+use matrix::MatrixStacks;
+use models::{GxFifoIrq, GxStat, PolygonAttr, TexImageParam};
+use polygon::{PolygonRam, PrimitiveAssembler, Viewport};
+use rendering::Renderer;
+
+use crate::nds::{gpus::vram::VramBanks, interrupts::Interrupts, Bits};
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct Gpu3d {
+    gxstat: GxStat, // 0x04000600
+    fifo: GxFifo,   // 0x04000400, 0x04000440..=0x040005FF
+    busy_cycles: u32,
+
+    // writes that came in while the FIFO was full and the engine was waiting for vblank
+    // the CPU is held on the bus until they fit, see is_stalling_cpu
+    stalled_writes: VecDeque<(Option<u8>, u32)>, // the command for command port writes, None for 0x04000400
+
+    // the DMA is asked for on the edge, when the FIFO drops below half full
+    below_half_full: bool,
+    dma_requested: bool,
+
+    matrices: MatrixStacks,
+    lighting: Lighting,
+    assembler: PrimitiveAssembler,
+
+    // current vertex attributes
+    vertex: [i16; 3],
+    color: [u8; 3],
+    texcoord: [i16; 2],
+    raw_texcoord: [i16; 2],    // before the texture matrix
+    polygon_attr: PolygonAttr, // only takes effect at the next BEGIN_VTXS
+    teximage_param: TexImageParam,
+    palette_base: u32,
+    viewport: Viewport,
+
+    // results of the test commands
+    box_test_result: bool,
+    pos_test_result: [i32; 4],
+    vec_test_result: [i16; 3],
+
+    // SWAP_BUFFERS halts the geometry engine until vblank, which is when the buffers actually swap
+    swap_pending: bool,
+    swap_params: u32,
+
+    pub geometry: PolygonRam,       // being filled by the geometry engine
+    pub rendering: PolygonRam,      // what the rendering engine draws
+    pub rendering_swap_params: u32, // bit 0 manual translucent sorting, bit 1 w-buffering
+    pub renderer: Renderer,
+
+    #[serde(skip)]
+    params: Vec<u32>,
+}
+
+impl Gpu3d {
+    pub fn read_gxstat(&self) -> u32 {
+        let mut value = self.gxstat.value();
+        value.set_bit(1, self.box_test_result);
+        value.set_bits(8, 12, self.matrices.position_pointer as u32 & 31);
+        value.set_bit(13, self.matrices.projection_pointer != 0);
+        value.set_bit(15, self.matrices.overflow);
+        value.set_bits(16, 24, self.fifo.len().min(256) as u32);
+        value.set_bit(25, self.fifo.is_less_than_half_full());
+        value.set_bit(26, self.fifo.is_empty());
+        value.set_bit(27, self.is_busy());
+        value
+    }
+
+    pub fn write_gxstat(&mut self, value: u32) {
+        self.gxstat.set(value);
+        if value.get_bit(15) {
+            // acknowledging the error also resets the projection stack pointer
+            self.matrices.overflow = false;
+            self.matrices.projection_pointer = 0;
+        }
+    }
+
+    // 0x04000604, polygons in the low half and vertices in the high half
+    pub fn read_ram_count(&self) -> u32 {
+        self.geometry.polygons.len() as u32 | (self.geometry.vertices.len() as u32) << 16
+    }
+
+    // 0x04000620..=0x040006A3, POS_RESULT, VEC_RESULT, CLIPMTX_RESULT and VECMTX_RESULT
+    pub fn read_result(&self, addr: usize) -> u32 {
+        let addr = addr & !3;
+        match addr {
+            0x04000620..=0x0400062F => self.pos_test_result[(addr - 0x04000620) / 4] as u32,
+            0x04000630..=0x04000637 => {
+                // two halfwords per word, the last one only has z
+                let i = (addr - 0x04000630) / 2;
+                let low = self.vec_test_result[i] as u16 as u32;
+                let high = self
+                    .vec_test_result
+                    .get(i + 1)
+                    .map_or(0, |&c| c as u16 as u32);
+                low | high << 16
+            }
+            0x04000640..=0x0400067F => {
+                let i = (addr - 0x04000640) / 4;
+                self.matrices.clip.0[i / 4][i % 4] as u32
+            }
+            0x04000680..=0x040006A3 => {
+                let i = (addr - 0x04000680) / 4;
+                self.matrices.vector.0[i / 3][i % 3] as u32
+            }
+            _ => 0,
+        }
+    }
+
+    pub fn write_gxfifo(&mut self, value: u32) {
+        self.write_fifo(None, value);
+    }
+
+    pub fn write_command_port(&mut self, addr: usize, value: u32) {
+        self.write_fifo(Some(((addr - 0x04000400) >> 2) as u8), value);
+    }
+
+    fn write_fifo(&mut self, command: Option<u8>, value: u32) {
+        self.make_room();
+        if self.fifo.is_full() || !self.stalled_writes.is_empty() {
+            self.stalled_writes.push_back((command, value));
+            return;
+        }
+
+        self.push_write(command, value);
+    }
+
+    fn push_write(&mut self, command: Option<u8>, value: u32) {
+        match command {
+            Some(command) => self.fifo.write_unpacked(command, value),
+            None => self.fifo.write_packed(value),
+        }
+    }
+
+    // writing to a full FIFO stalls the ARM9 until the geometry engine makes room
+    pub fn is_stalling_cpu(&self) -> bool {
+        !self.stalled_writes.is_empty()
+    }
+
+    fn is_busy(&self) -> bool {
+        self.busy_cycles > 0 || self.swap_pending || !self.fifo.is_empty()
+    }
+
+    // the CPU would be stalled when writing to a full FIFO, we run the commands early instead
+    // if the engine is waiting for vblank that can't happen, so the write is stalled until the swap
+    fn make_room(&mut self) {
+        while self.fifo.is_full() && !self.swap_pending {
+            if !self.run_command() {
+                break;
+            }
+        }
+    }
+
+    fn run_command(&mut self) -> bool {
+        let mut params = std::mem::take(&mut self.params);
+        let Some(command) = self.fifo.pop_command(&mut params) else {
+            self.params = params;
+            return false;
+        };
+
+        self.execute(command, &params);
+        self.busy_cycles += commands::cycles(command);
+        self.params = params;
+        true
+    }
+
+    pub fn clock(&mut self, interrupts: &mut Interrupts) {
+        if self.busy_cycles > 0 {
+            self.busy_cycles -= 1;
+        }
+        while self.busy_cycles == 0 && !self.swap_pending {
+            if !self.run_command() {
+                break;
+            }
+        }
+        while !self.fifo.is_full() {
+            let Some((command, value)) = self.stalled_writes.pop_front() else {
+                break;
+            };
+            self.push_write(command, value);
+        }
+
+        // the IRQ is level triggered, it stays requested for as long as the condition holds
+        let irq = match self.gxstat.get_fifo_irq() {
+            GxFifoIrq::Never => false,
+            GxFifoIrq::LessThanHalfFull => self.fifo.is_less_than_half_full(),
+            GxFifoIrq::Empty => self.fifo.is_empty(),
+        };
+        interrupts.f.falsy_set_geometry_fifo(irq);
+
+        let below_half_full = self.fifo.is_less_than_half_full();
+        if below_half_full && !self.below_half_full {
+            self.dma_requested = true;
+        }
+        self.below_half_full = below_half_full;
+    }
+
+    pub fn wants_dma(&mut self) -> bool {
+        std::mem::take(&mut self.dma_requested)
+    }
+
+    // makes the next clock see the edge again if the FIFO is still below half full
+    pub fn rearm_dma(&mut self) {
+        self.below_half_full = false;
+    }
+
+    pub fn vblank(&mut self, vram_banks: &VramBanks) {
+        if !self.swap_pending {
+            return;
+        }
+
+        self.swap_pending = false;
+        std::mem::swap(&mut self.geometry, &mut self.rendering);
+        self.geometry.clear();
+        self.rendering_swap_params = self.swap_params;
+        if self.rendering.overflow {
+            self.renderer.disp3dcnt.set_ram_overflow(true);
+        }
+
+        // the real thing renders line by line during the next frame, we do it all at once
+        self.renderer
+            .render(&self.rendering, self.rendering_swap_params, vram_banks);
+    }
+}
@@ -0,0 +1,86 @@
+use crate::nds::Bits;
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct Disp3dCnt(u32);
+
+impl Disp3dCnt {
+    const TEXTURE_MAPPING_OFFSET: u32 = 0;
+    const HIGHLIGHT_SHADING_OFFSET: u32 = 1;
+    const ALPHA_TEST_OFFSET: u32 = 2;
+    const ALPHA_BLENDING_OFFSET: u32 = 3;
+    const ANTI_ALIASING_OFFSET: u32 = 4;
+    const EDGE_MARKING_OFFSET: u32 = 5;
+    const FOG_ALPHA_ONLY_OFFSET: u32 = 6;
+    const FOG_OFFSET: u32 = 7;
+    const FOG_SHIFT_START: u32 = 8;
+    const FOG_SHIFT_END: u32 = 11;
+    const RDLINES_UNDERFLOW_OFFSET: u32 = 12;
+    const RAM_OVERFLOW_OFFSET: u32 = 13;
+    const CLEAR_IMAGE_OFFSET: u32 = 14;
+
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+
+    // the two error flags are acknowledged by writing 1 to them
+    pub fn set(&mut self, value: u32) {
+        let underflow =
+            self.get_rdlines_underflow() && !value.get_bit(Self::RDLINES_UNDERFLOW_OFFSET);
+        let overflow = self.get_ram_overflow() && !value.get_bit(Self::RAM_OVERFLOW_OFFSET);
+        self.0 = value & 0x4FFF;
+        self.0.set_bit(Self::RDLINES_UNDERFLOW_OFFSET, underflow);
+        self.0.set_bit(Self::RAM_OVERFLOW_OFFSET, overflow);
+    }
+
+    pub fn get_texture_mapping(&self) -> bool {
+        self.0.get_bit(Self::TEXTURE_MAPPING_OFFSET)
+    }
+
+    pub fn get_highlight_shading(&self) -> bool {
+        self.0.get_bit(Self::HIGHLIGHT_SHADING_OFFSET)
+    }
+
+    pub fn get_alpha_test(&self) -> bool {
+        self.0.get_bit(Self::ALPHA_TEST_OFFSET)
+    }
+
+    pub fn get_alpha_blending(&self) -> bool {
+        self.0.get_bit(Self::ALPHA_BLENDING_OFFSET)
+    }
+
+    pub fn get_anti_aliasing(&self) -> bool {
+        self.0.get_bit(Self::ANTI_ALIASING_OFFSET)
+    }
+
+    pub fn get_edge_marking(&self) -> bool {
+        self.0.get_bit(Self::EDGE_MARKING_OFFSET)
+    }
+
+    pub fn get_fog_alpha_only(&self) -> bool {
+        self.0.get_bit(Self::FOG_ALPHA_ONLY_OFFSET)
+    }
+
+    pub fn get_fog(&self) -> bool {
+        self.0.get_bit(Self::FOG_OFFSET)
+    }
+
+    pub fn get_fog_shift(&self) -> u32 {
+        self.0.get_bits(Self::FOG_SHIFT_START, Self::FOG_SHIFT_END)
+    }
+
+    pub fn get_rdlines_underflow(&self) -> bool {
+        self.0.get_bit(Self::RDLINES_UNDERFLOW_OFFSET)
+    }
+
+    pub fn get_ram_overflow(&self) -> bool {
+        self.0.get_bit(Self::RAM_OVERFLOW_OFFSET)
+    }
+
+    pub fn set_ram_overflow(&mut self, value: bool) {
+        self.0.set_bit(Self::RAM_OVERFLOW_OFFSET, value);
+    }
+
+    pub fn get_clear_image(&self) -> bool {
+        self.0.get_bit(Self::CLEAR_IMAGE_OFFSET)
+    }
+}
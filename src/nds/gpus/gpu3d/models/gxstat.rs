@@ -0,0 +1,37 @@
+use crate::nds::Bits;
+
+// only the bits that are actually stored, the rest of GXSTAT is put together by Gpu3d when it's read
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct GxStat(u32);
+
+#[derive(PartialEq)]
+pub enum GxFifoIrq {
+    Never,
+    LessThanHalfFull,
+    Empty,
+}
+
+impl GxStat {
+    const FIFO_IRQ_START: u32 = 30;
+    const FIFO_IRQ_END: u32 = 31;
+
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+
+    pub fn set(&mut self, value: u32) {
+        self.0.set_bits(
+            Self::FIFO_IRQ_START,
+            Self::FIFO_IRQ_END,
+            value.get_bits(Self::FIFO_IRQ_START, Self::FIFO_IRQ_END),
+        );
+    }
+
+    pub fn get_fifo_irq(&self) -> GxFifoIrq {
+        match self.0.get_bits(Self::FIFO_IRQ_START, Self::FIFO_IRQ_END) {
+            1 => GxFifoIrq::LessThanHalfFull,
+            2 => GxFifoIrq::Empty,
+            _ => GxFifoIrq::Never, // 3 is reserved
+        }
+    }
+}
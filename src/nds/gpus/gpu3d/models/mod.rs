@@ -0,0 +1,9 @@
+mod disp3dcnt;
+mod gxstat;
+mod polygon_attr;
+mod teximage_param;
+
+pub use disp3dcnt::Disp3dCnt;
+pub use gxstat::{GxFifoIrq, GxStat};
+pub use polygon_attr::{PolygonAttr, PolygonMode};
+pub use teximage_param::{TexImageParam, TextureFormat, TextureTransform};
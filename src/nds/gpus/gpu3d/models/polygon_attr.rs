@@ -0,0 +1,94 @@
+#![allow(dead_code)]
+
+use crate::nds::Bits;
+
+#[derive(Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
+pub struct PolygonAttr(u32);
+
+impl From<u32> for PolygonAttr {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PolygonMode {
+    Modulation,
+    Decal,
+    Toon,
+    Shadow,
+}
+
+impl PolygonAttr {
+    const LIGHTS_START: u32 = 0;
+    const LIGHTS_END: u32 = 3;
+    const MODE_START: u32 = 4;
+    const MODE_END: u32 = 5;
+    const RENDER_BACK_OFFSET: u32 = 6;
+    const RENDER_FRONT_OFFSET: u32 = 7;
+    const TRANSLUCENT_DEPTH_UPDATE_OFFSET: u32 = 11;
+    const FAR_PLANE_CLIP_OFFSET: u32 = 12;
+    const ONE_DOT_OFFSET: u32 = 13;
+    const DEPTH_EQUAL_OFFSET: u32 = 14;
+    const FOG_OFFSET: u32 = 15;
+    const ALPHA_START: u32 = 16;
+    const ALPHA_END: u32 = 20;
+    const POLYGON_ID_START: u32 = 24;
+    const POLYGON_ID_END: u32 = 29;
+
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+
+    pub fn get_light_enabled(&self, light: usize) -> bool {
+        self.0
+            .get_bits(Self::LIGHTS_START, Self::LIGHTS_END)
+            .get_bit(light as u32)
+    }
+
+    pub fn get_mode(&self) -> PolygonMode {
+        match self.0.get_bits(Self::MODE_START, Self::MODE_END) {
+            0 => PolygonMode::Modulation,
+            1 => PolygonMode::Decal,
+            2 => PolygonMode::Toon,
+            _ => PolygonMode::Shadow,
+        }
+    }
+
+    pub fn get_render_back(&self) -> bool {
+        self.0.get_bit(Self::RENDER_BACK_OFFSET)
+    }
+
+    pub fn get_render_front(&self) -> bool {
+        self.0.get_bit(Self::RENDER_FRONT_OFFSET)
+    }
+
+    pub fn get_translucent_depth_update(&self) -> bool {
+        self.0.get_bit(Self::TRANSLUCENT_DEPTH_UPDATE_OFFSET)
+    }
+
+    pub fn get_far_plane_clip(&self) -> bool {
+        self.0.get_bit(Self::FAR_PLANE_CLIP_OFFSET)
+    }
+
+    pub fn get_one_dot(&self) -> bool {
+        self.0.get_bit(Self::ONE_DOT_OFFSET)
+    }
+
+    pub fn get_depth_equal(&self) -> bool {
+        self.0.get_bit(Self::DEPTH_EQUAL_OFFSET)
+    }
+
+    pub fn get_fog(&self) -> bool {
+        self.0.get_bit(Self::FOG_OFFSET)
+    }
+
+    pub fn get_alpha(&self) -> u8 {
+        self.0.get_bits(Self::ALPHA_START, Self::ALPHA_END) as u8
+    }
+
+    pub fn get_polygon_id(&self) -> u8 {
+        self.0
+            .get_bits(Self::POLYGON_ID_START, Self::POLYGON_ID_END) as u8
+    }
+}
@@ -0,0 +1,112 @@
+#![allow(dead_code)]
+
+use crate::nds::Bits;
+
+#[derive(Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
+pub struct TexImageParam(u32);
+
+impl From<u32> for TexImageParam {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TextureFormat {
+    None,
+    A3I5,
+    Palette4,
+    Palette16,
+    Palette256,
+    Compressed,
+    A5I3,
+    Direct,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TextureTransform {
+    None,
+    TexCoord,
+    Normal,
+    Vertex,
+}
+
+impl TexImageParam {
+    const VRAM_OFFSET_START: u32 = 0;
+    const VRAM_OFFSET_END: u32 = 15;
+    const REPEAT_S_OFFSET: u32 = 16;
+    const REPEAT_T_OFFSET: u32 = 17;
+    const FLIP_S_OFFSET: u32 = 18;
+    const FLIP_T_OFFSET: u32 = 19;
+    const SIZE_S_START: u32 = 20;
+    const SIZE_S_END: u32 = 22;
+    const SIZE_T_START: u32 = 23;
+    const SIZE_T_END: u32 = 25;
+    const FORMAT_START: u32 = 26;
+    const FORMAT_END: u32 = 28;
+    const COLOR0_TRANSPARENT_OFFSET: u32 = 29;
+    const TRANSFORM_START: u32 = 30;
+    const TRANSFORM_END: u32 = 31;
+
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+
+    // in bytes, the register holds it in units of 8
+    pub fn get_vram_offset(&self) -> u32 {
+        self.0
+            .get_bits(Self::VRAM_OFFSET_START, Self::VRAM_OFFSET_END)
+            << 3
+    }
+
+    pub fn get_repeat_s(&self) -> bool {
+        self.0.get_bit(Self::REPEAT_S_OFFSET)
+    }
+
+    pub fn get_repeat_t(&self) -> bool {
+        self.0.get_bit(Self::REPEAT_T_OFFSET)
+    }
+
+    pub fn get_flip_s(&self) -> bool {
+        self.0.get_bit(Self::FLIP_S_OFFSET)
+    }
+
+    pub fn get_flip_t(&self) -> bool {
+        self.0.get_bit(Self::FLIP_T_OFFSET)
+    }
+
+    // 8 to 1024 texels
+    pub fn get_size_s(&self) -> u32 {
+        8 << self.0.get_bits(Self::SIZE_S_START, Self::SIZE_S_END)
+    }
+
+    pub fn get_size_t(&self) -> u32 {
+        8 << self.0.get_bits(Self::SIZE_T_START, Self::SIZE_T_END)
+    }
+
+    pub fn get_format(&self) -> TextureFormat {
+        match self.0.get_bits(Self::FORMAT_START, Self::FORMAT_END) {
+            0 => TextureFormat::None,
+            1 => TextureFormat::A3I5,
+            2 => TextureFormat::Palette4,
+            3 => TextureFormat::Palette16,
+            4 => TextureFormat::Palette256,
+            5 => TextureFormat::Compressed,
+            6 => TextureFormat::A5I3,
+            _ => TextureFormat::Direct,
+        }
+    }
+
+    pub fn get_color0_transparent(&self) -> bool {
+        self.0.get_bit(Self::COLOR0_TRANSPARENT_OFFSET)
+    }
+
+    pub fn get_transform(&self) -> TextureTransform {
+        match self.0.get_bits(Self::TRANSFORM_START, Self::TRANSFORM_END) {
+            0 => TextureTransform::None,
+            1 => TextureTransform::TexCoord,
+            2 => TextureTransform::Normal,
+            _ => TextureTransform::Vertex,
+        }
+    }
+}
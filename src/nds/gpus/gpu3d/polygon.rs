@@ -0,0 +1,277 @@
+use super::models::{PolygonAttr, TexImageParam};
+
+// these are the hardware limits, anything past them is dropped and flagged in DISP3DCNT
+pub const POLYGON_RAM_SIZE: usize = 2048;
+pub const VERTEX_RAM_SIZE: usize = 6144;
+
+// a clipped triangle can gain one vertex per clipping plane, a quad can end up with 10
+const MAX_CLIPPED_VERTICES: usize = 10;
+
+#[derive(Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
+pub struct Vertex {
+    pub clip: [i32; 4],     // x, y, z, w after the clip matrix, 20.12
+    pub screen: [i32; 2],   // after the viewport, in pixels
+    pub color: [u8; 3],     // 5 bits per component
+    pub texcoord: [i16; 2], // 1.11.4
+}
+
+#[derive(Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
+pub struct Polygon {
+    pub attr: PolygonAttr,
+    pub teximage_param: TexImageParam,
+    pub palette_base: u32,
+
+    pub vertex_start: u16, // index into vertex RAM
+    pub vertex_count: u8,
+    pub front_facing: bool,
+    pub translucent: bool,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum PrimitiveType {
+    #[default]
+    Triangles,
+    Quads,
+    TriangleStrip,
+    QuadStrip,
+}
+
+impl From<u32> for PrimitiveType {
+    fn from(value: u32) -> Self {
+        match value & 3 {
+            0 => PrimitiveType::Triangles,
+            1 => PrimitiveType::Quads,
+            2 => PrimitiveType::TriangleStrip,
+            _ => PrimitiveType::QuadStrip,
+        }
+    }
+}
+
+#[derive(Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub struct Viewport {
+    pub x1: i32,
+    pub y1: i32, // bottom, the DS counts upwards
+    pub x2: i32,
+    pub y2: i32,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            x1: 0,
+            y1: 0,
+            x2: 255,
+            y2: 191,
+        }
+    }
+}
+
+impl From<u32> for Viewport {
+    fn from(value: u32) -> Self {
+        Self {
+            x1: (value & 0xFF) as i32,
+            y1: ((value >> 8) & 0xFF) as i32,
+            x2: ((value >> 16) & 0xFF) as i32,
+            y2: ((value >> 24) & 0xFF) as i32,
+        }
+    }
+}
+
+impl Viewport {
+    fn to_screen(self, clip: [i32; 4]) -> [i32; 2] {
+        let w = clip[3] as i64;
+        if w == 0 {
+            return [self.x1, 192 - self.y1];
+        }
+
+        let width = (self.x2 - self.x1 + 1) as i64;
+        let height = (self.y2 - self.y1 + 1) as i64;
+        let x = ((clip[0] as i64 + w) * width / (2 * w)) as i32 + self.x1;
+        let y = ((clip[1] as i64 + w) * height / (2 * w)) as i32 + self.y1;
+        // flipped so the top of the viewport is the top of the screen
+        [x, 192 - y]
+    }
+}
+
+// the polygon and vertex RAM, the geometry engine fills one while the rendering engine draws the other
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct PolygonRam {
+    pub polygons: Vec<Polygon>,
+    pub vertices: Vec<Vertex>,
+    pub overflow: bool,
+}
+
+impl PolygonRam {
+    pub fn clear(&mut self) {
+        self.polygons.clear();
+        self.vertices.clear();
+        self.overflow = false;
+    }
+}
+
+// collects vertices between BEGIN_VTXS and END_VTXS and turns them into polygons
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct PrimitiveAssembler {
+    pub primitive: PrimitiveType,
+    pub attr: PolygonAttr, // latched at BEGIN_VTXS
+    vertices: Vec<Vertex>,
+    strip_odd: bool, // every other triangle in a strip is wound the other way
+}
+
+pub struct PolygonState {
+    pub teximage_param: TexImageParam,
+    pub palette_base: u32,
+    pub viewport: Viewport,
+}
+
+impl PrimitiveAssembler {
+    pub fn begin(&mut self, primitive: PrimitiveType, attr: PolygonAttr) {
+        self.primitive = primitive;
+        self.attr = attr;
+        self.vertices.clear();
+        self.strip_odd = false;
+    }
+
+    pub fn add_vertex(&mut self, vertex: Vertex, state: &PolygonState, ram: &mut PolygonRam) {
+        self.vertices.push(vertex);
+
+        match self.primitive {
+            PrimitiveType::Triangles if self.vertices.len() == 3 => {
+                let vertices = [self.vertices[0], self.vertices[1], self.vertices[2]];
+                self.emit(&vertices, state, ram);
+                self.vertices.clear();
+            }
+            PrimitiveType::Quads if self.vertices.len() == 4 => {
+                let vertices = [
+                    self.vertices[0],
+                    self.vertices[1],
+                    self.vertices[2],
+                    self.vertices[3],
+                ];
+                self.emit(&vertices, state, ram);
+                self.vertices.clear();
+            }
+            PrimitiveType::TriangleStrip if self.vertices.len() == 3 => {
+                let vertices = if self.strip_odd {
+                    [self.vertices[1], self.vertices[0], self.vertices[2]]
+                } else {
+                    [self.vertices[0], self.vertices[1], self.vertices[2]]
+                };
+                self.emit(&vertices, state, ram);
+                self.strip_odd = !self.strip_odd;
+                self.vertices.remove(0);
+            }
+            PrimitiveType::QuadStrip if self.vertices.len() == 4 => {
+                // the strip goes 0 1 3 2 around the edge
+                let vertices = [
+                    self.vertices[0],
+                    self.vertices[1],
+                    self.vertices[3],
+                    self.vertices[2],
+                ];
+                self.emit(&vertices, state, ram);
+                self.vertices.drain(..2);
+            }
+            _ => {}
+        }
+    }
+
+    fn emit(&self, vertices: &[Vertex], state: &PolygonState, ram: &mut PolygonRam) {
+        let front_facing = is_front_facing(vertices);
+        let visible = if front_facing {
+            self.attr.get_render_front()
+        } else {
+            self.attr.get_render_back()
+        };
+        if !visible {
+            return;
+        }
+
+        let Some(mut clipped) = clip_polygon(vertices, self.attr.get_far_plane_clip()) else {
+            return;
+        };
+
+        if ram.polygons.len() >= POLYGON_RAM_SIZE
+            || ram.vertices.len() + clipped.len() > VERTEX_RAM_SIZE
+        {
+            ram.overflow = true;
+            return;
+        }
+
+        for vertex in clipped.iter_mut() {
+            vertex.screen = state.viewport.to_screen(vertex.clip);
+        }
+
+        let alpha = self.attr.get_alpha();
+        ram.polygons.push(Polygon {
+            attr: self.attr,
+            teximage_param: state.teximage_param,
+            palette_base: state.palette_base,
+
+            vertex_start: ram.vertices.len() as u16,
+            vertex_count: clipped.len() as u8,
+            front_facing,
+            translucent: alpha != 0 && alpha != 31,
+        });
+        ram.vertices.extend(clipped);
+    }
+}
+
+// the sign of the area in homogeneous coordinates, which doesn't care about which side of the camera w is on
+fn is_front_facing(vertices: &[Vertex]) -> bool {
+    let [a, b, c] = [vertices[0].clip, vertices[1].clip, vertices[2].clip]
+        .map(|v| [v[0] as i64, v[1] as i64, v[3] as i64]);
+    let determinant = a[0] * (b[1] * c[2] - c[1] * b[2]) - b[0] * (a[1] * c[2] - c[1] * a[2])
+        + c[0] * (a[1] * b[2] - b[1] * a[2]);
+
+    // y points up in clip space, so counter clockwise there is clockwise on the screen
+    determinant >= 0
+}
+
+// Sutherland-Hodgman against the view volume, -w <= x, y, z <= w
+// polygons that cross the far plane are thrown away entirely unless they're allowed to be clipped
+pub fn clip_polygon(vertices: &[Vertex], far_plane_clip: bool) -> Option<Vec<Vertex>> {
+    if !far_plane_clip && vertices.iter().any(|v| v.clip[2] > v.clip[3]) {
+        return None;
+    }
+
+    let mut polygon = vertices.to_vec();
+    let mut output = Vec::with_capacity(MAX_CLIPPED_VERTICES);
+    for axis in 0..3 {
+        for sign in [1, -1] {
+            let distance = |v: &Vertex| v.clip[3] as i64 - sign * v.clip[axis] as i64;
+
+            output.clear();
+            for i in 0..polygon.len() {
+                let current = &polygon[i];
+                let next = &polygon[(i + 1) % polygon.len()];
+                let (d_current, d_next) = (distance(current), distance(next));
+
+                if d_current >= 0 {
+                    output.push(*current);
+                }
+                if (d_current >= 0) != (d_next >= 0) {
+                    output.push(interpolate(current, next, d_current, d_current - d_next));
+                }
+            }
+
+            std::mem::swap(&mut polygon, &mut output);
+            if polygon.len() < 3 {
+                return None;
+            }
+        }
+    }
+
+    Some(polygon)
+}
+
+fn interpolate(a: &Vertex, b: &Vertex, numerator: i64, denominator: i64) -> Vertex {
+    let lerp = |a: i64, b: i64| a + (b - a) * numerator / denominator;
+
+    Vertex {
+        clip: std::array::from_fn(|i| lerp(a.clip[i] as i64, b.clip[i] as i64) as i32),
+        screen: [0, 0],
+        color: std::array::from_fn(|i| lerp(a.color[i] as i64, b.color[i] as i64) as u8),
+        texcoord: std::array::from_fn(|i| lerp(a.texcoord[i] as i64, b.texcoord[i] as i64) as i16),
+    }
+}
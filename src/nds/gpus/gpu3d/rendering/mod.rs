@@ -0,0 +1,301 @@
+mod rasterizer;
+mod texture;
+
+use texture::{rgb555, TextureMemory};
+
+use crate::nds::{gpus::vram::VramBanks, Bits};
+
+use super::{
+    models::Disp3dCnt,
+    polygon::{Polygon, PolygonRam},
+};
+
+// 6 bits per colour component and 5 bits of alpha, this is what BG0 shows
+pub type Pixel3d = [u8; 4];
+
+#[derive(Clone, Copy, Default)]
+struct PixelAttributes {
+    opaque_id: u8,
+    translucent_id: u8,
+    translucent: bool,
+    edge: bool,
+    coverage: u8, // 0-31, how much of an edge pixel the polygon covers
+    fog: bool,
+    stencil: bool, // set by shadow mask polygons
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct Renderer {
+    pub disp3dcnt: Disp3dCnt, // 0x04000060
+    edge_colors: [u16; 8],    // 0x04000330
+    alpha_test_ref: u8,       // 0x04000340
+    clear_color: u32,         // 0x04000350
+    clear_depth: u16,         // 0x04000354
+    clear_offset: u16,        // 0x04000356
+    fog_color: u32,           // 0x04000358
+    fog_offset: u16,          // 0x0400035C
+    fog_table: [u8; 32],      // 0x04000360
+    toon_table: [u16; 32],    // 0x04000380
+    one_dot_depth: u16,       // 0x04000610
+
+    #[serde(skip)]
+    pub framebuffer: Vec<Pixel3d>,
+    #[serde(skip)]
+    below: Vec<Pixel3d>, // what an opaque pixel was drawn over, for anti-aliasing
+    #[serde(skip)]
+    depth: Vec<u32>,
+    #[serde(skip)]
+    attributes: Vec<PixelAttributes>,
+    #[serde(skip)]
+    textures: TextureMemory,
+}
+
+// 5 bit components become 6 bit ones the same way the hardware does it
+pub fn expand_color(color: [u8; 3]) -> [u8; 3] {
+    color.map(|c| if c == 0 { 0 } else { (c << 1) + 1 })
+}
+
+// 15 bit depths become 24 bit ones
+fn expand_depth(depth: u16) -> u32 {
+    let depth = (depth & 0x7FFF) as u32;
+    depth * 0x200 + ((depth + 1) / 0x8000) * 0x1FF
+}
+
+impl Renderer {
+    pub fn read_byte(&self, addr: usize) -> u8 {
+        // everything except DISP3DCNT is write only
+        match addr {
+            0x04000060..=0x04000063 => self.disp3dcnt.value().to_le_bytes()[addr & 3],
+            _ => 0,
+        }
+    }
+
+    pub fn write_byte(&mut self, addr: usize, value: u8) {
+        fn set_byte_16(target: &mut u16, addr: usize, value: u8) {
+            target.set_part::<1>((addr & 1) as u16, value as u16);
+        }
+        fn set_byte_32(target: &mut u32, addr: usize, value: u8) {
+            target.set_part::<1>((addr & 3) as u32, value as u32);
+        }
+
+        match addr {
+            0x04000060..=0x04000063 => {
+                let mut disp3dcnt = self.disp3dcnt.value() & !0x3000; // don't acknowledge by accident
+                set_byte_32(&mut disp3dcnt, addr, value);
+                self.disp3dcnt.set(disp3dcnt);
+            }
+            0x04000330..=0x0400033F => {
+                set_byte_16(&mut self.edge_colors[(addr - 0x04000330) / 2], addr, value)
+            }
+            0x04000340 => self.alpha_test_ref = value & 0x1F,
+            0x04000350..=0x04000353 => set_byte_32(&mut self.clear_color, addr, value),
+            0x04000354..=0x04000355 => set_byte_16(&mut self.clear_depth, addr, value),
+            0x04000356..=0x04000357 => set_byte_16(&mut self.clear_offset, addr, value),
+            0x04000358..=0x0400035B => set_byte_32(&mut self.fog_color, addr, value),
+            0x0400035C..=0x0400035D => set_byte_16(&mut self.fog_offset, addr, value),
+            0x04000360..=0x0400037F => self.fog_table[addr - 0x04000360] = value & 0x7F,
+            0x04000380..=0x040003BF => {
+                set_byte_16(&mut self.toon_table[(addr - 0x04000380) / 2], addr, value)
+            }
+            0x04000610..=0x04000611 => set_byte_16(&mut self.one_dot_depth, addr, value),
+            _ => {}
+        }
+    }
+
+    // draws a whole frame from the polygon RAM
+    pub fn render(&mut self, ram: &PolygonRam, swap_params: u32, vram_banks: &VramBanks) {
+        vram_banks.copy_texture_memory(&mut self.textures.image, &mut self.textures.palette);
+        self.clear();
+
+        let w_buffering = swap_params.get_bit(1);
+        let manual_sort = swap_params.get_bit(0);
+
+        // opaque polygons go first, translucent ones are drawn over them
+        let (mut translucent, opaque): (Vec<&Polygon>, Vec<&Polygon>) =
+            ram.polygons.iter().partition(|polygon| polygon.translucent);
+        if !manual_sort {
+            translucent.sort_by_key(|polygon| {
+                let vertices = &ram.vertices[polygon.vertex_start as usize
+                    ..(polygon.vertex_start as usize + polygon.vertex_count as usize)];
+                let bottom = vertices.iter().map(|v| v.screen[1]).max().unwrap_or(0);
+                let top = vertices.iter().map(|v| v.screen[1]).min().unwrap_or(0);
+                (bottom, top)
+            });
+        }
+
+        for polygon in opaque.into_iter().chain(translucent) {
+            self.draw_polygon(polygon, &ram.vertices, w_buffering);
+        }
+
+        if self.disp3dcnt.get_edge_marking() {
+            self.apply_edge_marking();
+        }
+        if self.disp3dcnt.get_fog() {
+            self.apply_fog();
+        }
+        if self.disp3dcnt.get_anti_aliasing() {
+            self.apply_anti_aliasing();
+        }
+    }
+
+    fn clear(&mut self) {
+        let clear_id = self.clear_color.get_bits(24, 29) as u8;
+        let clear_attributes = PixelAttributes {
+            opaque_id: clear_id,
+            translucent_id: clear_id,
+            fog: self.clear_color.get_bit(15),
+            ..Default::default()
+        };
+
+        self.framebuffer.resize(256 * 192, [0; 4]);
+        self.below.resize(256 * 192, [0; 4]);
+        self.depth.resize(256 * 192, 0);
+        self.attributes.resize(256 * 192, clear_attributes);
+
+        if self.disp3dcnt.get_clear_image() {
+            // the colours come from texture slot 2 and the depths from slot 3, both scrollable
+            let (offset_x, offset_y) = (self.clear_offset & 0xFF, self.clear_offset >> 8);
+            for y in 0..192 {
+                for x in 0..256 {
+                    let i = y * 256 + x;
+                    let source_x = (x + offset_x as usize) & 0xFF;
+                    let source_y = (y + offset_y as usize) & 0xFF;
+                    let addr = (source_y * 256 + source_x) * 2;
+
+                    let color = u16::from_le_bytes([
+                        self.textures.image[0x40000 + addr],
+                        self.textures.image[0x40000 + addr + 1],
+                    ]);
+                    let depth = u16::from_le_bytes([
+                        self.textures.image[0x60000 + addr],
+                        self.textures.image[0x60000 + addr + 1],
+                    ]);
+
+                    let [r, g, b] = expand_color(rgb555(color));
+                    self.framebuffer[i] = [r, g, b, if color.get_bit(15) { 31 } else { 0 }];
+                    self.depth[i] = expand_depth(depth);
+                    self.attributes[i] = PixelAttributes {
+                        fog: depth.get_bit(15),
+                        ..clear_attributes
+                    };
+                }
+            }
+        } else {
+            let [r, g, b] = expand_color(rgb555(self.clear_color as u16));
+            let alpha = self.clear_color.get_bits(16, 20) as u8;
+            self.framebuffer.fill([r, g, b, alpha]);
+            self.depth.fill(expand_depth(self.clear_depth));
+            self.attributes.fill(clear_attributes);
+        }
+        self.below.copy_from_slice(&self.framebuffer);
+    }
+
+    // edges of opaque polygons that are in front of a different polygon get outlined
+    fn apply_edge_marking(&mut self) {
+        let clear_id = self.clear_color.get_bits(24, 29) as u8;
+        let clear_depth = expand_depth(self.clear_depth);
+        let neighbour = |renderer: &Renderer, x: i32, y: i32| -> (u8, u32) {
+            if !(0..256).contains(&x) || !(0..192).contains(&y) {
+                return (clear_id, clear_depth);
+            }
+            let i = (y * 256 + x) as usize;
+            (renderer.attributes[i].opaque_id, renderer.depth[i])
+        };
+
+        let mut marked = Vec::new();
+        for y in 0..192 {
+            for x in 0..256 {
+                let i = (y * 256 + x) as usize;
+                let attributes = self.attributes[i];
+                if !attributes.edge {
+                    continue;
+                }
+
+                let id = attributes.opaque_id;
+                let depth = self.depth[i];
+                let is_outline = [(-1, 0), (1, 0), (0, -1), (0, 1)].iter().any(|(dx, dy)| {
+                    let (other_id, other_depth) = neighbour(self, x + dx, y + dy);
+                    other_id != id && depth < other_depth
+                });
+                if is_outline {
+                    marked.push((i, id));
+                }
+            }
+        }
+
+        for (i, id) in marked {
+            let [r, g, b] = expand_color(rgb555(self.edge_colors[(id >> 3) as usize]));
+            let alpha = self.framebuffer[i][3];
+            self.framebuffer[i] = [r, g, b, alpha];
+        }
+    }
+
+    fn apply_fog(&mut self) {
+        let [fog_r, fog_g, fog_b] = expand_color(rgb555(self.fog_color as u16));
+        let fog_alpha = self.fog_color.get_bits(16, 20);
+        let alpha_only = self.disp3dcnt.get_fog_alpha_only();
+        let step = (0x400 >> self.disp3dcnt.get_fog_shift()) as u32;
+        let offset = self.fog_offset as u32 & 0x7FFF;
+
+        for i in 0..256 * 192 {
+            if !self.attributes[i].fog {
+                continue;
+            }
+
+            let density = self.fog_density(self.depth[i] >> 9, offset, step);
+            let blend =
+                |fog: u32, color: u8| ((fog * density + color as u32 * (128 - density)) >> 7) as u8;
+
+            let pixel = &mut self.framebuffer[i];
+            if !alpha_only {
+                pixel[0] = blend(fog_r as u32, pixel[0]);
+                pixel[1] = blend(fog_g as u32, pixel[1]);
+                pixel[2] = blend(fog_b as u32, pixel[2]);
+            }
+            pixel[3] = blend(fog_alpha, pixel[3]);
+        }
+    }
+
+    // the table has 32 entries spread out from FOG_OFFSET, with linear interpolation between them
+    fn fog_density(&self, depth: u32, offset: u32, step: u32) -> u32 {
+        let density = |i: usize| match self.fog_table[i] {
+            127 => 128,
+            density => density as u32,
+        };
+
+        let first = offset + step;
+        if depth < first || step == 0 {
+            return density(0);
+        }
+
+        let index = ((depth - first) / step) as usize;
+        if index >= 31 {
+            return density(31);
+        }
+
+        let fraction = (depth - first) % step;
+        (density(index) * (step - fraction) + density(index + 1) * fraction) / step
+    }
+
+    // edges of opaque polygons are blended with whatever they were drawn over, by how much of the pixel they cover
+    fn apply_anti_aliasing(&mut self) {
+        for i in 0..256 * 192 {
+            let attributes = self.attributes[i];
+            if !attributes.edge || attributes.translucent {
+                continue;
+            }
+
+            let (pixel, below) = (self.framebuffer[i], self.below[i]);
+            let coverage = attributes.coverage as u16;
+            let blend = |new: u8, old: u8| {
+                ((new as u16 * (coverage + 1) + old as u16 * (31 - coverage)) >> 5) as u8
+            };
+            self.framebuffer[i] = [
+                blend(pixel[0], below[0]),
+                blend(pixel[1], below[1]),
+                blend(pixel[2], below[2]),
+                pixel[3],
+            ];
+        }
+    }
+}
@@ -0,0 +1,335 @@
+use crate::nds::gpus::gpu3d::{
+    models::{PolygonMode, TextureFormat},
+    polygon::{Polygon, Vertex},
+};
+
+use super::{expand_color, rgb555, texture::wrap_coordinate, Renderer};
+
+// everything that gets interpolated across a polygon
+// colours and texture coordinates are divided by w so they come out perspective correct
+#[derive(Clone, Copy)]
+struct Interpolant {
+    x: f32,
+    inverse_w: f32,
+    z: f32, // z-buffer depth, linear in screen space
+    color: [f32; 3],
+    texcoord: [f32; 2],
+}
+
+impl Interpolant {
+    fn new(vertex: &Vertex) -> Self {
+        let w = vertex.clip[3].max(1) as f32;
+        let z = vertex.clip[2] as f32 / w;
+        let color = expand_color(vertex.color);
+
+        Self {
+            x: vertex.screen[0] as f32,
+            inverse_w: 1.0 / w,
+            z: ((z * 0x4000 as f32) + 0x3FFF as f32) * 0x200 as f32,
+            color: color.map(|c| c as f32 / w),
+            texcoord: vertex.texcoord.map(|c| c as f32 / w),
+        }
+    }
+
+    fn lerp(a: &Self, b: &Self, t: f32) -> Self {
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        Self {
+            x: lerp(a.x, b.x),
+            inverse_w: lerp(a.inverse_w, b.inverse_w),
+            z: lerp(a.z, b.z),
+            color: std::array::from_fn(|i| lerp(a.color[i], b.color[i])),
+            texcoord: std::array::from_fn(|i| lerp(a.texcoord[i], b.texcoord[i])),
+        }
+    }
+}
+
+impl Renderer {
+    pub(super) fn draw_polygon(
+        &mut self,
+        polygon: &Polygon,
+        vertices: &[Vertex],
+        w_buffering: bool,
+    ) {
+        let start = polygon.vertex_start as usize;
+        let vertices = &vertices[start..start + polygon.vertex_count as usize];
+        let points: Vec<(f32, Interpolant)> = vertices
+            .iter()
+            .map(|v| (v.screen[1] as f32, Interpolant::new(v)))
+            .collect();
+
+        let top = vertices.iter().map(|v| v.screen[1]).min().unwrap_or(0);
+        let bottom = vertices.iter().map(|v| v.screen[1]).max().unwrap_or(0);
+        let left = vertices.iter().map(|v| v.screen[0]).min().unwrap_or(0);
+        let right = vertices.iter().map(|v| v.screen[0]).max().unwrap_or(0);
+
+        // polygons that shrink down to a single dot can be hidden past a certain depth
+        if !polygon.attr.get_one_dot() && right - left <= 1 && bottom - top <= 1 {
+            let w = vertices.iter().map(|v| v.clip[3]).max().unwrap_or(0);
+            if w > self.one_dot_depth as i32 {
+                return;
+            }
+        }
+
+        // the bottom row belongs to the next polygon, unless this one is flat
+        let flat = bottom == top;
+        let last_row = if flat { bottom } else { bottom - 1 };
+        let edge_x = |y: f32| span(&points, y.clamp(top as f32, bottom as f32));
+        for y in top.max(0)..=last_row.min(191) {
+            // pixels are sampled at their centres, so polygons sharing an edge never both draw a pixel
+            let centre = if flat { y as f32 } else { y as f32 + 0.5 };
+            let Some((span_left, span_right)) = span(&points, centre) else {
+                continue;
+            };
+            let x_start = (span_left.x - 0.5).ceil() as i32;
+            let x_end = ((span_right.x - 0.5).ceil() as i32 - 1).max(x_start);
+
+            // where the left and right edges are at the top and bottom of the row
+            // shallow edges run along the row for a few pixels, and all of them are on the edge
+            let (Some((top_left, top_right)), Some((bottom_left, bottom_right))) =
+                (edge_x(y as f32), edge_x(y as f32 + 1.0))
+            else {
+                continue;
+            };
+            let left_edge_end = top_left.x.max(bottom_left.x).ceil() as i32 - 1;
+            let right_edge_start = top_right.x.min(bottom_right.x).floor() as i32;
+            let is_top_or_bottom = y == top || y == last_row; // the top and bottom edges
+
+            for x in x_start.max(0)..=x_end.min(255) {
+                let t = if span_right.x > span_left.x {
+                    (x as f32 + 0.5 - span_left.x) / (span_right.x - span_left.x)
+                } else {
+                    0.0
+                };
+                let point = Interpolant::lerp(&span_left, &span_right, t.clamp(0.0, 1.0));
+
+                let left = (x == x_start || x <= left_edge_end)
+                    .then(|| edge_coverage(x, top_left.x, bottom_left.x, true));
+                let right = (x == x_end || x >= right_edge_start)
+                    .then(|| edge_coverage(x, top_right.x, bottom_right.x, false));
+                let edge = match (left, right) {
+                    (None, None) if !is_top_or_bottom => None,
+                    (left, right) => Some(left.unwrap_or(31).min(right.unwrap_or(31))),
+                };
+                self.draw_pixel(polygon, x as usize, y as usize, &point, edge, w_buffering);
+            }
+        }
+    }
+
+    fn draw_pixel(
+        &mut self,
+        polygon: &Polygon,
+        x: usize,
+        y: usize,
+        point: &Interpolant,
+        edge: Option<u8>, // how much of the pixel is covered when it's on an edge, 0-31
+        w_buffering: bool,
+    ) {
+        let attr = polygon.attr;
+        let w = 1.0 / point.inverse_w;
+        let depth = if w_buffering { w } else { point.z }.clamp(0.0, 0xFFFFFF as f32) as u32;
+
+        // polygons with an alpha of 0 are drawn as wireframes
+        let wireframe = attr.get_alpha() == 0;
+        if wireframe && edge.is_none() {
+            return;
+        }
+
+        let color = point.color.map(|c| (c * w).clamp(0.0, 63.0) as u8);
+        let texcoord = point.texcoord.map(|c| (c * w) as i32);
+        let ([r, g, b], alpha) = self.shade(polygon, color, texcoord);
+
+        if alpha == 0 || (self.disp3dcnt.get_alpha_test() && alpha <= self.alpha_test_ref) {
+            return;
+        }
+
+        let i = y * 256 + x;
+        let mode = attr.get_mode();
+        let id = attr.get_polygon_id();
+        let depth_passes = if attr.get_depth_equal() {
+            depth.abs_diff(self.depth[i]) <= 0x200
+        } else {
+            depth < self.depth[i]
+        };
+
+        // shadows are drawn in two steps, first a mask with id 0 marks where the volume is behind something
+        // then the shadow itself only draws over those marked pixels that belong to other polygons
+        if mode == PolygonMode::Shadow {
+            if id == 0 {
+                if !depth_passes {
+                    self.attributes[i].stencil = true;
+                }
+                return;
+            }
+
+            let attributes = self.attributes[i];
+            if !attributes.stencil || attributes.opaque_id == id {
+                return;
+            }
+            self.attributes[i].stencil = false;
+        }
+
+        if !depth_passes {
+            return;
+        }
+
+        let attributes = &mut self.attributes[i];
+        if alpha == 31 {
+            self.below[i] = self.framebuffer[i];
+            self.framebuffer[i] = [r, g, b, 31];
+            self.depth[i] = depth;
+            attributes.opaque_id = id;
+            attributes.translucent = false;
+            attributes.edge = edge.is_some();
+            attributes.coverage = edge.unwrap_or(31);
+            attributes.fog = attr.get_fog();
+            return;
+        }
+
+        // a translucent polygon can't draw over itself, or over another one sharing its id
+        if attributes.translucent && attributes.translucent_id == id {
+            return;
+        }
+
+        let existing = self.framebuffer[i];
+        self.framebuffer[i] = if self.disp3dcnt.get_alpha_blending() && existing[3] > 0 {
+            let blend = |new: u8, old: u8| {
+                ((new as u32 * (alpha as u32 + 1) + old as u32 * (31 - alpha as u32)) >> 5) as u8
+            };
+            [
+                blend(r, existing[0]),
+                blend(g, existing[1]),
+                blend(b, existing[2]),
+                alpha.max(existing[3]),
+            ]
+        } else {
+            [r, g, b, alpha]
+        };
+
+        attributes.translucent_id = id;
+        attributes.translucent = true;
+        attributes.fog &= attr.get_fog();
+        if attr.get_translucent_depth_update() {
+            self.depth[i] = depth;
+        }
+    }
+
+    // colours here are 6 bits per component, the result's alpha is 5 bits
+    fn shade(&self, polygon: &Polygon, color: [u8; 3], texcoord: [i32; 2]) -> ([u8; 3], u8) {
+        let attr = polygon.attr;
+        let alpha = match attr.get_alpha() {
+            0 => 31,
+            alpha => alpha,
+        };
+
+        let texel = self.sample_texture(polygon, texcoord);
+        let modulate = |texel: Option<([u8; 3], u8)>, color: [u8; 3]| match texel {
+            Some((texel_color, texel_alpha)) => (
+                std::array::from_fn(|i| {
+                    (((texel_color[i] as u32 + 1) * (color[i] as u32 + 1) - 1) >> 6) as u8
+                }),
+                (((texel_alpha as u32 + 1) * (alpha as u32 + 1) - 1) >> 5) as u8,
+            ),
+            None => (color, alpha),
+        };
+
+        match attr.get_mode() {
+            PolygonMode::Modulation | PolygonMode::Shadow => modulate(texel, color),
+            PolygonMode::Decal => match texel {
+                Some((texel_color, texel_alpha)) => {
+                    let texel_alpha = texel_alpha as u32;
+                    let color = std::array::from_fn(|i| match texel_alpha {
+                        31 => texel_color[i],
+                        _ => {
+                            ((texel_color[i] as u32 * texel_alpha
+                                + color[i] as u32 * (31 - texel_alpha))
+                                >> 5) as u8
+                        }
+                    });
+                    (color, alpha)
+                }
+                None => (color, alpha),
+            },
+            PolygonMode::Toon => {
+                // the table is looked up with the red component
+                let toon = expand_color(rgb555(self.toon_table[(color[0] >> 1) as usize]));
+                if self.disp3dcnt.get_highlight_shading() {
+                    let grey = [color[0]; 3];
+                    let (color, alpha) = modulate(texel, grey);
+                    (std::array::from_fn(|i| (color[i] + toon[i]).min(63)), alpha)
+                } else {
+                    modulate(texel, toon)
+                }
+            }
+        }
+    }
+
+    fn sample_texture(&self, polygon: &Polygon, texcoord: [i32; 2]) -> Option<([u8; 3], u8)> {
+        let param = polygon.teximage_param;
+        if !self.disp3dcnt.get_texture_mapping() || param.get_format() == TextureFormat::None {
+            return None;
+        }
+
+        let s = wrap_coordinate(
+            texcoord[0],
+            param.get_size_s(),
+            param.get_repeat_s(),
+            param.get_flip_s(),
+        );
+        let t = wrap_coordinate(
+            texcoord[1],
+            param.get_size_t(),
+            param.get_repeat_t(),
+            param.get_flip_t(),
+        );
+
+        self.textures
+            .sample(param, polygon.palette_base, s, t)
+            .map(|(color, alpha)| (expand_color(color), alpha))
+    }
+}
+
+// how much of a pixel on the edge is inside the polygon, from the edge's x at the top (a) and bottom (b) of the row
+fn edge_coverage(x: i32, a: f32, b: f32, inside_right: bool) -> u8 {
+    let (low, high) = (a.min(b), a.max(b));
+    let x = x as f32;
+    let coverage = if high - low < 1.0 {
+        // steep edges pass through the pixel, so it's how far across the pixel the edge is
+        let centre = (a + b) / 2.0;
+        if inside_right {
+            x + 1.0 - centre
+        } else {
+            centre - x
+        }
+    } else {
+        // shallow edges run along the row, so it's how far along the run the pixel is
+        let t = (x + 0.5 - low) / (high - low);
+        if inside_right {
+            t
+        } else {
+            1.0 - t
+        }
+    };
+    (coverage.clamp(0.0, 1.0) * 31.0).round() as u8
+}
+
+// where the polygon's outline crosses this row, leftmost first
+fn span(points: &[(f32, Interpolant)], y: f32) -> Option<(Interpolant, Interpolant)> {
+    let mut crossings = Vec::with_capacity(4);
+    for i in 0..points.len() {
+        let (y0, a) = &points[i];
+        let (y1, b) = &points[(i + 1) % points.len()];
+
+        if y0 == y1 {
+            if *y0 == y {
+                crossings.push(*a);
+                crossings.push(*b);
+            }
+        } else if y >= y0.min(*y1) && y <= y0.max(*y1) {
+            crossings.push(Interpolant::lerp(a, b, (y - y0) / (y1 - y0)));
+        }
+    }
+
+    let left = crossings.iter().min_by(|a, b| a.x.total_cmp(&b.x))?;
+    let right = crossings.iter().max_by(|a, b| a.x.total_cmp(&b.x))?;
+    Some((*left, *right))
+}
@@ -0,0 +1,124 @@
+use crate::nds::gpus::gpu3d::models::{TexImageParam, TextureFormat};
+
+// a copy of the texture slots taken when the frame starts rendering
+#[derive(Default)]
+pub struct TextureMemory {
+    pub image: Vec<u8>,   // 4 slots of 128KB
+    pub palette: Vec<u8>, // 6 slots of 16KB
+}
+
+// colours are 5 bits per component here, alpha is 0..=31
+pub type Texel = ([u8; 3], u8);
+
+pub fn rgb555(value: u16) -> [u8; 3] {
+    [
+        (value & 0x1F) as u8,
+        ((value >> 5) & 0x1F) as u8,
+        ((value >> 10) & 0x1F) as u8,
+    ]
+}
+
+impl TextureMemory {
+    fn image_byte(&self, addr: u32) -> u8 {
+        self.image[addr as usize & 0x7FFFF]
+    }
+
+    fn image_halfword(&self, addr: u32) -> u16 {
+        u16::from_le_bytes([self.image_byte(addr), self.image_byte(addr + 1)])
+    }
+
+    fn palette_color(&self, addr: u32) -> [u8; 3] {
+        let addr = addr as usize % self.palette.len();
+        rgb555(u16::from_le_bytes([
+            self.palette[addr],
+            self.palette[(addr + 1) % self.palette.len()],
+        ]))
+    }
+
+    // s and t are in texels, already wrapped or clamped
+    pub fn sample(&self, param: TexImageParam, palette_base: u32, s: u32, t: u32) -> Option<Texel> {
+        let width = param.get_size_s();
+        let base = param.get_vram_offset();
+        let texel = t * width + s;
+        // 4 colour palettes are addressed in steps of 8 bytes, everything else in steps of 16
+        let palette = palette_base << 4;
+        let transparent0 = param.get_color0_transparent();
+
+        match param.get_format() {
+            TextureFormat::None => None,
+            TextureFormat::A3I5 => {
+                let value = self.image_byte(base + texel);
+                let alpha = value >> 5;
+                let color = self.palette_color(palette + (value as u32 & 0x1F) * 2);
+                Some((color, (alpha << 2) | (alpha >> 1)))
+            }
+            TextureFormat::Palette4 => {
+                let value = (self.image_byte(base + texel / 4) >> ((texel & 3) * 2)) & 3;
+                let color = self.palette_color((palette_base << 3) + value as u32 * 2);
+                Some((color, if transparent0 && value == 0 { 0 } else { 31 }))
+            }
+            TextureFormat::Palette16 => {
+                let value = (self.image_byte(base + texel / 2) >> ((texel & 1) * 4)) & 0xF;
+                let color = self.palette_color(palette + value as u32 * 2);
+                Some((color, if transparent0 && value == 0 { 0 } else { 31 }))
+            }
+            TextureFormat::Palette256 => {
+                let value = self.image_byte(base + texel);
+                let color = self.palette_color(palette + value as u32 * 2);
+                Some((color, if transparent0 && value == 0 { 0 } else { 31 }))
+            }
+            TextureFormat::Compressed => Some(self.sample_compressed(base, palette, width, s, t)),
+            TextureFormat::A5I3 => {
+                let value = self.image_byte(base + texel);
+                let color = self.palette_color(palette + (value as u32 & 7) * 2);
+                Some((color, value >> 3))
+            }
+            TextureFormat::Direct => {
+                let value = self.image_halfword(base + texel * 2);
+                Some((rgb555(value), if value & 0x8000 != 0 { 31 } else { 0 }))
+            }
+        }
+    }
+
+    // 4x4 blocks of 2 bit indices in slot 0 or 2, with a halfword per block in slot 1 saying what they mean
+    fn sample_compressed(&self, base: u32, palette: u32, width: u32, s: u32, t: u32) -> Texel {
+        let block = (t / 4) * (width / 4) + s / 4;
+        let block_addr = base + block * 4;
+        let row = self.image_byte(block_addr + (t & 3));
+        let index = (row >> ((s & 3) * 2)) & 3;
+
+        let slot = block_addr & 0x60000;
+        let info_addr =
+            0x20000 + ((block_addr & 0x1FFFF) >> 1) + if slot == 0x40000 { 0x10000 } else { 0 };
+        let info = self.image_halfword(info_addr);
+        let palette = palette + (info as u32 & 0x3FFF) * 4;
+        let color = |i: u32| self.palette_color(palette + i * 2);
+        let mix = |a: u32, b: u32, c0: [u8; 3], c1: [u8; 3]| -> [u8; 3] {
+            std::array::from_fn(|i| ((c0[i] as u32 * a + c1[i] as u32 * b) / (a + b)) as u8)
+        };
+
+        match (info >> 14, index) {
+            (0 | 1, 3) => ([0; 3], 0),
+            (1, 2) => (mix(1, 1, color(0), color(1)), 31),
+            (3, 2) => (mix(5, 3, color(0), color(1)), 31),
+            (3, 3) => (mix(3, 5, color(0), color(1)), 31),
+            (_, index) => (color(index as u32), 31),
+        }
+    }
+}
+
+// texture coordinates are 1.11.4, this turns them into a texel along one axis
+pub fn wrap_coordinate(coordinate: i32, size: u32, repeat: bool, flip: bool) -> u32 {
+    let size = size as i32;
+    let texel = coordinate >> 4;
+    if !repeat {
+        return texel.clamp(0, size - 1) as u32;
+    }
+
+    let wrapped = texel.rem_euclid(size);
+    if flip && texel.div_euclid(size) & 1 != 0 {
+        (size - 1 - wrapped) as u32
+    } else {
+        wrapped as u32
+    }
+}
@@ -1,12 +1,20 @@
+pub mod framebuffer;
 pub mod gpu2d;
+pub mod gpu3d;
 mod models;
 mod vram;
 
-use gpu2d::Gpu2d;
+use framebuffer::Framebuffer;
+use gpu2d::{models::DisplayMode, Gpu2d};
+use gpu3d::Gpu3d;
 use models::DispStat;
 use vram::VramBanks;
 
-use crate::nds::bus::{bus7::Bus7, bus9::Bus9};
+use crate::nds::{
+    bus::{bus7::Bus7, bus9::Bus9},
+    dma::DmaTriggers,
+    shared::models::PowCnt1,
+};
 
 #[derive(Default, serde::Deserialize, serde::Serialize)]
 pub struct Gpus {
@@ -15,14 +23,16 @@ pub struct Gpus {
 
     pub a: Gpu2d<true>,
     pub b: Gpu2d<false>,
+    pub gpu3d: Gpu3d,
 
     pub vram_banks: VramBanks,
     pub wramcnt: u8,
 
-    x: u32, // TODO: this isn't real. ideally the clock function should just do an entire row at a time but I cannot be bothered touching cycle stuff rn. performance will suffer.
+    line_cycles: u32, // how far into the current line we are
 }
 
 impl Gpus {
+    #[cfg(feature = "ui")]
     pub fn new_fake() -> Self {
         Self {
             dispstat: DispStat::default(),
@@ -30,27 +40,65 @@ impl Gpus {
 
             a: Gpu2d::new_fake(),
             b: Gpu2d::new_fake(),
+            gpu3d: Gpu3d::default(),
 
             vram_banks: VramBanks::new_fake(),
             wramcnt: 0,
 
-            x: 0,
+            line_cycles: 0,
         }
     }
 }
 
+// the clock runs at 33MHz and each dot takes 6 cycles, a line is 256 visible dots and 99 in hblank
+const CYCLES_PER_DOT: u32 = 6;
+const HBLANK_START: u32 = 256 * CYCLES_PER_DOT;
+const CYCLES_PER_LINE: u32 = (256 + 99) * CYCLES_PER_DOT;
+
 impl Gpus {
-    pub fn clock(&mut self, bus9: &mut Bus9, bus7: &mut Bus7) {
-        self.x = (self.x + 1) % (256 + 99);
-        self.vcount = (self.vcount + (self.x == 0) as u16) % (192 + 71);
+    pub fn clock(&mut self, bus9: &mut Bus9, bus7: &mut Bus7, dma_triggers: &mut DmaTriggers) {
+        self.line_cycles = (self.line_cycles + 1) % CYCLES_PER_LINE;
+        let line_start = self.line_cycles == 0;
+        self.vcount = (self.vcount + line_start as u16) % (192 + 71);
 
-        let hblanking = self.x >= 256;
-        let hblank_start = self.x == 256;
+        let hblanking = self.line_cycles >= HBLANK_START;
+        let hblank_start = self.line_cycles == HBLANK_START;
         let vblanking = self.vcount >= 192 && self.vcount != 262;
-        let vblank_start = self.vcount == 192;
+        let vblank_start = self.vcount == 192 && line_start;
         self.dispstat.set_hblank_flag(hblanking);
         self.dispstat.set_vblank_flag(vblanking);
 
+        if hblank_start && self.vcount < 192 {
+            dma_triggers.insert(DmaTriggers::HBLANK);
+            let framebuffer_3d = &self.gpu3d.renderer.framebuffer;
+            self.a
+                .hblank(self.vcount, &mut self.vram_banks, framebuffer_3d);
+            self.b
+                .hblank(self.vcount, &mut self.vram_banks, framebuffer_3d);
+        }
+        if vblank_start {
+            dma_triggers.insert(DmaTriggers::VBLANK);
+            self.a.vblank();
+            self.b.vblank();
+            self.gpu3d.vblank(&self.vram_banks);
+        }
+        if self.vcount == 0 && line_start {
+            dma_triggers.insert(DmaTriggers::DISPLAY_START);
+        }
+        // the display FIFO is drained 8 pixels at a time
+        if self.vcount < 192
+            && !hblanking
+            && self.line_cycles % (8 * CYCLES_PER_DOT) == 0
+            && self.a.dispcnt.get_display_mode() == DisplayMode::MAIN_MEMORY_DISPLAY
+        {
+            dma_triggers.insert(DmaTriggers::MAIN_MEMORY_DISPLAY);
+        }
+
+        self.gpu3d.clock(&mut bus9.interrupts);
+        if self.gpu3d.wants_dma() {
+            dma_triggers.insert(DmaTriggers::GX_FIFO);
+        }
+
         if hblank_start && self.dispstat.get_hblank_irq_enable() {
             bus9.interrupts.f.set_lcd_hblank(true);
             bus7.interrupts.f.set_lcd_hblank(true);
@@ -63,9 +111,27 @@ impl Gpus {
         let vcount_setting = self.dispstat.get_vcount_setting();
         let is_vcounter_match = vcount_setting == self.vcount;
         self.dispstat.set_vcounter_flag(is_vcounter_match);
-        if is_vcounter_match && self.dispstat.get_vcounter_irq_enable() {
+        // only requested once when the line starts, so it can be acknowledged within the line
+        if line_start && is_vcounter_match && self.dispstat.get_vcounter_irq_enable() {
             bus9.interrupts.f.set_lcd_vcounter_match(true);
             bus7.interrupts.f.set_lcd_vcounter_match(true);
         }
     }
 }
+
+impl Gpus {
+    // engine A is on the top screen when POWCNT1's display swap bit is set, and on the bottom otherwise
+    pub fn top_screen(&self, powcnt1: &PowCnt1) -> &Framebuffer {
+        match powcnt1.get_display_swap() {
+            true => &self.a.framebuffer,
+            false => &self.b.framebuffer,
+        }
+    }
+
+    pub fn bottom_screen(&self, powcnt1: &PowCnt1) -> &Framebuffer {
+        match powcnt1.get_display_swap() {
+            true => &self.b.framebuffer,
+            false => &self.a.framebuffer,
+        }
+    }
+}
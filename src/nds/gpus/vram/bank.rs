@@ -49,6 +49,7 @@ impl<const ID: u8> Default for VramBank<ID> {
 }
 
 impl<const ID: u8> VramBank<ID> {
+    #[cfg(feature = "ui")]
     pub fn new_fake() -> Self {
         Self {
             mst: Mst::default(),
@@ -192,6 +193,25 @@ impl<const ID: u8> VramBank<ID> {
         false
     }
 
+    // texture image slots are 128KB each and texture palette slots are 16KB
+    pub fn copy_texture_data(&self, image: &mut [u8], palette: &mut [u8]) {
+        if !self.enabled || !matches!(self.mst, Mst::D) {
+            return;
+        }
+
+        let (memory, start) = match ID {
+            0..=3 => (image, self.start * 0x20000),
+            4..=6 => (palette, self.start * 0x4000),
+            _ => return,
+        };
+        for (target, value) in memory[start..start + self.data.len()]
+            .iter_mut()
+            .zip(&self.data)
+        {
+            *target |= value; // overlapping banks get mixed together, like on the bus
+        }
+    }
+
     pub fn read_virtual_slice<const T: usize>(&self, addr: usize) -> (bool, [u8; T]) {
         if self.on_bus || !self.enabled {
             return (false, [0; T]);
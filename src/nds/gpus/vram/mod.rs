@@ -17,6 +17,7 @@ pub struct VramBanks {
 }
 
 impl VramBanks {
+    #[cfg(feature = "ui")]
     pub fn new_fake() -> Self {
         Self {
             a: VramBank::new_fake(),
@@ -69,6 +70,22 @@ impl VramBanks {
         a | b | c | d | e | f | g | h | i
     }
 
+    // the 3D engine reads textures from banks that aren't on the bus, 512KB of images and 96KB of palettes
+    pub fn copy_texture_memory(&self, image: &mut Vec<u8>, palette: &mut Vec<u8>) {
+        image.clear();
+        image.resize(0x80000, 0);
+        palette.clear();
+        palette.resize(0x18000, 0);
+
+        self.a.copy_texture_data(image, palette);
+        self.b.copy_texture_data(image, palette);
+        self.c.copy_texture_data(image, palette);
+        self.d.copy_texture_data(image, palette);
+        self.e.copy_texture_data(image, palette);
+        self.f.copy_texture_data(image, palette);
+        self.g.copy_texture_data(image, palette);
+    }
+
     pub fn read_virtual_slice<const T: usize>(
         &self,
         virtual_location: VirtualLocation,
@@ -116,6 +133,7 @@ impl VramBanks {
     }
 }
 
+#[derive(Clone, Copy)]
 pub enum VirtualLocation {
     BgExtendedPaletteA,
     BgExtendedPaletteB,
@@ -47,6 +47,7 @@ impl InterruptFlags {
     const LCD_HBLANK_OFFSET: u32 = 1;
     const LCD_VCOUNTER_MATCH_OFFSET: u32 = 2;
     const TIMER_OVERFLOW_START: u32 = 3;
+    const SIO_RTC_OFFSET: u32 = 7; // arm7 only
 
     const DMA0_OFFSET: u32 = 8;
     const DMA1_OFFSET: u32 = 9;
@@ -58,6 +59,8 @@ impl InterruptFlags {
     const IPC_RECEIVE_FIFO_NOT_EMPTY_OFFSET: u32 = 18;
     const NDS_GAME_CARD_DATA_TRANSFER_COMPLETION_OFFSET: u32 = 19;
 
+    const GEOMETRY_FIFO_OFFSET: u32 = 21; // arm9 only
+
     pub fn value(&self) -> u32 {
         self.0
     }
@@ -83,6 +86,13 @@ impl InterruptFlags {
         self.0.set_bit(bit, self.0.get_bit(bit) || value);
     }
 
+    pub fn falsy_set_rtc(&mut self, value: bool) {
+        self.0.set_bit(
+            Self::SIO_RTC_OFFSET,
+            self.0.get_bit(Self::SIO_RTC_OFFSET) || value,
+        );
+    }
+
     pub fn set_dma(&mut self, dma: u8, value: bool) {
         let bit = match dma {
             0 => Self::DMA0_OFFSET,
@@ -129,4 +139,11 @@ impl InterruptFlags {
                 || value,
         );
     }
+
+    pub fn falsy_set_geometry_fifo(&mut self, value: bool) {
+        self.0.set_bit(
+            Self::GEOMETRY_FIFO_OFFSET,
+            self.0.get_bit(Self::GEOMETRY_FIFO_OFFSET) || value,
+        );
+    }
 }
@@ -102,6 +102,9 @@ pub enum LogSource {
     Cart,
     VramBank(u8),
     Spi,
+    Rtc,
+    Gpu2d,
+    Gpu3d,
 }
 
 impl Display for LogKind {
@@ -154,6 +157,9 @@ impl Display for LogSource {
             LogSource::Cart => write!(f, "Cart"),
             LogSource::VramBank(id) => write!(f, "VramBank({})", id),
             LogSource::Spi => write!(f, "SPI"),
+            LogSource::Rtc => write!(f, "RTC"),
+            LogSource::Gpu2d => write!(f, "GPU2D"),
+            LogSource::Gpu3d => write!(f, "GPU3D"),
         }
     }
 }
@@ -1,7 +1,7 @@
 pub mod arm;
 mod bits;
 pub mod bus;
-mod cart;
+pub mod cart;
 mod cp15;
 mod div;
 pub mod dma;
@@ -9,10 +9,14 @@ mod emulator;
 pub mod gpus;
 mod interrupts;
 pub mod logger;
+pub mod rtc;
 pub mod shared;
 mod spi;
+pub mod spu;
 mod sqrt;
 mod timers;
 
 pub use bits::*;
-pub use emulator::{CycleState, Emulator};
+pub use emulator::Emulator;
+#[cfg(feature = "ui")]
+pub use emulator::{Breakpoints, CycleState};
@@ -0,0 +1,388 @@
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, TimeDelta, Timelike};
+
+use super::{interrupts::Interrupts, logger, Bits};
+
+// Seiko S-3511A, the ARM7 talks to it by bit-banging 0x04000138
+// bits are latched on the rising edge of SCK, the command byte is sent MSB first and data bytes LSB first
+// most of this is from gbatek, melonDS for the bits it doesn't cover
+
+const CYCLES_PER_TICK: u32 = 33_513_982 / 32; // the interrupt logic runs at 32Hz
+
+#[derive(Clone, Copy, Default)]
+pub struct RtcSettings {
+    pub offset: i64,                   // seconds added on top of the current time
+    pub frozen: Option<NaiveDateTime>, // report this time forever instead of the host's clock
+}
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct Rtc {
+    cnt: u8, // 0x04000138
+
+    command: Option<u8>,
+    bit: u8,
+    byte: u8,
+    position: usize,
+    input: Vec<u8>,
+    output: Vec<u8>,
+
+    status1: u8,
+    status2: u8,
+    alarm1: [u8; 3],
+    alarm2: [u8; 3],
+    frequency: u8, // alarm1's register when INT1 is in frequency mode
+    clock_adjust: u8,
+    free: u8,
+    adjust: i64, // seconds the software has moved the clock by
+
+    cycles: u32,
+    ticks: u32,
+    frequency_active: bool,
+    last_minute: Option<u32>,
+
+    #[serde(skip)]
+    pub settings: RtcSettings,
+}
+
+impl Rtc {
+    const DATA_OFFSET: u8 = 0;
+    const SCK_OFFSET: u8 = 1;
+    const CS_OFFSET: u8 = 2;
+    const DATA_DIRECTION_OFFSET: u8 = 4; // 0: in, 1: out
+
+    const STATUS1_RESET_OFFSET: u8 = 0;
+    const STATUS1_24_HOUR_OFFSET: u8 = 1;
+    const STATUS1_INT1_OFFSET: u8 = 4;
+    const STATUS1_INT2_OFFSET: u8 = 5;
+    const STATUS2_INT2_ENABLE_OFFSET: u8 = 6;
+
+    const HOUR_PM_OFFSET: u8 = 6;
+    const ALARM_ENABLE_OFFSET: u8 = 7;
+
+    // the battery keeps it going, so only the transfer is interrupted
+    pub fn reset(&mut self) {
+        self.cnt = 0;
+        self.end_transfer();
+    }
+
+    pub fn load_state(&mut self, rtc: Rtc) {
+        let settings = self.settings;
+        *self = rtc;
+        self.settings = settings;
+    }
+
+    pub fn read(&self) -> u8 {
+        self.cnt
+    }
+
+    pub fn write(&mut self, value: u8) {
+        let old = self.cnt;
+        let data = if value.get_bit(Self::DATA_DIRECTION_OFFSET) {
+            value.get_bit(Self::DATA_OFFSET)
+        } else {
+            old.get_bit(Self::DATA_OFFSET)
+        };
+        self.cnt = value;
+        self.cnt.set_bit(Self::DATA_OFFSET, data);
+
+        if !self.cnt.get_bit(Self::CS_OFFSET) {
+            if old.get_bit(Self::CS_OFFSET) {
+                self.end_transfer();
+            }
+            return;
+        }
+
+        if !old.get_bit(Self::CS_OFFSET) {
+            // new transfer, the command comes first
+            self.end_transfer();
+            return;
+        }
+
+        if old.get_bit(Self::SCK_OFFSET) || !self.cnt.get_bit(Self::SCK_OFFSET) {
+            return;
+        }
+
+        if self.cnt.get_bit(Self::DATA_DIRECTION_OFFSET) {
+            self.input_bit(data);
+        } else {
+            let bit = self.output_bit();
+            self.cnt.set_bit(Self::DATA_OFFSET, bit);
+        }
+    }
+
+    pub fn clock(&mut self, cycles: u32, interrupts: &mut Interrupts, rcnt: u16) {
+        self.cycles += cycles;
+        while self.cycles >= CYCLES_PER_TICK {
+            self.cycles -= CYCLES_PER_TICK;
+            self.tick(interrupts, rcnt);
+        }
+    }
+
+    fn tick(&mut self, interrupts: &mut Interrupts, rcnt: u16) {
+        self.ticks = (self.ticks + 1) % 32;
+
+        let now = self.now();
+        let minute = now.hour() * 60 + now.minute();
+        let minute_changed = self.last_minute.is_some_and(|last| last != minute);
+        self.last_minute = Some(minute);
+
+        let int1 = match self.status2 & 0xF {
+            0b0001 | 0b0101 => {
+                // each bit selects a square wave, 1Hz at bit 0 up to 16Hz at bit 4
+                let active =
+                    (0..5).any(|i| self.frequency.get_bit(i) && self.ticks.get_bit(4 - i as u32));
+                let rising = active && !self.frequency_active;
+                self.frequency_active = active;
+                rising
+            }
+            0b0010 | 0b0011 | 0b0110 | 0b0111 => minute_changed,
+            0b0100 => minute_changed && self.alarm_matches(&self.alarm1, now),
+            _ => false, // disabled, or 32kHz output
+        };
+        let int2 = self.status2.get_bit(Self::STATUS2_INT2_ENABLE_OFFSET)
+            && minute_changed
+            && self.alarm_matches(&self.alarm2, now);
+
+        if !int1 && !int2 {
+            return;
+        }
+
+        if int1 {
+            self.status1.set_bit(Self::STATUS1_INT1_OFFSET, true);
+        }
+        if int2 {
+            self.status1.set_bit(Self::STATUS1_INT2_OFFSET, true);
+        }
+
+        // only reaches the ARM7 while RCNT is in GPIO mode with the SI interrupt enabled
+        if rcnt & 0xC100 == 0x8100 {
+            interrupts.f.falsy_set_rtc(true);
+        }
+    }
+
+    fn input_bit(&mut self, value: bool) {
+        self.byte.set_bit(self.bit, value);
+        self.bit += 1;
+        if self.bit < 8 {
+            return;
+        }
+
+        let byte = self.byte;
+        self.byte = 0;
+        self.bit = 0;
+
+        match self.command {
+            None => self.start_command(byte),
+            Some(command) if !command.get_bit(7) => {
+                self.input.push(byte);
+                self.write_register(command.get_bits(4, 6), byte);
+                self.position += 1;
+            }
+            Some(_) => {} // writing during a read does nothing
+        }
+    }
+
+    fn output_bit(&mut self) -> bool {
+        if self.command.is_none() {
+            return false;
+        }
+
+        let byte = self.output.get(self.position).copied().unwrap_or(0);
+        let bit = byte.get_bit(self.bit);
+        self.bit += 1;
+        if self.bit == 8 {
+            self.bit = 0;
+            self.position += 1;
+        }
+
+        bit
+    }
+
+    fn start_command(&mut self, byte: u8) {
+        // gbatek's order is 0110 in the low nibble, but it's often sent the other way around
+        let command = if byte & 0x0F == 0x06 {
+            byte
+        } else if byte & 0xF0 == 0x60 {
+            byte.reverse_bits()
+        } else {
+            logger::warn_once(
+                logger::LogSource::Rtc,
+                format!("Unknown RTC command {:#04X}", byte),
+            );
+            return;
+        };
+
+        self.command = Some(command);
+        self.position = 0;
+        self.input.clear();
+        if command.get_bit(7) {
+            self.output = self.read_register(command.get_bits(4, 6));
+        }
+    }
+
+    fn end_transfer(&mut self) {
+        self.command = None;
+        self.bit = 0;
+        self.byte = 0;
+        self.position = 0;
+        self.input.clear();
+        self.output.clear();
+    }
+
+    fn read_register(&mut self, register: u8) -> Vec<u8> {
+        match register {
+            0 => {
+                let value = self.status1;
+                // the interrupt and power flags are cleared once they've been seen
+                self.status1 &= 0x0F;
+                vec![value]
+            }
+            1 => vec![self.status2],
+            2 => self.encode_date_time(self.now()),
+            3 => self.encode_date_time(self.now())[4..].to_vec(),
+            4 => {
+                if self.is_frequency_mode() {
+                    vec![self.frequency]
+                } else {
+                    self.alarm1.to_vec()
+                }
+            }
+            5 => self.alarm2.to_vec(),
+            6 => vec![self.clock_adjust],
+            7 => vec![self.free],
+            _ => unreachable!(),
+        }
+    }
+
+    fn write_register(&mut self, register: u8, value: u8) {
+        match (register, self.position) {
+            (0, 0) => {
+                if value.get_bit(Self::STATUS1_RESET_OFFSET) {
+                    self.reset_registers();
+                }
+                self.status1 = (self.status1 & !0x0E) | (value & 0x0E);
+            }
+            (1, 0) => self.status2 = value,
+            (2, 6) => {
+                let input = self.input.clone();
+                self.set_date_time(&input);
+            }
+            (3, 2) => {
+                let mut input = self.encode_date_time(self.now())[..4].to_vec();
+                input.extend_from_slice(&self.input);
+                self.set_date_time(&input);
+            }
+            (4, 0) if self.is_frequency_mode() => self.frequency = value,
+            (4, 0..=2) => self.alarm1[self.position] = value,
+            (5, 0..=2) => self.alarm2[self.position] = value,
+            (6, 0) => self.clock_adjust = value,
+            (7, 0) => self.free = value,
+            _ => {}
+        }
+    }
+
+    fn reset_registers(&mut self) {
+        self.status1 = 0;
+        self.status2 = 0;
+        self.alarm1 = [0; 3];
+        self.alarm2 = [0; 3];
+        self.frequency = 0;
+        self.clock_adjust = 0;
+        self.free = 0;
+
+        let midnight = NaiveDate::from_ymd_opt(2000, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        self.set_time(midnight);
+    }
+
+    fn is_frequency_mode(&self) -> bool {
+        self.status2 & 0b1011 == 0b0001
+    }
+
+    fn alarm_matches(&self, alarm: &[u8; 3], now: NaiveDateTime) -> bool {
+        let weekday = now.weekday().num_days_from_sunday();
+        let hour = self.decode_hour(alarm[1]);
+        let minute = from_bcd(alarm[2] & 0x7F);
+
+        (!alarm[0].get_bit(Self::ALARM_ENABLE_OFFSET) || (alarm[0] & 0x07) as u32 == weekday)
+            && (!alarm[1].get_bit(Self::ALARM_ENABLE_OFFSET) || hour == now.hour())
+            && (!alarm[2].get_bit(Self::ALARM_ENABLE_OFFSET) || minute == now.minute())
+    }
+
+    fn now(&self) -> NaiveDateTime {
+        let base = self
+            .settings
+            .frozen
+            .unwrap_or_else(|| Local::now().naive_local());
+        TimeDelta::try_seconds(self.settings.offset + self.adjust)
+            .and_then(|delta| base.checked_add_signed(delta))
+            .unwrap_or(base)
+    }
+
+    fn set_time(&mut self, time: NaiveDateTime) {
+        self.adjust += (time - self.now()).num_seconds();
+    }
+
+    // year, month, day, day of week, hour, minute, second
+    fn encode_date_time(&self, time: NaiveDateTime) -> Vec<u8> {
+        let hour = if self.status1.get_bit(Self::STATUS1_24_HOUR_OFFSET) {
+            to_bcd(time.hour())
+        } else {
+            to_bcd(time.hour() % 12)
+        };
+
+        vec![
+            to_bcd(time.year().rem_euclid(100) as u32),
+            to_bcd(time.month()),
+            to_bcd(time.day()),
+            time.weekday().num_days_from_sunday() as u8,
+            // the PM flag is set in 24 hour mode too
+            hour | ((time.hour() >= 12) as u8) << Self::HOUR_PM_OFFSET,
+            to_bcd(time.minute()),
+            to_bcd(time.second()),
+        ]
+    }
+
+    fn set_date_time(&mut self, input: &[u8]) {
+        let time = NaiveDate::from_ymd_opt(
+            2000 + from_bcd(input[0]) as i32,
+            from_bcd(input[1] & 0x1F),
+            from_bcd(input[2] & 0x3F),
+        )
+        .and_then(|date| {
+            date.and_hms_opt(
+                self.decode_hour(input[4]),
+                from_bcd(input[5] & 0x7F),
+                from_bcd(input[6] & 0x7F),
+            )
+        });
+
+        match time {
+            Some(time) => self.set_time(time),
+            None => logger::warn_once(
+                logger::LogSource::Rtc,
+                format!("Invalid date/time written to the RTC {:02X?}", input),
+            ),
+        }
+    }
+
+    fn decode_hour(&self, value: u8) -> u32 {
+        let hour = from_bcd(value & 0x3F);
+        if !self.status1.get_bit(Self::STATUS1_24_HOUR_OFFSET)
+            && value.get_bit(Self::HOUR_PM_OFFSET)
+        {
+            hour + 12
+        } else {
+            hour
+        }
+    }
+}
+
+fn to_bcd(value: u32) -> u8 {
+    (((value / 10) << 4) | (value % 10)) as u8
+}
+
+fn from_bcd(value: u8) -> u32 {
+    ((value >> 4) * 10 + (value & 0x0F)) as u32
+}
@@ -1,6 +1,6 @@
 use models::{ExtKeyIn, IpcFifo, IpcSync, KeyInput, PostFlg, PowCnt1};
 
-use super::{cart::Cartridge, gpus::Gpus};
+use super::{cart::Cartridge, dma::DmaTriggers, gpus::Gpus};
 
 #[derive(serde::Deserialize, serde::Serialize)]
 pub struct Shared {
@@ -20,6 +20,8 @@ pub struct Shared {
 
     #[serde(skip)]
     pub touchscreen_point: (f32, f32), // not real
+    #[serde(skip)]
+    pub dma_triggers: DmaTriggers, // not real, events waiting to be handed to the DMAs
 }
 
 impl Default for Shared {
@@ -38,11 +40,13 @@ impl Default for Shared {
             powcnt1: PowCnt1::default(),
 
             touchscreen_point: (0.0, 0.0),
+            dma_triggers: DmaTriggers::empty(),
         }
     }
 }
 
 impl Shared {
+    #[cfg(feature = "ui")]
     pub fn new_fake() -> Self {
         Self {
             cart: Cartridge::default(),
@@ -58,9 +62,11 @@ impl Shared {
             powcnt1: PowCnt1::default(),
 
             touchscreen_point: (0.0, 0.0),
+            dma_triggers: DmaTriggers::empty(),
         }
     }
 
+    #[cfg(feature = "ui")]
     pub fn load_state(&mut self, shared: Self) {
         self.cart.load_state(shared.cart);
         self.gpus = shared.gpus;
@@ -82,6 +88,7 @@ impl Shared {
         self.ipcsync = IpcSync::default();
         self.ipcfifo = IpcFifo::default();
         self.powcnt1 = PowCnt1::default();
+        self.dma_triggers = DmaTriggers::empty();
     }
 }
 
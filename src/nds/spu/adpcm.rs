@@ -0,0 +1,52 @@
+// IMA-ADPCM, 4 bits per sample with a header word holding the initial value and table index
+
+const INDEX_TABLE: [i32; 8] = [-1, -1, -1, -1, 2, 4, 6, 8];
+
+const STEP_TABLE: [i32; 89] = [
+    0x0007, 0x0008, 0x0009, 0x000A, 0x000B, 0x000C, 0x000D, 0x000E, 0x0010, 0x0011, 0x0013, 0x0015,
+    0x0017, 0x0019, 0x001C, 0x001F, 0x0022, 0x0025, 0x0029, 0x002D, 0x0032, 0x0037, 0x003C, 0x0042,
+    0x0049, 0x0050, 0x0058, 0x0061, 0x006B, 0x0076, 0x0082, 0x008F, 0x009D, 0x00AD, 0x00BE, 0x00D1,
+    0x00E6, 0x00FD, 0x0117, 0x0133, 0x0151, 0x0173, 0x0198, 0x01C1, 0x01EE, 0x0220, 0x0256, 0x0292,
+    0x02D4, 0x031C, 0x036C, 0x03C3, 0x0424, 0x048E, 0x0502, 0x0583, 0x0610, 0x06AB, 0x0756, 0x0812,
+    0x08E0, 0x09C3, 0x0ABD, 0x0BD0, 0x0CFF, 0x0E4C, 0x0FBA, 0x114C, 0x1307, 0x14EE, 0x1706, 0x1954,
+    0x1BDC, 0x1EA5, 0x21B6, 0x2515, 0x28CA, 0x2CDF, 0x315B, 0x364B, 0x3BB9, 0x41B2, 0x4844, 0x4F7E,
+    0x5771, 0x602F, 0x69CE, 0x7462, 0x7FFF,
+];
+
+#[derive(Clone, Copy, Default, serde::Deserialize, serde::Serialize)]
+pub struct AdpcmState {
+    pub value: i16,
+    pub index: u8,
+}
+
+impl AdpcmState {
+    pub fn from_header(header: u32) -> Self {
+        Self {
+            value: header as i16,
+            index: ((header >> 16) & 0x7F).min(88) as u8,
+        }
+    }
+
+    pub fn decode(&mut self, nibble: u8) {
+        let step = STEP_TABLE[self.index as usize];
+        let mut diff = step / 8;
+        if nibble & 1 != 0 {
+            diff += step / 4;
+        }
+        if nibble & 2 != 0 {
+            diff += step / 2;
+        }
+        if nibble & 4 != 0 {
+            diff += step;
+        }
+
+        let value = self.value as i32;
+        self.value = if nibble & 8 != 0 {
+            (value - diff).max(-0x7FFF)
+        } else {
+            (value + diff).min(0x7FFF)
+        } as i16;
+
+        self.index = (self.index as i32 + INDEX_TABLE[(nibble & 7) as usize]).clamp(0, 88) as u8;
+    }
+}
@@ -0,0 +1,90 @@
+use crate::nds::{
+    bus::{bus7::Bus7, BusTrait},
+    shared::Shared,
+};
+
+use super::models::SndCapCnt;
+
+// records a source into memory at the rate of its partner channel's timer (1 for capture 0, 3 for capture 1)
+// usually paired with that same channel playing the buffer back, for reverb and the like
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct SoundCapture {
+    pub cnt: SndCapCnt, // 0x04000508 + x
+    dad: u32,           // 0x04000510 + x * 8, destination address
+    len: u16,           // 0x04000514 + x * 8, length in words
+
+    timer: u32,
+    position: u32, // in bytes
+}
+
+impl SoundCapture {
+    pub fn read_byte(&self, offset: usize) -> u8 {
+        // the length is write only
+        match offset {
+            0..=3 => self.dad.to_le_bytes()[offset],
+            _ => 0,
+        }
+    }
+
+    pub fn write_byte(&mut self, offset: usize, value: u8) {
+        match offset {
+            0..=3 => {
+                let mut bytes = self.dad.to_le_bytes();
+                bytes[offset] = value;
+                self.dad = u32::from_le_bytes(bytes) & 0x07FFFFFC;
+            }
+            4..=5 => {
+                let mut bytes = self.len.to_le_bytes();
+                bytes[offset - 4] = value;
+                self.len = u16::from_le_bytes(bytes);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn write_cnt(&mut self, value: u8, timer_reload: u16) {
+        let was_started = self.cnt.get_start();
+        self.cnt.set(value);
+
+        if !was_started && self.cnt.get_start() {
+            self.timer = timer_reload as u32;
+            self.position = 0;
+        }
+    }
+
+    // advances by one output sample, like the channels do
+    pub fn clock(&mut self, bus: &mut Bus7, shared: &mut Shared, timer_reload: u16, sample: i32) {
+        if !self.cnt.get_start() {
+            return;
+        }
+
+        let sample = sample.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+        self.timer += 512;
+        while self.timer >= 0x10000 {
+            self.timer = self.timer - 0x10000 + timer_reload as u32;
+            self.write_sample(bus, shared, sample);
+            if !self.cnt.get_start() {
+                break;
+            }
+        }
+    }
+
+    fn write_sample(&mut self, bus: &mut Bus7, shared: &mut Shared, sample: i16) {
+        let addr = self.dad + self.position;
+        if self.cnt.get_pcm8() {
+            bus.write_byte(shared, &mut None, addr, (sample >> 8) as u8);
+            self.position += 1;
+        } else {
+            bus.write_halfword(shared, &mut None, addr, sample as u16);
+            self.position += 2;
+        }
+
+        let end = self.len.max(1) as u32 * 4;
+        if self.position >= end {
+            self.position = 0;
+            if self.cnt.get_one_shot() {
+                self.cnt.set_start(false);
+            }
+        }
+    }
+}
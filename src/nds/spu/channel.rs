@@ -0,0 +1,224 @@
+use crate::nds::{
+    bus::{bus7::Bus7, BusTrait},
+    shared::Shared,
+    Bits,
+};
+
+use super::{
+    adpcm::AdpcmState,
+    models::{SoundFormat, SoundRepeat, SoundXCnt},
+};
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct SoundChannel {
+    index: u8,
+
+    pub cnt: SoundXCnt, // 0x04000400 + x * 0x10
+    sad: u32,           // 0x04000404 + x * 0x10, source address
+    tmr: u16,           // 0x04000408 + x * 0x10, timer reload, counts up at 16.76MHz
+    pnt: u16,           // 0x0400040A + x * 0x10, loop start in words
+    len: u32,           // 0x0400040C + x * 0x10, length after the loop start in words
+
+    timer: u32,
+    position: u32, // in samples, or nibbles for ADPCM
+    sample: i16,
+    adpcm: AdpcmState,
+    adpcm_loop: AdpcmState,
+    noise: u16,
+}
+
+impl SoundChannel {
+    pub fn new(index: u8) -> Self {
+        Self {
+            index,
+            ..Default::default()
+        }
+    }
+
+    pub fn read_byte(&self, offset: usize) -> u8 {
+        // everything except the control register is write only
+        match offset {
+            0..=3 => self.cnt.value().to_le_bytes()[offset],
+            _ => 0,
+        }
+    }
+
+    pub fn write_byte(&mut self, offset: usize, value: u8) {
+        match offset {
+            0..=3 => {
+                let was_started = self.cnt.get_start();
+                let mut bytes = self.cnt.value().to_le_bytes();
+                bytes[offset] = value;
+                self.cnt.set(u32::from_le_bytes(bytes));
+
+                if !was_started && self.cnt.get_start() {
+                    self.start();
+                } else if was_started && !self.cnt.get_start() {
+                    self.stop();
+                }
+            }
+            4..=7 => {
+                let mut bytes = self.sad.to_le_bytes();
+                bytes[offset - 4] = value;
+                self.sad = u32::from_le_bytes(bytes) & 0x07FFFFFC;
+            }
+            8..=9 => {
+                let mut bytes = self.tmr.to_le_bytes();
+                bytes[offset - 8] = value;
+                self.tmr = u16::from_le_bytes(bytes);
+            }
+            10..=11 => {
+                let mut bytes = self.pnt.to_le_bytes();
+                bytes[offset - 10] = value;
+                self.pnt = u16::from_le_bytes(bytes);
+            }
+            _ => {
+                let mut bytes = self.len.to_le_bytes();
+                bytes[offset - 12] = value;
+                self.len = u32::from_le_bytes(bytes) & 0x003FFFFF;
+            }
+        }
+    }
+
+    fn start(&mut self) {
+        self.timer = self.tmr as u32;
+        self.position = 0;
+        self.sample = 0;
+        self.noise = 0x7FFF;
+    }
+
+    fn stop(&mut self) {
+        self.cnt.set_start(false);
+        if !self.cnt.get_hold() {
+            self.sample = 0;
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.cnt.get_start()
+    }
+
+    pub fn get_timer_reload(&self) -> u16 {
+        self.tmr
+    }
+
+    // advances the channel by one output sample, which is 512 ticks of its timer
+    pub fn clock(&mut self, bus: &Bus7, shared: &mut Shared) {
+        if !self.is_running() {
+            return;
+        }
+
+        self.timer += 512;
+        while self.timer >= 0x10000 {
+            self.timer = self.timer - 0x10000 + self.tmr as u32;
+            self.next_sample(bus, shared);
+            if !self.is_running() {
+                break;
+            }
+        }
+    }
+
+    fn next_sample(&mut self, bus: &Bus7, shared: &mut Shared) {
+        let samples_per_word = match self.cnt.get_format() {
+            SoundFormat::Pcm8 => 4,
+            SoundFormat::Pcm16 => 2,
+            SoundFormat::ImaAdpcm => 8,
+            SoundFormat::Psg => {
+                self.next_psg_sample();
+                return;
+            }
+        };
+
+        if self.cnt.get_format() == SoundFormat::ImaAdpcm && self.position == 0 {
+            let header = bus.read_word(shared, &mut None, self.sad);
+            self.adpcm = AdpcmState::from_header(header);
+            self.adpcm_loop = self.adpcm;
+            self.position = 8; // skip the header
+        }
+
+        let loop_start = self.pnt as u32 * samples_per_word;
+        let end = (self.pnt as u32 + self.len) * samples_per_word;
+        if self.position >= end {
+            match self.cnt.get_repeat_mode() {
+                SoundRepeat::OneShot => {
+                    self.stop();
+                    return;
+                }
+                SoundRepeat::Loop => {
+                    self.position = loop_start;
+                    if self.cnt.get_format() == SoundFormat::ImaAdpcm {
+                        // the loop can't start inside the header
+                        self.position = self.position.max(8);
+                        self.adpcm = self.adpcm_loop;
+                    }
+                }
+                // manual never stops by itself, it just keeps reading whatever comes after the sample
+                SoundRepeat::Manual => {}
+            }
+        }
+
+        match self.cnt.get_format() {
+            SoundFormat::Pcm8 => {
+                let value = bus.read_byte(shared, &mut None, self.sad + self.position);
+                self.sample = ((value as i8) as i16) << 8;
+            }
+            SoundFormat::Pcm16 => {
+                let value = bus.read_halfword(shared, &mut None, self.sad + self.position * 2);
+                self.sample = value as i16;
+            }
+            SoundFormat::ImaAdpcm => {
+                if self.position == loop_start {
+                    self.adpcm_loop = self.adpcm;
+                }
+
+                let value = bus.read_byte(shared, &mut None, self.sad + self.position / 2);
+                let nibble = if self.position & 1 == 0 {
+                    value & 0xF
+                } else {
+                    value >> 4
+                };
+                self.adpcm.decode(nibble);
+                self.sample = self.adpcm.value;
+            }
+            SoundFormat::Psg => unreachable!(),
+        }
+
+        self.position += 1;
+    }
+
+    fn next_psg_sample(&mut self) {
+        match self.index {
+            8..=13 => {
+                // 8 steps, the duty decides how many of them are high
+                let step = self.position & 7;
+                self.sample = if 7 - step <= self.cnt.get_wave_duty() {
+                    0x7FFF
+                } else {
+                    -0x7FFF
+                };
+                self.position = self.position.wrapping_add(1);
+            }
+            14..=15 => {
+                if self.noise.get_bit(0) {
+                    self.noise = (self.noise >> 1) ^ 0x6000;
+                    self.sample = -0x7FFF;
+                } else {
+                    self.noise >>= 1;
+                    self.sample = 0x7FFF;
+                }
+            }
+            _ => self.sample = 0,
+        }
+    }
+
+    // the sample after volume, before panning
+    pub fn output(&self) -> i32 {
+        (((self.sample as i32) >> self.cnt.get_volume_shift()) * self.cnt.get_volume_mul()) >> 7
+    }
+
+    pub fn output_stereo(&self) -> (i32, i32) {
+        let sample = self.output();
+        let pan = self.cnt.get_panning();
+        ((sample * (128 - pan)) >> 7, (sample * pan) >> 7)
+    }
+}
@@ -0,0 +1,200 @@
+mod adpcm;
+mod capture;
+mod channel;
+mod models;
+pub mod output;
+
+use std::collections::VecDeque;
+
+use capture::SoundCapture;
+use channel::SoundChannel;
+use models::SoundCnt;
+
+use super::{bus::bus7::Bus7, shared::Shared};
+
+// one sample every 1024 ARM7 cycles, about 32.7kHz
+const CYCLES_PER_SAMPLE: u32 = 1024;
+pub const SAMPLE_RATE: f64 = 33_513_982.0 / CYCLES_PER_SAMPLE as f64;
+
+// about a quarter of a second, if nobody drains it the oldest samples are dropped
+const BUFFER_CAPACITY: usize = 8192;
+
+#[derive(serde::Deserialize, serde::Serialize)]
+pub struct Spu {
+    channels: [SoundChannel; 16],
+    soundcnt: SoundCnt, // 0x04000500
+    soundbias: u16,     // 0x04000504
+    captures: [SoundCapture; 2],
+
+    cycles: u32,
+
+    #[serde(skip)]
+    samples: VecDeque<[i16; 2]>,
+}
+
+impl Default for Spu {
+    fn default() -> Self {
+        Self {
+            channels: std::array::from_fn(|i| SoundChannel::new(i as u8)),
+            soundcnt: SoundCnt::default(),
+            soundbias: 0x200,
+            captures: Default::default(),
+
+            cycles: 0,
+
+            samples: VecDeque::new(),
+        }
+    }
+}
+
+impl Spu {
+    pub fn read_slice<const T: usize>(&self, addr: usize) -> [u8; T] {
+        std::array::from_fn(|i| self.read_byte(addr + i))
+    }
+
+    fn read_byte(&self, addr: usize) -> u8 {
+        match addr {
+            0x04000400..=0x040004FF => self.channels[(addr >> 4) & 0xF].read_byte(addr & 0xF),
+            0x04000500..=0x04000501 => self.soundcnt.value().to_le_bytes()[addr & 1],
+            0x04000504..=0x04000505 => self.soundbias.to_le_bytes()[addr & 1],
+            0x04000508 => self.captures[0].cnt.value(),
+            0x04000509 => self.captures[1].cnt.value(),
+            0x04000510..=0x04000517 => self.captures[0].read_byte(addr - 0x04000510),
+            0x04000518..=0x0400051F => self.captures[1].read_byte(addr - 0x04000518),
+            _ => 0,
+        }
+    }
+
+    pub fn write_slice<const T: usize>(&mut self, addr: usize, value: [u8; T]) {
+        for (i, byte) in value.into_iter().enumerate() {
+            self.write_byte(addr + i, byte);
+        }
+    }
+
+    fn write_byte(&mut self, addr: usize, value: u8) {
+        match addr {
+            0x04000400..=0x040004FF => {
+                self.channels[(addr >> 4) & 0xF].write_byte(addr & 0xF, value)
+            }
+            0x04000500..=0x04000501 => {
+                let mut bytes = self.soundcnt.value().to_le_bytes();
+                bytes[addr & 1] = value;
+                self.soundcnt.set(u16::from_le_bytes(bytes));
+            }
+            0x04000504..=0x04000505 => {
+                let mut bytes = self.soundbias.to_le_bytes();
+                bytes[addr & 1] = value;
+                self.soundbias = u16::from_le_bytes(bytes) & 0x3FF;
+            }
+            0x04000508 => {
+                let timer_reload = self.channels[1].get_timer_reload();
+                self.captures[0].write_cnt(value, timer_reload);
+            }
+            0x04000509 => {
+                let timer_reload = self.channels[3].get_timer_reload();
+                self.captures[1].write_cnt(value, timer_reload);
+            }
+            0x04000510..=0x04000517 => self.captures[0].write_byte(addr - 0x04000510, value),
+            0x04000518..=0x0400051F => self.captures[1].write_byte(addr - 0x04000518, value),
+            _ => {}
+        }
+    }
+
+    // returns true once enough cycles have passed for the next sample
+    // the samples themselves are generated separately, as the channels need to read from the bus
+    pub fn clock(&mut self, cycles: u32) -> bool {
+        self.cycles += cycles;
+        self.cycles >= CYCLES_PER_SAMPLE
+    }
+
+    // the capture units write their samples back through the bus
+    pub fn generate_samples(&mut self, bus: &mut Bus7, shared: &mut Shared) {
+        while self.cycles >= CYCLES_PER_SAMPLE {
+            self.cycles -= CYCLES_PER_SAMPLE;
+
+            let sample = self.generate_sample(bus, shared);
+            if self.samples.len() == BUFFER_CAPACITY {
+                self.samples.pop_front();
+            }
+            self.samples.push_back(sample);
+        }
+    }
+
+    pub fn drain_samples(&mut self) -> impl Iterator<Item = [i16; 2]> + '_ {
+        self.samples.drain(..)
+    }
+
+    fn generate_sample(&mut self, bus: &mut Bus7, shared: &mut Shared) -> [i16; 2] {
+        if !self.soundcnt.get_master_enable() {
+            return [0, 0];
+        }
+
+        for channel in self.channels.iter_mut() {
+            channel.clock(bus, shared);
+        }
+
+        let mut outputs: [(i32, i32); 16] =
+            std::array::from_fn(|i| self.channels[i].output_stereo());
+        for (capture, (source, target)) in self.captures.iter().zip([(0, 1), (2, 3)]) {
+            if capture.cnt.get_start() && capture.cnt.get_add() {
+                outputs[target].0 += outputs[source].0;
+                outputs[target].1 += outputs[source].1;
+            }
+        }
+
+        let mut mixer = (0, 0);
+        for (i, output) in outputs.iter().enumerate() {
+            if (i == 1 && self.soundcnt.get_skip_channel1())
+                || (i == 3 && self.soundcnt.get_skip_channel3())
+            {
+                continue;
+            }
+            mixer.0 += output.0;
+            mixer.1 += output.1;
+        }
+
+        let capture_sources = [
+            if self.captures[0].cnt.get_source() {
+                self.channels[0].output()
+            } else {
+                mixer.0
+            },
+            if self.captures[1].cnt.get_source() {
+                self.channels[2].output()
+            } else {
+                mixer.1
+            },
+        ];
+        let timer_reloads = [
+            self.channels[1].get_timer_reload(),
+            self.channels[3].get_timer_reload(),
+        ];
+        for (i, capture) in self.captures.iter_mut().enumerate() {
+            capture.clock(bus, shared, timer_reloads[i], capture_sources[i]);
+        }
+
+        let left = match self.soundcnt.get_left_output() {
+            0 => mixer.0,
+            1 => outputs[1].0,
+            2 => outputs[3].0,
+            _ => outputs[1].0 + outputs[3].0,
+        };
+        let right = match self.soundcnt.get_right_output() {
+            0 => mixer.1,
+            1 => outputs[1].1,
+            2 => outputs[3].1,
+            _ => outputs[1].1 + outputs[3].1,
+        };
+
+        [self.master_output(left), self.master_output(right)]
+    }
+
+    // the DAC is 10 bits around SOUNDBIAS, which gets taken back out as real speakers never see it
+    fn master_output(&self, sample: i32) -> i16 {
+        let sample = (sample * self.soundcnt.get_master_volume()) >> 7;
+        let bias = self.soundbias as i32;
+        let dac = ((sample >> 6) + bias).clamp(0, 0x3FF);
+
+        ((dac - bias) << 6).clamp(i16::MIN as i32, i16::MAX as i32) as i16
+    }
+}
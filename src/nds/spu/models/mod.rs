@@ -0,0 +1,7 @@
+mod sndcapcnt;
+mod soundcnt;
+mod soundxcnt;
+
+pub use sndcapcnt::SndCapCnt;
+pub use soundcnt::SoundCnt;
+pub use soundxcnt::{SoundFormat, SoundRepeat, SoundXCnt};
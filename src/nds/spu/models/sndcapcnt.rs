@@ -0,0 +1,48 @@
+use crate::nds::Bits;
+
+// 0x04000508, 0x04000509
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct SndCapCnt(u8);
+
+impl SndCapCnt {
+    const ADD_OFFSET: u8 = 0;
+    const SOURCE_OFFSET: u8 = 1;
+    const ONE_SHOT_OFFSET: u8 = 2;
+    const PCM8_OFFSET: u8 = 3;
+    const START_OFFSET: u8 = 7;
+
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+
+    pub fn set(&mut self, value: u8) {
+        self.0 = value & 0x8F;
+    }
+
+    // capture 0 adds channel 0 to channel 1's output, capture 1 adds channel 2 to channel 3's
+    pub fn get_add(&self) -> bool {
+        self.0.get_bit(Self::ADD_OFFSET)
+    }
+
+    // false: the mixer (left for capture 0, right for capture 1), true: channel 0 or 2
+    pub fn get_source(&self) -> bool {
+        self.0.get_bit(Self::SOURCE_OFFSET)
+    }
+
+    pub fn get_one_shot(&self) -> bool {
+        self.0.get_bit(Self::ONE_SHOT_OFFSET)
+    }
+
+    // false: PCM16, true: PCM8
+    pub fn get_pcm8(&self) -> bool {
+        self.0.get_bit(Self::PCM8_OFFSET)
+    }
+
+    pub fn get_start(&self) -> bool {
+        self.0.get_bit(Self::START_OFFSET)
+    }
+
+    pub fn set_start(&mut self, value: bool) {
+        self.0.set_bit(Self::START_OFFSET, value);
+    }
+}
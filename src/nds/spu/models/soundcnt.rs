@@ -0,0 +1,54 @@
+use crate::nds::Bits;
+
+// 0x04000500, master control
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct SoundCnt(u16);
+
+impl SoundCnt {
+    const MASTER_VOLUME_START: u16 = 0;
+    const MASTER_VOLUME_END: u16 = 6;
+    const LEFT_OUTPUT_START: u16 = 8;
+    const LEFT_OUTPUT_END: u16 = 9;
+    const RIGHT_OUTPUT_START: u16 = 10;
+    const RIGHT_OUTPUT_END: u16 = 11;
+    const SKIP_CHANNEL1_OFFSET: u16 = 12;
+    const SKIP_CHANNEL3_OFFSET: u16 = 13;
+    const MASTER_ENABLE_OFFSET: u16 = 15;
+
+    pub fn value(&self) -> u16 {
+        self.0
+    }
+
+    pub fn set(&mut self, value: u16) {
+        self.0 = value & 0xBF7F;
+    }
+
+    pub fn get_master_volume(&self) -> i32 {
+        self.0
+            .get_bits(Self::MASTER_VOLUME_START, Self::MASTER_VOLUME_END) as i32
+    }
+
+    // 0: mixer, 1: channel 1, 2: channel 3, 3: channel 1+3
+    pub fn get_left_output(&self) -> u16 {
+        self.0
+            .get_bits(Self::LEFT_OUTPUT_START, Self::LEFT_OUTPUT_END)
+    }
+
+    pub fn get_right_output(&self) -> u16 {
+        self.0
+            .get_bits(Self::RIGHT_OUTPUT_START, Self::RIGHT_OUTPUT_END)
+    }
+
+    // channels 1 and 3 can be kept out of the mixer, usually so they can be captured on their own
+    pub fn get_skip_channel1(&self) -> bool {
+        self.0.get_bit(Self::SKIP_CHANNEL1_OFFSET)
+    }
+
+    pub fn get_skip_channel3(&self) -> bool {
+        self.0.get_bit(Self::SKIP_CHANNEL3_OFFSET)
+    }
+
+    pub fn get_master_enable(&self) -> bool {
+        self.0.get_bit(Self::MASTER_ENABLE_OFFSET)
+    }
+}
@@ -0,0 +1,105 @@
+use crate::nds::Bits;
+
+#[derive(PartialEq)]
+pub enum SoundFormat {
+    Pcm8,
+    Pcm16,
+    ImaAdpcm,
+    Psg, // PSG on channels 8-13, noise on 14-15, nothing on the rest
+}
+
+#[derive(PartialEq)]
+pub enum SoundRepeat {
+    Manual,
+    Loop,
+    OneShot,
+}
+
+// 0x04000400 + x * 0x10
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct SoundXCnt(u32);
+
+impl SoundXCnt {
+    const VOLUME_MUL_START: u32 = 0;
+    const VOLUME_MUL_END: u32 = 6;
+    const VOLUME_DIV_START: u32 = 8;
+    const VOLUME_DIV_END: u32 = 9;
+    const HOLD_OFFSET: u32 = 15;
+    const PANNING_START: u32 = 16;
+    const PANNING_END: u32 = 22;
+    const WAVE_DUTY_START: u32 = 24;
+    const WAVE_DUTY_END: u32 = 26;
+    const REPEAT_MODE_START: u32 = 27;
+    const REPEAT_MODE_END: u32 = 28;
+    const FORMAT_START: u32 = 29;
+    const FORMAT_END: u32 = 30;
+    const START_OFFSET: u32 = 31;
+
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+
+    pub fn set(&mut self, value: u32) {
+        self.0 = value & 0xFF7F837F;
+    }
+
+    pub fn get_volume_mul(&self) -> i32 {
+        self.0
+            .get_bits(Self::VOLUME_MUL_START, Self::VOLUME_MUL_END) as i32
+    }
+
+    // how far the sample is shifted right before the volume is applied
+    pub fn get_volume_shift(&self) -> u32 {
+        match self
+            .0
+            .get_bits(Self::VOLUME_DIV_START, Self::VOLUME_DIV_END)
+        {
+            0 => 0,
+            1 => 1,
+            2 => 2,
+            _ => 4,
+        }
+    }
+
+    // keep outputting the last sample once a one-shot ends
+    pub fn get_hold(&self) -> bool {
+        self.0.get_bit(Self::HOLD_OFFSET)
+    }
+
+    // 0: left, 64: center, 127: right
+    pub fn get_panning(&self) -> i32 {
+        self.0.get_bits(Self::PANNING_START, Self::PANNING_END) as i32
+    }
+
+    pub fn get_wave_duty(&self) -> u32 {
+        self.0.get_bits(Self::WAVE_DUTY_START, Self::WAVE_DUTY_END)
+    }
+
+    pub fn get_repeat_mode(&self) -> SoundRepeat {
+        match self
+            .0
+            .get_bits(Self::REPEAT_MODE_START, Self::REPEAT_MODE_END)
+        {
+            1 => SoundRepeat::Loop,
+            2 => SoundRepeat::OneShot,
+            _ => SoundRepeat::Manual, // 3 is prohibited
+        }
+    }
+
+    pub fn get_format(&self) -> SoundFormat {
+        match self.0.get_bits(Self::FORMAT_START, Self::FORMAT_END) {
+            0 => SoundFormat::Pcm8,
+            1 => SoundFormat::Pcm16,
+            2 => SoundFormat::ImaAdpcm,
+            _ => SoundFormat::Psg,
+        }
+    }
+
+    pub fn get_start(&self) -> bool {
+        self.0.get_bit(Self::START_OFFSET)
+    }
+
+    pub fn set_start(&mut self, value: bool) {
+        self.0.set_bit(Self::START_OFFSET, value);
+    }
+}
@@ -0,0 +1,20 @@
+mod null;
+#[cfg(not(target_arch = "wasm32"))]
+mod wav;
+
+pub use null::NullSink;
+#[cfg(not(target_arch = "wasm32"))]
+pub use wav::WavWriter;
+
+// what the SPU's mix gets resampled to for playback
+pub const HOST_SAMPLE_RATE: u32 = 48000;
+
+// somewhere for the SPU's mix to go, the UI plays it and headless runs can record it
+pub trait AudioSink {
+    fn sample_rate(&self) -> u32;
+    // how many samples are waiting to be played, None if the sink doesn't play in real time
+    // only the UI plays anything in real time
+    #[cfg(feature = "ui")]
+    fn buffered(&self) -> Option<usize>;
+    fn write(&mut self, samples: &[[i16; 2]]);
+}
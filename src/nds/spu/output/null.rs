@@ -0,0 +1,17 @@
+use super::{AudioSink, HOST_SAMPLE_RATE};
+
+// throws everything away, for headless runs or when there's nothing to play through
+pub struct NullSink;
+
+impl AudioSink for NullSink {
+    fn sample_rate(&self) -> u32 {
+        HOST_SAMPLE_RATE
+    }
+
+    #[cfg(feature = "ui")]
+    fn buffered(&self) -> Option<usize> {
+        None
+    }
+
+    fn write(&mut self, _samples: &[[i16; 2]]) {}
+}
@@ -0,0 +1,100 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Seek, SeekFrom, Write},
+};
+
+use crate::nds::logger;
+
+use super::AudioSink;
+
+// 16 bit stereo PCM, the sizes in the header are filled in when it's finished
+pub struct WavWriter {
+    file: BufWriter<File>,
+    sample_rate: u32,
+    frames: u32,
+    failed: bool,
+}
+
+impl WavWriter {
+    pub fn create(path: &str, sample_rate: u32) -> std::io::Result<Self> {
+        let mut writer = Self {
+            file: BufWriter::new(File::create(path)?),
+            sample_rate,
+            frames: 0,
+            failed: false,
+        };
+        writer.write_header()?;
+        Ok(writer)
+    }
+
+    fn write_header(&mut self) -> std::io::Result<()> {
+        let data_size = self.frames * 4;
+
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(b"RIFF")?;
+        self.file.write_all(&(36 + data_size).to_le_bytes())?;
+        self.file.write_all(b"WAVE")?;
+
+        self.file.write_all(b"fmt ")?;
+        self.file.write_all(&16u32.to_le_bytes())?;
+        self.file.write_all(&1u16.to_le_bytes())?; // PCM
+        self.file.write_all(&2u16.to_le_bytes())?; // channels
+        self.file.write_all(&self.sample_rate.to_le_bytes())?;
+        self.file.write_all(&(self.sample_rate * 4).to_le_bytes())?; // bytes per second
+        self.file.write_all(&4u16.to_le_bytes())?; // bytes per frame
+        self.file.write_all(&16u16.to_le_bytes())?; // bits per sample
+
+        self.file.write_all(b"data")?;
+        self.file.write_all(&data_size.to_le_bytes())?;
+        self.file.seek(SeekFrom::End(0))?;
+        Ok(())
+    }
+
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        self.write_header()?;
+        self.file.flush()
+    }
+}
+
+impl AudioSink for WavWriter {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[cfg(feature = "ui")]
+    fn buffered(&self) -> Option<usize> {
+        None
+    }
+
+    fn write(&mut self, samples: &[[i16; 2]]) {
+        if self.failed {
+            return;
+        }
+
+        let result = samples.iter().try_for_each(|sample| {
+            self.file.write_all(&sample[0].to_le_bytes())?;
+            self.file.write_all(&sample[1].to_le_bytes())
+        });
+        match result {
+            Ok(_) => self.frames += samples.len() as u32,
+            Err(e) => {
+                self.failed = true;
+                logger::error(
+                    logger::LogSource::Emu,
+                    format!("Failed to write audio recording: {}", e),
+                );
+            }
+        }
+    }
+}
+
+impl Drop for WavWriter {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            logger::error(
+                logger::LogSource::Emu,
+                format!("Failed to finish audio recording: {}", e),
+            );
+        }
+    }
+}
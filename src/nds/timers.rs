@@ -69,16 +69,6 @@ impl Timer {
         self.control.0
     }
 
-    pub fn set(&mut self, value: u32) {
-        let old_prescaler = self.control.get_prescaler();
-        let old_enable = self.control.get_timer_operating();
-
-        self.reload = value as u16;
-        self.control.set(value.get_bits(16, 31) as u16);
-
-        self.cnt_updated(old_prescaler, old_enable);
-    }
-
     pub fn set_l(&mut self, value: u16) {
         self.reload = value;
     }
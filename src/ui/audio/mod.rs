@@ -0,0 +1,154 @@
+#[cfg(not(target_arch = "wasm32"))]
+mod native;
+mod resampler;
+
+use std::{
+    fmt::Display,
+    sync::mpsc::{channel, Receiver, Sender},
+};
+
+use resampler::Resampler;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::nds::logger;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::nds::spu::output::WavWriter;
+use crate::nds::spu::{
+    self,
+    output::{AudioSink, NullSink},
+};
+
+// how far the playback rate can be nudged to keep the latency on target, 0.5% isn't audible
+const MAX_RATE_ADJUSTMENT: f64 = 0.005;
+
+#[derive(Clone, Copy, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum AudioBackend {
+    #[default]
+    Native,
+    Null,
+}
+
+impl AudioBackend {
+    pub const ALL: [AudioBackend; 2] = [AudioBackend::Native, AudioBackend::Null];
+
+    fn create_sink(&self) -> Box<dyn AudioSink> {
+        match self {
+            #[cfg(not(target_arch = "wasm32"))]
+            AudioBackend::Native => Box::new(native::NativeSink::new()),
+            #[cfg(target_arch = "wasm32")]
+            AudioBackend::Native => Box::new(NullSink),
+            AudioBackend::Null => Box::new(NullSink),
+        }
+    }
+}
+
+impl Display for AudioBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AudioBackend::Native => write!(f, "Native"),
+            AudioBackend::Null => write!(f, "None"),
+        }
+    }
+}
+
+pub struct AudioOutput {
+    backend: Option<AudioBackend>, // None until the first update creates the sink
+    sink: Box<dyn AudioSink>,
+    resampler: Resampler,
+
+    // recordings get the SPU's output untouched, so they're the same every run
+    #[cfg(not(target_arch = "wasm32"))]
+    recording: Option<WavWriter>,
+    pub record_channel: (Sender<String>, Receiver<String>),
+
+    input: Vec<[i16; 2]>,
+    output: Vec<[i16; 2]>,
+}
+
+impl Default for AudioOutput {
+    fn default() -> Self {
+        Self {
+            backend: None,
+            sink: Box::new(NullSink),
+            resampler: Resampler::default(),
+
+            #[cfg(not(target_arch = "wasm32"))]
+            recording: None,
+            record_channel: channel(),
+
+            input: Vec::new(),
+            output: Vec::new(),
+        }
+    }
+}
+
+impl AudioOutput {
+    // called once a frame, after the emulator has run
+    pub fn update(&mut self, spu: &mut spu::Spu, backend: AudioBackend, latency_ms: u32) {
+        if self.backend != Some(backend) {
+            self.backend = Some(backend);
+            self.sink = backend.create_sink();
+            self.resampler = Resampler::default();
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Ok(path) = self.record_channel.1.try_recv() {
+            self.start_recording(&path);
+        }
+
+        self.input.clear();
+        self.input.extend(spu.drain_samples());
+        if self.input.is_empty() {
+            return;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(recording) = &mut self.recording {
+            recording.write(&self.input);
+        }
+
+        let sample_rate = self.sink.sample_rate() as f64;
+        let mut ratio = sample_rate / spu::SAMPLE_RATE;
+        if let Some(buffered) = self.sink.buffered() {
+            // speed up a touch when we're running low, slow down when too much has piled up
+            let target = latency_ms as f64 / 1000.0 * sample_rate;
+            let error = 1.0 - buffered as f64 / target;
+            ratio *= 1.0
+                + (error * MAX_RATE_ADJUSTMENT).clamp(-MAX_RATE_ADJUSTMENT, MAX_RATE_ADJUSTMENT);
+        }
+
+        self.output.clear();
+        self.resampler.process(&self.input, ratio, &mut self.output);
+        self.sink.write(&self.output);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start_recording(&mut self, path: &str) {
+        let sample_rate = spu::SAMPLE_RATE.round() as u32;
+        match WavWriter::create(path, sample_rate) {
+            Ok(writer) => {
+                logger::info(
+                    logger::LogSource::Emu,
+                    format!("Recording audio to {}", path),
+                );
+                self.recording = Some(writer);
+            }
+            Err(e) => logger::error(
+                logger::LogSource::Emu,
+                format!("Failed to start recording audio to {}: {}", path, e),
+            ),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn stop_recording(&mut self) {
+        if self.recording.take().is_some() {
+            logger::info(logger::LogSource::Emu, "Stopped recording audio");
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+}
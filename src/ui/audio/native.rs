@@ -0,0 +1,132 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+use crate::nds::{
+    logger,
+    spu::output::{AudioSink, HOST_SAMPLE_RATE},
+};
+
+// more than this waiting to be played means we're running way ahead, the oldest samples get dropped
+const MAX_QUEUED_SECONDS: usize = 1;
+
+// plays through the system's default output device
+// the device pulls samples out of the queue from its own thread whenever it needs more
+pub struct NativeSink {
+    stream: Option<cpal::Stream>,
+    queue: Arc<Mutex<VecDeque<[i16; 2]>>>,
+    sample_rate: u32,
+}
+
+impl NativeSink {
+    pub fn new() -> Self {
+        let queue = Arc::new(Mutex::new(VecDeque::new()));
+        match Self::open_stream(&queue) {
+            Ok((stream, sample_rate)) => Self {
+                stream: Some(stream),
+                queue,
+                sample_rate,
+            },
+            Err(e) => {
+                logger::warn_once(
+                    logger::LogSource::Emu,
+                    format!("Couldn't open an audio device, audio is disabled: {}", e),
+                );
+                Self {
+                    stream: None,
+                    queue,
+                    sample_rate: HOST_SAMPLE_RATE,
+                }
+            }
+        }
+    }
+
+    fn open_stream(queue: &Arc<Mutex<VecDeque<[i16; 2]>>>) -> Result<(cpal::Stream, u32), String> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or("there's no output device")?;
+        let supported = device.default_output_config().map_err(|e| e.to_string())?;
+        let sample_format = supported.sample_format();
+        let config = supported.config();
+
+        let stream = match sample_format {
+            cpal::SampleFormat::F32 => Self::build_stream::<f32>(&device, &config, queue),
+            cpal::SampleFormat::I16 => Self::build_stream::<i16>(&device, &config, queue),
+            cpal::SampleFormat::U16 => Self::build_stream::<u16>(&device, &config, queue),
+            cpal::SampleFormat::I32 => Self::build_stream::<i32>(&device, &config, queue),
+            format => return Err(format!("unsupported sample format {}", format)),
+        }
+        .map_err(|e| e.to_string())?;
+        stream.play().map_err(|e| e.to_string())?;
+
+        logger::info(
+            logger::LogSource::Emu,
+            format!(
+                "Playing audio through {} at {}Hz",
+                device.name().unwrap_or_default(),
+                config.sample_rate.0
+            ),
+        );
+        Ok((stream, config.sample_rate.0))
+    }
+
+    fn build_stream<T: cpal::SizedSample + cpal::FromSample<i16>>(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        queue: &Arc<Mutex<VecDeque<[i16; 2]>>>,
+    ) -> Result<cpal::Stream, cpal::BuildStreamError> {
+        let channels = config.channels as usize;
+        let queue = queue.clone();
+        device.build_output_stream(
+            config,
+            move |data: &mut [T], _| {
+                let mut queue = queue.lock().unwrap();
+                for frame in data.chunks_mut(channels) {
+                    // running dry plays silence until there's more
+                    let [l, r] = queue.pop_front().unwrap_or_default();
+                    for (channel, sample) in frame.iter_mut().enumerate() {
+                        let value = match (channels, channel) {
+                            (1, _) => ((l as i32 + r as i32) / 2) as i16,
+                            (_, 0) => l,
+                            (_, 1) => r,
+                            _ => 0,
+                        };
+                        *sample = T::from_sample(value);
+                    }
+                }
+            },
+            |e| logger::error(logger::LogSource::Emu, format!("Audio stream error: {}", e)),
+            None,
+        )
+    }
+}
+
+impl AudioSink for NativeSink {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    // whatever the device hasn't pulled out of the queue yet
+    fn buffered(&self) -> Option<usize> {
+        self.stream.as_ref()?;
+        Some(self.queue.lock().unwrap().len())
+    }
+
+    fn write(&mut self, samples: &[[i16; 2]]) {
+        if self.stream.is_none() {
+            return;
+        }
+
+        let mut queue = self.queue.lock().unwrap();
+        queue.extend(samples);
+
+        let max_len = self.sample_rate as usize * MAX_QUEUED_SECONDS;
+        if queue.len() > max_len {
+            let excess = queue.len() - max_len;
+            queue.drain(..excess);
+        }
+    }
+}
@@ -0,0 +1,26 @@
+// linear interpolation, plenty for the DS's 32.7kHz output
+#[derive(Default)]
+pub struct Resampler {
+    position: f64, // how far we are between the previous sample and the next one
+    previous: [i16; 2],
+}
+
+impl Resampler {
+    // ratio is output samples per input sample
+    pub fn process(&mut self, input: &[[i16; 2]], ratio: f64, output: &mut Vec<[i16; 2]>) {
+        let step = 1.0 / ratio;
+        for &sample in input {
+            while self.position < 1.0 {
+                output.push(lerp(self.previous, sample, self.position));
+                self.position += step;
+            }
+            self.position -= 1.0;
+            self.previous = sample;
+        }
+    }
+}
+
+fn lerp(a: [i16; 2], b: [i16; 2], t: f64) -> [i16; 2] {
+    let lerp = |a: i16, b: i16| (a as f64 + (b as f64 - a as f64) * t) as i16;
+    [lerp(a[0], b[0]), lerp(a[1], b[1])]
+}
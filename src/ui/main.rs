@@ -9,6 +9,7 @@ use crate::nds::{
 };
 
 use super::{
+    audio::AudioOutput,
     screens::ScreenOptions,
     windows::{
         debug::{
@@ -32,18 +33,22 @@ use super::{
 
 // this file needs a small clean
 
+// rom bytes and the path they were loaded from, if there is one
+type LoadRom = (Vec<u8>, Option<String>);
+
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)]
 pub struct NitrousGUI {
     #[serde(skip)]
     is_first_run: bool,
-    pub mode_safety: bool, // gpu option
 
     #[serde(skip)]
     pub emulator: Emulator,
+    #[serde(skip)]
+    pub audio: AudioOutput,
 
     #[serde(skip)]
-    pub load_rom_channel: (Sender<Vec<u8>>, Receiver<Vec<u8>>),
+    pub load_rom_channel: (Sender<LoadRom>, Receiver<LoadRom>),
     #[serde(skip)]
     pub load_state_channel: (Sender<Emulator>, Receiver<Emulator>),
 
@@ -94,6 +99,7 @@ impl Default for NitrousGUI {
             is_first_run: true,
 
             emulator: Emulator::default(),
+            audio: AudioOutput::default(),
 
             load_rom_channel: channel(),
             load_state_channel: channel(),
@@ -122,8 +128,6 @@ impl Default for NitrousGUI {
 
             fps_info: FpsInfoWindow::default(),
 
-            mode_safety: false,
-
             last_cycle_count: 0,
             last_frame_cycles_execution_time: Duration::ZERO,
             last_cycle_arm7_discrepency: 0,
@@ -153,6 +157,12 @@ impl eframe::App for NitrousGUI {
         eframe::set_value(storage, eframe::APP_KEY, self);
     }
 
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.emulator.shared.cart.backup.flush();
+        #[cfg(not(target_arch = "wasm32"))]
+        self.audio.stop_recording();
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         let idle_time = self.last_end_instant.elapsed();
 
@@ -160,9 +170,8 @@ impl eframe::App for NitrousGUI {
 
         self.handle_input(ctx);
 
-        // TODO: don't have this here or anywhere idk
-        self.emulator.shared.gpus.a.mode_safety = self.mode_safety;
-        self.emulator.shared.gpus.b.mode_safety = self.mode_safety;
+        self.emulator.firmware_boot = self.preferences.firmware_boot;
+        self.emulator.bus7.rtc.settings = self.preferences.rtc_settings();
 
         self.fps_info.fps_counter.push_current_time();
         let measured_fps = self.fps_info.fps_counter.average_fps();
@@ -194,7 +203,7 @@ impl eframe::App for NitrousGUI {
         let (cycles_ran_arm9, cycles_ran_arm7, cycles_ran_gpu) = self.emulator.run_for(
             target_cycles_arm9,
             self.last_cycle_arm7_discrepency,
-            (&mut self.arm9_disassembler, &mut self.arm7_disassembler),
+            &mut (&mut self.arm9_disassembler, &mut self.arm7_disassembler),
         );
 
         let arm7_discrepency = cycles_ran_arm7 - (cycles_ran_arm9 / 2) as i32;
@@ -203,6 +212,13 @@ impl eframe::App for NitrousGUI {
         self.last_cycle_count = cycles_ran_arm9;
         self.last_frame_cycles_execution_time = frame_cycles_start.elapsed();
 
+        // the frame pacing above decides how much audio there is, the audio output smooths out the rest
+        self.audio.update(
+            &mut self.emulator.bus7.spu,
+            self.preferences.audio_backend,
+            self.preferences.audio_latency,
+        );
+
         let emulation_time = emulation_start_time.elapsed();
 
         let ui_start_time = Instant::now();
@@ -252,7 +268,7 @@ impl eframe::App for NitrousGUI {
         self.test_window.show(ctx);
 
         // File
-        self.preferences.show(ctx);
+        self.preferences.show(&mut self.emulator, ctx);
 
         if !self.emulator.is_running() {
             // do slow stuff if idle
@@ -267,8 +283,9 @@ impl eframe::App for NitrousGUI {
                 self.preferences.try_load_firmware(&mut self.emulator.bus7);
             }
 
-            if let Ok(bytes) = self.load_rom_channel.1.try_recv() {
-                self.emulator.load_rom(bytes);
+            if let Ok((bytes, path)) = self.load_rom_channel.1.try_recv() {
+                self.emulator.load_rom(bytes, path);
+                self.preferences.apply_backup_override(&mut self.emulator);
             }
 
             if let Ok(emulator) = self.load_state_channel.1.try_recv() {
@@ -1,6 +1,6 @@
 use std::io::{Read, Write};
 
-use crate::nds::logger;
+use crate::nds::{gpus::framebuffer::ColourOutput, logger};
 
 use super::{NitrousGUI, NitrousUI};
 
@@ -71,8 +71,13 @@ impl NitrousGUI {
             execute(async move {
                 let file = task.await;
                 if let Some(file) = file {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    let path = Some(file.path().to_string_lossy().to_string());
+                    #[cfg(target_arch = "wasm32")]
+                    let path = None;
+
                     let bytes = file.read().await;
-                    let _result = sender.send(bytes);
+                    let _result = sender.send((bytes, path));
                     ctx.request_repaint();
                 }
             });
@@ -187,6 +192,32 @@ impl NitrousGUI {
             return true;
         }
 
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            ui.separator();
+
+            if self.audio.is_recording() {
+                if ui.button("Stop recording audio").clicked() {
+                    self.audio.stop_recording();
+                    return true;
+                }
+            } else if ui.button("Record audio").clicked() {
+                let sender = self.audio.record_channel.0.clone();
+
+                let task = rfd::AsyncFileDialog::new()
+                    .add_filter("WAV", &["wav"])
+                    .save_file();
+
+                execute(async move {
+                    let file = task.await;
+                    if let Some(file) = file {
+                        let _result = sender.send(file.path().to_string_lossy().to_string());
+                    }
+                });
+                return true;
+            }
+        }
+
         ui.separator();
 
         ui.checkbox(&mut self.preferences.open, "Preferences");
@@ -269,6 +300,25 @@ impl NitrousGUI {
                 "Right",
             );
         });
+        ui.menu_button("Colours", |ui| {
+            ui.set_width(100.0);
+
+            ui.selectable_value(
+                &mut self.screen_options.colour_output,
+                ColourOutput::Raw,
+                "Raw",
+            );
+            ui.selectable_value(
+                &mut self.screen_options.colour_output,
+                ColourOutput::Accurate,
+                "Accurate",
+            );
+            ui.selectable_value(
+                &mut self.screen_options.colour_output,
+                ColourOutput::LcdCorrected,
+                "LCD colours",
+            );
+        });
 
         false
     }
@@ -322,10 +372,6 @@ impl NitrousGUI {
                 ui.checkbox(&mut objs[2], "OBJs 2");
                 ui.checkbox(&mut objs[3], "OBJs 3");
             });
-
-            ui.checkbox(&mut self.mode_safety, "Mode Safety");
-            self.emulator.shared.gpus.a.mode_safety = self.mode_safety;
-            self.emulator.shared.gpus.b.mode_safety = self.mode_safety;
         });
         ui.checkbox(&mut self.benchmark.open, "Benchmark");
         ui.checkbox(&mut self.emulation_log.open, "Emulation Log");
@@ -1,4 +1,7 @@
-use crate::nds::logger::{self, format_debug};
+use crate::nds::{
+    gpus::framebuffer::{ColourOutput, Framebuffer},
+    logger::{self, format_debug},
+};
 
 use super::{NitrousGUI, NitrousWindow};
 
@@ -9,6 +12,7 @@ pub struct ScreenOptions {
     pub scale: f32,
     pub scale_text: String,
     pub horizontal_alignment: egui::Align,
+    pub colour_output: ColourOutput,
 
     pub top_screens: Vec<u32>,
     pub top_screen_count: u32,
@@ -25,6 +29,7 @@ impl Default for ScreenOptions {
             scale: 1.0,
             scale_text: "1".to_string(),
             horizontal_alignment: egui::Align::Center,
+            colour_output: ColourOutput::default(),
 
             top_screens: Vec::new(),
             top_screen_count: 0,
@@ -38,35 +43,26 @@ impl Default for ScreenOptions {
 
 impl NitrousGUI {
     pub fn render_screens(&mut self, ctx: &egui::Context) {
-        let engine_a_result = self.emulator.shared.gpus.a.render(&self.emulator.shared);
-        let engine_b_result = self.emulator.shared.gpus.b.render(&self.emulator.shared);
-        let (top_texture, bot_texture) = if self.emulator.shared.powcnt1.get_display_swap() {
-            (
-                ctx.load_texture(
-                    "top_screen",
-                    engine_a_result.image_data,
-                    egui::TextureOptions::NEAREST,
-                ),
-                ctx.load_texture(
-                    "bot_screen",
-                    engine_b_result.image_data,
-                    egui::TextureOptions::NEAREST,
-                ),
-            )
-        } else {
-            (
-                ctx.load_texture(
-                    "top_screen",
-                    engine_b_result.image_data,
-                    egui::TextureOptions::NEAREST,
-                ),
-                ctx.load_texture(
-                    "bot_screen",
-                    engine_a_result.image_data,
-                    egui::TextureOptions::NEAREST,
-                ),
+        let shared = &self.emulator.shared;
+        let colour_output = self.screen_options.colour_output;
+        let to_image = |framebuffer: &Framebuffer| {
+            egui::ColorImage::from_rgba_unmultiplied(
+                [Framebuffer::WIDTH, Framebuffer::HEIGHT],
+                &framebuffer.to_rgba(colour_output),
             )
         };
+        let top_texture = ctx.load_texture(
+            "top_screen",
+            to_image(shared.gpus.top_screen(&shared.powcnt1)),
+            egui::TextureOptions::NEAREST,
+        );
+        let bot_texture = ctx.load_texture(
+            "bot_screen",
+            to_image(shared.gpus.bottom_screen(&shared.powcnt1)),
+            egui::TextureOptions::NEAREST,
+        );
+        let engine_a_result = shared.gpus.a.render_debug(&shared.gpus.vram_banks);
+        let engine_b_result = shared.gpus.b.render_debug(&shared.gpus.vram_banks);
 
         let mut top_screen =
             egui::Image::from_texture(egui::load::SizedTexture::from_handle(&top_texture))
@@ -3,7 +3,7 @@
 use crate::{
     nds::{
         arm::{self, instructions, models::Disassembly, ArmBool, ArmInternalRW, ArmTrait},
-        bus, dma, logger, shared, CycleState, Emulator,
+        bus, dma, logger, shared, Breakpoints, CycleState, Emulator,
     },
     ui::{NitrousUI, NitrousWindow},
 };
@@ -13,6 +13,16 @@ use super::{
     ArmDisassemblerWindow,
 };
 
+// the ARM9's window first, then the ARM7's
+impl Breakpoints for (&mut ArmDisassemblerWindow, &mut ArmDisassemblerWindow) {
+    fn check_breakpoints<const ARM_BOOL: bool>(&mut self, emulator: &mut Emulator) -> bool {
+        match ARM_BOOL {
+            ArmBool::ARM9 => self.0.check_breakpoints::<ARM_BOOL>(emulator),
+            ArmBool::ARM7 => self.1.check_breakpoints::<ARM_BOOL>(emulator),
+        }
+    }
+}
+
 impl ArmDisassemblerWindow {
     pub fn check_breakpoints<const ARM_BOOL: bool>(&mut self, emulator: &mut Emulator) -> bool {
         let pc = match ARM_BOOL {
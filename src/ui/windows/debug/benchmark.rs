@@ -27,7 +27,7 @@ impl BenchmarkWindow {
         &mut self,
         ctx: &egui::Context,
         emulator: &mut Emulator,
-        disassembler_windows: (&mut ArmDisassemblerWindow, &mut ArmDisassemblerWindow),
+        mut disassembler_windows: (&mut ArmDisassemblerWindow, &mut ArmDisassemblerWindow),
     ) {
         egui::Window::new_nitrous("Benchmark", ctx)
             .open(&mut self.open)
@@ -51,7 +51,7 @@ impl BenchmarkWindow {
                     if ui.button("Run").clicked() {
                         let start_time = Instant::now();
                         emulator.start();
-                        emulator.run_for(self.cycles_to_run, 0, disassembler_windows);
+                        emulator.run_for(self.cycles_to_run, 0, &mut disassembler_windows);
                         emulator.pause();
                         let end_time = Instant::now();
                         self.last_result = Some(end_time - start_time);
@@ -1,6 +1,9 @@
 use crate::{
     nds::{
-        gpus::gpu2d::{BackgroundResult, BackgroundResults},
+        gpus::{
+            framebuffer::bgr555_to_rgb8,
+            gpu2d::{BackgroundResult, BackgroundResults},
+        },
         Bits, IfElse,
     },
     ui::NitrousWindow,
@@ -12,9 +15,6 @@ pub struct MapViewerWindow {
     pub open: bool,
 }
 
-// TODO: make this global or something stop copy pasting it everywhere
-const COLOUR_MULT: f32 = 255.0 / 31.0;
-
 // TODO: improve or finish this, i threw this together in a few minutes and don't care
 impl MapViewerWindow {
     pub fn show(&mut self, ctx: &egui::Context, bgs: &Option<BackgroundResults>) {
@@ -59,9 +59,7 @@ impl MapViewerWindow {
                 let y = (y + bg_y_offset) % bg_height;
 
                 let pixel = bg[x][y];
-                let r = ((pixel.get_bits(0, 4) as f32) * COLOUR_MULT) as u8;
-                let g = ((pixel.get_bits(5, 9) as f32) * COLOUR_MULT) as u8;
-                let b = ((pixel.get_bits(10, 14) as f32) * COLOUR_MULT) as u8;
+                let [r, g, b] = bgr555_to_rgb8(pixel);
 
                 pixels[i] = egui::Color32::from_rgba_premultiplied(
                     r,
@@ -1,5 +1,5 @@
 use crate::{
-    nds::{Bits, Emulator},
+    nds::{gpus::framebuffer::bgr555_to_rgb8, Emulator},
     ui::NitrousWindow,
 };
 
@@ -9,9 +9,6 @@ pub struct PaletteViewerWindow {
     pub open: bool,
 }
 
-// TODO: make this global or something stop copy pasting it everywhere
-const COLOUR_MULT: f32 = 255.0 / 31.0;
-
 // TODO: improve or finish this, i threw this together in a few minutes and don't care
 impl PaletteViewerWindow {
     pub fn show(&mut self, emulator: &Emulator, ctx: &egui::Context) {
@@ -33,9 +30,7 @@ impl PaletteViewerWindow {
                                 );
                                 let color = u16::from_le_bytes(color_bytes);
 
-                                let r = ((color.get_bits(0, 4) as f32) * COLOUR_MULT) as u8;
-                                let g = ((color.get_bits(5, 9) as f32) * COLOUR_MULT) as u8;
-                                let b = ((color.get_bits(10, 14) as f32) * COLOUR_MULT) as u8;
+                                let [r, g, b] = bgr555_to_rgb8(color);
                                 let size = egui::Vec2::splat(16.0);
                                 row.col(|ui| {
                                     let (rect, _response) =
@@ -1,4 +1,4 @@
-use crate::{nds::Bits, ui::NitrousWindow};
+use crate::{nds::gpus::framebuffer::bgr555_to_rgb8, ui::NitrousWindow};
 
 #[derive(Default, serde::Deserialize, serde::Serialize)]
 #[serde(default)]
@@ -6,9 +6,6 @@ pub struct TileViewerWindow {
     pub open: bool,
 }
 
-// TODO: make this global or something stop copy pasting it everywhere
-const COLOUR_MULT: f32 = 255.0 / 31.0;
-
 // TODO: improve or finish this, i threw this together in a few minutes and don't care
 impl TileViewerWindow {
     pub fn show(&mut self, ctx: &egui::Context, tiles: &(Vec<Vec<u16>>, usize)) {
@@ -46,9 +43,7 @@ impl TileViewerWindow {
                 let y = (y + bg_y_offset) % bg_height;
 
                 let pixel = bg[x][y];
-                let r = ((pixel.get_bits(0, 4) as f32) * COLOUR_MULT) as u8;
-                let g = ((pixel.get_bits(5, 9) as f32) * COLOUR_MULT) as u8;
-                let b = ((pixel.get_bits(10, 14) as f32) * COLOUR_MULT) as u8;
+                let [r, g, b] = bgr555_to_rgb8(pixel);
 
                 pixels[i] = egui::Color32::from_rgba_premultiplied(r, g, b, 255);
             });
@@ -44,14 +44,12 @@ impl MemoryViewerWindow {
                                 let selected = self.memory_viewer_selected == Some(i);
 
                                 let mut is_editing = false;
-                                let byte = if selected
-                                    && self.memory_viewer_selected_pending_value.is_some()
-                                {
+                                let pending_value = self
+                                    .memory_viewer_selected_pending_value
+                                    .filter(|_| selected);
+                                let byte = if let Some(pending_value) = pending_value {
                                     is_editing = true;
-                                    format!(
-                                        "{:01X}.",
-                                        self.memory_viewer_selected_pending_value.unwrap()
-                                    )
+                                    format!("{:01X}.", pending_value)
                                 } else {
                                     format!("{:02X}", *b)
                                 };
@@ -116,37 +114,29 @@ impl MemoryViewerWindow {
                                 key: egui::Key::ArrowUp,
                                 pressed: true,
                                 ..
-                            } => {
-                                if selected >= 16 {
-                                    self.memory_viewer_selected = Some(selected - 16);
-                                }
+                            } if selected >= 16 => {
+                                self.memory_viewer_selected = Some(selected - 16);
                             }
                             egui::Event::Key {
                                 key: egui::Key::ArrowDown,
                                 pressed: true,
                                 ..
-                            } => {
-                                if selected < 0x687FFFF {
-                                    self.memory_viewer_selected = Some(selected + 16);
-                                }
+                            } if selected < 0x687FFFF => {
+                                self.memory_viewer_selected = Some(selected + 16);
                             }
                             egui::Event::Key {
                                 key: egui::Key::ArrowLeft,
                                 pressed: true,
                                 ..
-                            } => {
-                                if selected > 0 {
-                                    self.memory_viewer_selected = Some(selected - 1);
-                                }
+                            } if selected > 0 => {
+                                self.memory_viewer_selected = Some(selected - 1);
                             }
                             egui::Event::Key {
                                 key: egui::Key::ArrowRight,
                                 pressed: true,
                                 ..
-                            } => {
-                                if selected < 0x687FFFF {
-                                    self.memory_viewer_selected = Some(selected + 1);
-                                }
+                            } if selected < 0x687FFFF => {
+                                self.memory_viewer_selected = Some(selected + 1);
                             }
                             egui::Event::Key {
                                 key: egui::Key::Backspace,
@@ -163,9 +153,7 @@ impl MemoryViewerWindow {
                                 pressed: true,
                                 ..
                             } => {
-                                if self.memory_viewer_selected_pending_value.is_some() {
-                                    self.memory_viewer_selected_pending_value = None;
-                                }
+                                self.memory_viewer_selected_pending_value = None;
                             }
                             egui::Event::Paste(contents) => {
                                 let mut chars = contents.chars();
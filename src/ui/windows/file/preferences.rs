@@ -1,8 +1,11 @@
-use std::sync::mpsc::{channel, Receiver, Sender};
+use std::{
+    collections::HashMap,
+    sync::mpsc::{channel, Receiver, Sender},
+};
 
 use crate::{
-    nds::{arm::ArmKind, bus::BusTrait},
-    ui::NitrousWindow,
+    nds::{arm::ArmKind, bus::BusTrait, cart::backup::BackupKind, rtc::RtcSettings, Emulator},
+    ui::{audio::AudioBackend, NitrousWindow},
 };
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -14,6 +17,13 @@ pub struct PreferencesWindow {
     arm9_bios_path: String,
     arm7_bios_path: String,
     firmware_path: String,
+    pub firmware_boot: bool,
+    rtc_offset: i64,                               // in seconds
+    rtc_frozen: bool,                              // for reproducible runs
+    rtc_frozen_time: String,                       // YYYY-MM-DD HH:MM:SS
+    backup_overrides: HashMap<String, BackupKind>, // game code -> save type
+    pub audio_backend: AudioBackend,
+    pub audio_latency: u32, // in milliseconds
 
     #[serde(skip)]
     #[cfg(not(target_arch = "wasm32"))]
@@ -43,6 +53,13 @@ impl Default for PreferencesWindow {
             arm9_bios_path: String::new(),
             arm7_bios_path: String::new(),
             firmware_path: String::new(),
+            firmware_boot: false,
+            rtc_offset: 0,
+            rtc_frozen: false,
+            rtc_frozen_time: "2000-01-01 00:00:00".to_string(),
+            backup_overrides: HashMap::new(),
+            audio_backend: AudioBackend::default(),
+            audio_latency: 60,
 
             load_arm9_bios_channel: channel(),
             load_arm7_bios_channel: channel(),
@@ -52,7 +69,7 @@ impl Default for PreferencesWindow {
 }
 
 impl PreferencesWindow {
-    pub fn show(&mut self, ctx: &egui::Context) {
+    pub fn show(&mut self, emulator: &mut Emulator, ctx: &egui::Context) {
         let mut open = self.open;
         egui::Window::new_nitrous("Preferences", ctx)
             .open(&mut open)
@@ -63,6 +80,12 @@ impl PreferencesWindow {
                         PreferencesPanel::Emulation,
                         "Emulation",
                     );
+                    ui.selectable_value(
+                        &mut self.selected,
+                        PreferencesPanel::Cartridge,
+                        "Cartridge",
+                    );
+                    ui.selectable_value(&mut self.selected, PreferencesPanel::Audio, "Audio");
                 });
 
                 ui.separator();
@@ -71,6 +94,12 @@ impl PreferencesWindow {
                     PreferencesPanel::Emulation => {
                         self.show_emulation_preferences(ui);
                     }
+                    PreferencesPanel::Cartridge => {
+                        self.show_cartridge_preferences(ui, emulator);
+                    }
+                    PreferencesPanel::Audio => {
+                        self.show_audio_preferences(ui);
+                    }
                 }
             });
 
@@ -170,6 +199,115 @@ impl PreferencesWindow {
                 });
             }
         });
+
+        ui.checkbox(&mut self.firmware_boot, "Boot through firmware")
+            .on_hover_text("Requires the BIOS files and firmware. Takes effect on the next reset.");
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label("RTC offset:");
+            ui.add(egui::DragValue::new(&mut self.rtc_offset).suffix(" seconds"));
+        });
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.rtc_frozen, "Freeze RTC at")
+                .on_hover_text("The clock never moves, which makes runs reproducible.");
+            ui.add_enabled(
+                self.rtc_frozen,
+                egui::TextEdit::singleline(&mut self.rtc_frozen_time),
+            );
+        });
+
+        if self.rtc_frozen && self.parse_rtc_frozen_time().is_none() {
+            ui.label("Expected a time like 2000-01-01 00:00:00");
+        }
+    }
+
+    fn parse_rtc_frozen_time(&self) -> Option<chrono::NaiveDateTime> {
+        chrono::NaiveDateTime::parse_from_str(&self.rtc_frozen_time, RTC_TIME_FORMAT).ok()
+    }
+
+    pub fn rtc_settings(&self) -> RtcSettings {
+        RtcSettings {
+            offset: self.rtc_offset,
+            frozen: if self.rtc_frozen {
+                self.parse_rtc_frozen_time()
+            } else {
+                None
+            },
+        }
+    }
+
+    fn show_cartridge_preferences(&mut self, ui: &mut egui::Ui, emulator: &mut Emulator) {
+        let cart = &mut emulator.shared.cart;
+        if !cart.loaded {
+            ui.label("No ROM loaded.");
+            return;
+        }
+
+        let game_code = cart.metadata.game_code.clone();
+        ui.label(format!(
+            "{} ({})",
+            cart.metadata.game_title.trim_end_matches('\0'),
+            game_code
+        ));
+        ui.label(format!("Current save type: {}", cart.backup.kind));
+
+        let current = self.backup_overrides.get(&game_code).copied();
+        let mut selected = current;
+        ui.horizontal(|ui| {
+            ui.label("Save type:");
+            egui::ComboBox::from_id_source("backup_override")
+                .selected_text(match selected {
+                    Some(kind) => kind.to_string(),
+                    None => "Automatic".to_string(),
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut selected, None, "Automatic");
+                    for kind in BackupKind::ALL {
+                        ui.selectable_value(&mut selected, Some(kind), kind.to_string());
+                    }
+                });
+        });
+
+        if selected != current {
+            match selected {
+                Some(kind) => {
+                    self.backup_overrides.insert(game_code, kind);
+                    cart.backup.set_kind(kind);
+                }
+                None => {
+                    self.backup_overrides.remove(&game_code);
+                    cart.detect_backup_kind();
+                }
+            }
+        }
+    }
+
+    fn show_audio_preferences(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Output:");
+            egui::ComboBox::from_id_source("audio_backend")
+                .selected_text(self.audio_backend.to_string())
+                .show_ui(ui, |ui| {
+                    for backend in AudioBackend::ALL {
+                        ui.selectable_value(&mut self.audio_backend, backend, backend.to_string());
+                    }
+                });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Latency:");
+            ui.add(egui::Slider::new(&mut self.audio_latency, 20..=250).suffix(" ms"));
+        });
+    }
+
+    pub fn apply_backup_override(&self, emulator: &mut Emulator) {
+        let cart = &mut emulator.shared.cart;
+        if let Some(kind) = self.backup_overrides.get(&cart.metadata.game_code) {
+            cart.backup.set_kind(*kind);
+        }
     }
 
     pub fn try_load_bios<Bus: BusTrait>(&mut self, bus: &mut Bus) {
@@ -225,9 +363,13 @@ impl PreferencesWindow {
     }
 }
 
+const RTC_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
 #[derive(PartialEq)]
 enum PreferencesPanel {
     Emulation,
+    Cartridge,
+    Audio,
 }
 
 #[cfg(not(target_arch = "wasm32"))]